@@ -0,0 +1,4 @@
+-- Device details shown in the active sessions list
+ALTER TABLE sessions
+    ADD COLUMN IF NOT EXISTS user_agent TEXT,
+    ADD COLUMN IF NOT EXISTS ip TEXT;
@@ -0,0 +1,21 @@
+-- TOTP (RFC 6238) second factor for users and admins. A credential stays pending
+-- until its first code is confirmed.
+CREATE TABLE IF NOT EXISTS totp_credentials (
+    id UUID PRIMARY KEY,
+    user_id UUID UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    admin_id UUID UNIQUE REFERENCES admins(id) ON DELETE CASCADE,
+    secret TEXT NOT NULL,
+    confirmed_at TIMESTAMPTZ,
+    last_used_step BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK ((user_id IS NULL) <> (admin_id IS NULL))
+);
+
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+    id UUID PRIMARY KEY,
+    credential_id UUID NOT NULL REFERENCES totp_credentials(id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    used_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_totp_recovery_credential ON totp_recovery_codes(credential_id) WHERE used_at IS NULL;
@@ -0,0 +1,14 @@
+-- Failed attempt counters for brute-force protection. Kept in Postgres so every worker
+-- and dyno sees the same counts.
+CREATE TABLE IF NOT EXISTS auth_throttles (
+    action TEXT NOT NULL,
+    key TEXT NOT NULL,
+    failures INT NOT NULL DEFAULT 0,
+    locked_until TIMESTAMPTZ,
+    last_failure_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (action, key)
+);
+
+-- Wrong guesses against a user's outstanding OTP codes
+ALTER TABLE user_otp_codes
+    ADD COLUMN IF NOT EXISTS attempts INT NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- OTP codes are now stored as keyed hashes. Codes issued before this migration were
+-- plaintext; burn them rather than hashing them in place (they expire within minutes anyway).
+ALTER TABLE user_otp_codes RENAME COLUMN code TO code_hash;
+
+UPDATE user_otp_codes SET used_at = now() WHERE used_at IS NULL;
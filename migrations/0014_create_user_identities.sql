@@ -0,0 +1,20 @@
+-- External sign-in identities (Google, or any OIDC provider). A user may link several,
+-- at most one per provider.
+CREATE TABLE IF NOT EXISTS user_identities (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    email TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMPTZ,
+    UNIQUE (provider, subject),
+    UNIQUE (user_id, provider)
+);
+
+-- Carry over existing Google links
+INSERT INTO user_identities (id, user_id, provider, subject, email)
+SELECT gen_random_uuid(), id, 'google', google_sub, email FROM users WHERE google_sub IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE users DROP COLUMN IF EXISTS google_sub;
@@ -0,0 +1,3 @@
+-- Address an email-change code was sent to; the swap only happens once that code is confirmed
+ALTER TABLE user_otp_codes
+    ADD COLUMN IF NOT EXISTS target_email TEXT;
@@ -0,0 +1,5 @@
+-- Self-service profile preferences
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS preferred_currency TEXT NOT NULL DEFAULT 'IDR',
+    ADD COLUMN IF NOT EXISTS locale TEXT NOT NULL DEFAULT 'id-ID',
+    ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'Asia/Jakarta';
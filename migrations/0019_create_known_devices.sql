@@ -0,0 +1,12 @@
+-- Devices (user agent fingerprint) and networks each user has signed in from, so a login
+-- from somewhere new can be reported to the account owner.
+CREATE TABLE IF NOT EXISTS known_devices (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    fingerprint TEXT NOT NULL,
+    ip_range TEXT NOT NULL,
+    user_agent TEXT,
+    first_seen_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, fingerprint, ip_range)
+);
@@ -0,0 +1,7 @@
+-- Keyset pagination walks (sort column, id) per user; one index per sort option
+CREATE INDEX IF NOT EXISTS idx_transactions_user_date_id ON transactions(user_id, occurred_on, id);
+CREATE INDEX IF NOT EXISTS idx_transactions_user_amount_id ON transactions(user_id, amount, id);
+CREATE INDEX IF NOT EXISTS idx_transactions_user_created_id ON transactions(user_id, created_at, id);
+
+-- Covered by idx_transactions_user_date_id
+DROP INDEX IF EXISTS idx_transactions_user_date;
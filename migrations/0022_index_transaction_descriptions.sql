@@ -0,0 +1,4 @@
+-- Substring search on descriptions (ILIKE '%...%') via trigrams
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_description_trgm ON transactions USING gin (description gin_trgm_ops);
@@ -0,0 +1,13 @@
+-- Files attached to a transaction, such as a photo of the receipt. Kept in the database so
+-- a deployment needs no separate file storage; the `attachment_max_bytes` setting caps their size.
+CREATE TABLE IF NOT EXISTS transaction_attachments (
+    id UUID PRIMARY KEY,
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes INT NOT NULL,
+    content BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_attachments_transaction ON transaction_attachments(transaction_id);
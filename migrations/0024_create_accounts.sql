@@ -0,0 +1,20 @@
+-- Where money is held: cash, bank accounts, e-wallets, ...
+CREATE TABLE IF NOT EXISTS accounts (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('cash','bank','e_wallet','credit_card','savings','investment','other')),
+    currency TEXT NOT NULL,
+    opening_balance NUMERIC(14,2) NOT NULL DEFAULT 0,
+    archived BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_accounts_user ON accounts(user_id);
+
+-- Optional so existing transactions stay valid; an account with transactions cannot be deleted
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS account_id UUID REFERENCES accounts(id) ON DELETE RESTRICT;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_account_date ON transactions(account_id, occurred_on);
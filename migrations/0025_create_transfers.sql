@@ -0,0 +1,30 @@
+-- Money moved between two of the user's accounts. Each transfer is booked as transactions
+-- without a category (so they are neither income nor expense): a negative leg on the source
+-- account and a positive one on the destination, plus an expense for the fee, if any.
+CREATE TABLE IF NOT EXISTS transfers (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    from_account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE RESTRICT,
+    to_account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE RESTRICT,
+    amount NUMERIC(14,2) NOT NULL CHECK (amount > 0),
+    fee NUMERIC(14,2) NOT NULL DEFAULT 0 CHECK (fee >= 0),
+    fee_category_id UUID REFERENCES categories(id) ON DELETE RESTRICT,
+    occurred_on DATE NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (from_account_id <> to_account_id),
+    CHECK (fee = 0 OR fee_category_id IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transfers_user_date ON transfers(user_id, occurred_on);
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS transfer_id UUID REFERENCES transfers(id) ON DELETE CASCADE;
+
+ALTER TABLE transactions ALTER COLUMN category_id DROP NOT NULL;
+
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_category_or_transfer;
+ALTER TABLE transactions
+    ADD CONSTRAINT transactions_category_or_transfer CHECK (category_id IS NOT NULL OR transfer_id IS NOT NULL);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_transfer ON transactions(transfer_id);
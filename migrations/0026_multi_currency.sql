@@ -0,0 +1,34 @@
+-- Every transaction records its currency; existing rows take their account's, else the user's
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS currency TEXT;
+
+UPDATE transactions t
+SET currency = COALESCE(
+    (SELECT a.currency FROM accounts a WHERE a.id = t.account_id),
+    (SELECT u.preferred_currency FROM users u WHERE u.id = t.user_id)
+)
+WHERE currency IS NULL;
+
+ALTER TABLE transactions ALTER COLUMN currency SET NOT NULL;
+
+-- Decimals depend on the currency (0 for IDR, 3 for KWD). The application stores amounts
+-- with exactly their currency's decimals, so the columns cannot fix a scale the way
+-- NUMERIC(20,3) would (50000 IDR would read back as 50000.000); the domain keeps the same
+-- bound instead: at most 3 decimals and 17 integer digits.
+CREATE DOMAIN money_amount AS NUMERIC
+    CHECK (VALUE = round(VALUE, 3) AND abs(VALUE) < 1e17);
+
+ALTER TABLE transactions ALTER COLUMN amount TYPE money_amount;
+ALTER TABLE accounts ALTER COLUMN opening_balance TYPE money_amount;
+ALTER TABLE transfers ALTER COLUMN amount TYPE money_amount, ALTER COLUMN fee TYPE money_amount;
+
+-- 1 base_currency = rate quote_currency, as published for rate_date
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    base_currency TEXT NOT NULL,
+    quote_currency TEXT NOT NULL,
+    rate_date DATE NOT NULL,
+    rate NUMERIC(24,10) NOT NULL CHECK (rate > 0),
+    source TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (base_currency, quote_currency, rate_date),
+    CHECK (base_currency <> quote_currency)
+);
@@ -0,0 +1,45 @@
+-- Templates that book a transaction on a schedule, e.g. rent on the 1st of every month or
+-- a salary on the last Friday. A background job books each due occurrence once.
+CREATE TABLE IF NOT EXISTS recurring_transactions (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    category_id UUID NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+    account_id UUID REFERENCES accounts(id) ON DELETE RESTRICT,
+    amount money_amount NOT NULL,
+    currency TEXT NOT NULL,
+    description TEXT,
+    frequency TEXT NOT NULL CHECK (frequency IN ('daily','weekly','monthly','yearly')),
+    every INT NOT NULL DEFAULT 1 CHECK (every BETWEEN 1 AND 366),
+    -- Monthly/yearly on the nth (1-4, or -1 for the last) given weekday instead of a day of the month
+    weekday TEXT,
+    week_of_month SMALLINT CHECK (week_of_month IN (-1, 1, 2, 3, 4)),
+    -- First date of the current schedule; moved forward when the schedule is edited
+    start_date DATE NOT NULL,
+    end_date DATE,
+    max_occurrences INT CHECK (max_occurrences > 0),
+    -- Occurrences used up so far, booked or skipped
+    occurrences_generated INT NOT NULL DEFAULT 0,
+    last_occurrence DATE,
+    -- NULL once the series has ended
+    next_occurrence DATE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK ((weekday IS NULL) = (week_of_month IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_recurring_user ON recurring_transactions(user_id);
+CREATE INDEX IF NOT EXISTS idx_recurring_next ON recurring_transactions(next_occurrence) WHERE next_occurrence IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS recurring_skips (
+    recurring_id UUID NOT NULL REFERENCES recurring_transactions(id) ON DELETE CASCADE,
+    occurrence_date DATE NOT NULL,
+    PRIMARY KEY (recurring_id, occurrence_date)
+);
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS recurring_id UUID REFERENCES recurring_transactions(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS occurrence_date DATE;
+
+-- Booking an occurrence twice is impossible, whichever server instance gets to it
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_recurring_occurrence_key;
+ALTER TABLE transactions
+    ADD CONSTRAINT transactions_recurring_occurrence_key UNIQUE (recurring_id, occurrence_date);
@@ -0,0 +1,15 @@
+-- Category lines of a transaction spread over several categories (one receipt covering
+-- groceries and household goods). The lines add up to the transaction's amount, and the
+-- transaction itself carries the first line's category. A category used by any line cannot
+-- be deleted (no cascade here).
+CREATE TABLE IF NOT EXISTS transaction_splits (
+    id UUID PRIMARY KEY,
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    category_id UUID NOT NULL REFERENCES categories(id),
+    amount money_amount NOT NULL,
+    description TEXT,
+    position SMALLINT NOT NULL,
+    UNIQUE (transaction_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_splits_category ON transaction_splits(category_id);
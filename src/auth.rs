@@ -1,50 +1,144 @@
 use crate::config::AppConfig;
 use crate::errors::AppError;
+use crate::repositories::session_repo;
+use crate::services::api_token_service;
 use actix_web::{dev::Payload, http::header, FromRequest, HttpRequest};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use chrono::{Duration, Utc};
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::Validation;
 use password_hash::{PasswordHash, PasswordVerifier, SaltString, PasswordHasher};
 use password_hash::rand_core::OsRng;
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
-use std::future::{ready, Ready};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::pin::Pin;
 use uuid::Uuid;
 
+/// Which kind of account a token or session belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Admin,
+}
+
+impl Role {
+    /// Value of the `aud` claim for tokens issued to this kind of account.
+    pub fn audience(self) -> &'static str {
+        match self {
+            Role::User => "finance-user",
+            Role::Admin => "finance-admin",
+        }
+    }
+
+    /// Splits a subject id into the (user_id, admin_id) column pair used by tables that
+    /// hold rows for both kinds of account.
+    pub fn owner_ids(self, subject_id: Uuid) -> (Option<Uuid>, Option<Uuid>) {
+        match self {
+            Role::User => (Some(subject_id), None),
+            Role::Admin => (None, Some(subject_id)),
+        }
+    }
+
+    /// Audience of the 2FA challenge token handed out by a password-verified login.
+    pub fn mfa_audience(self) -> &'static str {
+        match self {
+            Role::User => "finance-user-mfa",
+            Role::Admin => "finance-admin-mfa",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: Uuid,
+    /// Session the access token was issued for; revoking it invalidates the token.
+    pub sid: Uuid,
+    pub aud: String,
+    pub iss: String,
     pub exp: usize,
     pub iat: usize,
 }
 
-pub fn create_jwt(user_id: Uuid, cfg: &AppConfig) -> Result<String, AppError> {
+pub fn create_jwt(subject_id: Uuid, session_id: Uuid, role: Role, cfg: &AppConfig) -> Result<String, AppError> {
     let now = Utc::now();
-    let exp = now + Duration::hours(cfg.jwt_exp_hours as i64);
+    let exp = now + Duration::minutes(cfg.jwt_access_exp_minutes as i64);
     let claims = Claims {
-        sub: user_id,
+        sub: subject_id,
+        sid: session_id,
+        aud: role.audience().to_string(),
+        iss: cfg.jwt_issuer.clone(),
         iat: now.timestamp() as usize,
         exp: exp.timestamp() as usize,
     };
-    let token = jsonwebtoken::encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(cfg.jwt_secret.as_bytes()),
-    )?;
-    Ok(token)
+    cfg.jwt_keys.encode(&claims)
+}
+
+// Claim checks shared by access and purpose tokens; the algorithm comes from the key.
+fn validation(audience: &str, cfg: &AppConfig) -> Validation {
+    let mut validation = Validation::default();
+    validation.set_audience(&[audience]);
+    validation.set_issuer(&[cfg.jwt_issuer.as_str()]);
+    validation.set_required_spec_claims(&["exp", "sub", "aud", "iss"]);
+    validation
+}
+
+/// Verifies signature, expiry, issuer and that the token was issued for `role`.
+pub fn verify_jwt(token: &str, role: Role, cfg: &AppConfig) -> Result<Claims, AppError> {
+    cfg.jwt_keys.decode(token, validation(role.audience(), cfg))
+}
+
+/// Short-lived single-purpose token (e.g. a 2FA challenge). Its audience never matches an
+/// access token audience, so it cannot be used as a bearer token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PurposeClaims {
+    pub sub: Uuid,
+    pub aud: String,
+    pub iss: String,
+    pub exp: usize,
+    pub iat: usize,
+}
+
+pub fn create_purpose_jwt(subject_id: Uuid, audience: &str, ttl: Duration, cfg: &AppConfig) -> Result<String, AppError> {
+    let now = Utc::now();
+    let claims = PurposeClaims {
+        sub: subject_id,
+        aud: audience.to_string(),
+        iss: cfg.jwt_issuer.clone(),
+        iat: now.timestamp() as usize,
+        exp: (now + ttl).timestamp() as usize,
+    };
+    cfg.jwt_keys.encode(&claims)
+}
+
+pub fn verify_purpose_jwt(token: &str, audience: &str, cfg: &AppConfig) -> Result<PurposeClaims, AppError> {
+    cfg.jwt_keys.decode(token, validation(audience, cfg))
+}
+
+/// Random URL-safe secret for opaque tokens (refresh tokens and the like).
+pub fn generate_token_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Opaque tokens are only ever stored as their SHA-256 digest.
+pub fn hash_token(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
 }
 
-pub fn verify_jwt(token: &str, cfg: &AppConfig) -> Result<Claims, AppError> {
-    let data = jsonwebtoken::decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(cfg.jwt_secret.as_bytes()),
-        &Validation::default(),
-    )?;
-    Ok(data.claims)
+// Argon2id with the configured costs; hashes made with other parameters still verify,
+// because each PHC string carries its own.
+fn argon2(cfg: &AppConfig) -> Result<argon2::Argon2<'static>, AppError> {
+    let params = argon2::Params::new(cfg.argon2_memory_kib, cfg.argon2_iterations, cfg.argon2_parallelism, None)
+        .map_err(|_| AppError::Internal)?;
+    Ok(argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params))
 }
 
-pub fn hash_password(plain: &str) -> Result<String, AppError> {
+pub fn hash_password(plain: &str, cfg: &AppConfig) -> Result<String, AppError> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = argon2::Argon2::default();
-    let hash = argon2
+    let hash = argon2(cfg)?
         .hash_password(plain.as_bytes(), &salt)
         .map_err(|_| AppError::Internal)?
         .to_string();
@@ -52,40 +146,106 @@ pub fn hash_password(plain: &str) -> Result<String, AppError> {
 }
 
 pub fn verify_password(plain: &str, hash: &str) -> Result<bool, AppError> {
+    // Accounts created through Google/OIDC have no password at all
+    if hash.is_empty() {
+        return Ok(false);
+    }
     let parsed = PasswordHash::new(hash).map_err(|_| AppError::Internal)?;
     Ok(argon2::Argon2::default()
         .verify_password(plain.as_bytes(), &parsed)
         .is_ok())
 }
 
+/// True when `hash` was not made with Argon2id and the currently configured costs, so it
+/// should be replaced the next time the plaintext is at hand.
+pub fn password_needs_rehash(hash: &str, cfg: &AppConfig) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else { return false };
+    if parsed.algorithm != argon2::Algorithm::Argon2id.ident() || parsed.version != Some(argon2::Version::V0x13.into()) {
+        return true;
+    }
+    match argon2::Params::try_from(&parsed) {
+        Ok(p) => p.m_cost() != cfg.argon2_memory_kib || p.t_cost() != cfg.argon2_iterations || p.p_cost() != cfg.argon2_parallelism,
+        Err(_) => true,
+    }
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .map(|s| s.to_string())
+}
+
+const SESSION_TOUCH_INTERVAL_MINUTES: i64 = 5;
+
+type AuthFuture<T> = Pin<Box<dyn Future<Output = Result<T, AppError>>>>;
+
+// Validates the bearer JWT for the expected role and checks that its session is still
+// active and owned by the token's subject.
+fn authenticate(req: &HttpRequest, role: Role) -> AuthFuture<Claims> {
+    let cfg = req.app_data::<actix_web::web::Data<AppConfig>>().cloned();
+    let pool = req
+        .app_data::<actix_web::web::Data<crate::db::DbPool>>()
+        .cloned();
+    let token = bearer_token(req);
+
+    Box::pin(async move {
+        let cfg = cfg.ok_or(AppError::Internal)?;
+        let pool = pool.ok_or(AppError::Internal)?;
+        let token = token.ok_or(AppError::Unauthorized)?;
+        let claims = verify_jwt(&token, role, &cfg)?;
+        let session = session_repo::find_active(pool.get_ref(), claims.sid)
+            .await?
+            .ok_or(AppError::Unauthorized)?;
+        let owner = match role {
+            Role::User => session.user_id,
+            Role::Admin => session.admin_id,
+        };
+        if owner != Some(claims.sub) {
+            return Err(AppError::Unauthorized);
+        }
+        // Keep "last seen" roughly current without writing on every request
+        if Utc::now() - session.last_seen_at > Duration::minutes(SESSION_TOUCH_INTERVAL_MINUTES) {
+            if let Err(e) = session_repo::touch(pool.get_ref(), claims.sid).await {
+                log::warn!("Failed to update last_seen_at for session {}: {}", claims.sid, e);
+            }
+        }
+        Ok(claims)
+    })
+}
+
+// Personal access tokens only reach the resources their scopes cover.
+fn authenticate_api_token(req: &HttpRequest) -> AuthFuture<Uuid> {
+    let pool = req
+        .app_data::<actix_web::web::Data<crate::db::DbPool>>()
+        .cloned();
+    let token = bearer_token(req);
+    let method = req.method().clone();
+    let path = req.path().to_string();
+
+    Box::pin(async move {
+        let pool = pool.ok_or(AppError::Internal)?;
+        let token = token.ok_or(AppError::Unauthorized)?;
+        api_token_service::authenticate(pool.get_ref(), &token, &method, &path).await
+    })
+}
+
+/// The signed-in user, from a login JWT or a personal access token.
 #[derive(Debug, Clone, Copy)]
 pub struct AuthUser(pub Uuid);
 
 impl FromRequest for AuthUser {
     type Error = AppError;
-    type Future = Ready<Result<Self, Self::Error>>;
+    type Future = AuthFuture<Self>;
 
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let cfg = match req.app_data::<actix_web::web::Data<AppConfig>>() {
-            Some(c) => c.clone(),
-            None => return ready(Err(AppError::Internal)),
-        };
-
-        let auth = req
-            .headers()
-            .get(header::AUTHORIZATION)
-            .and_then(|h| h.to_str().ok())
-            .unwrap_or("")
-            .to_string();
-
-        if let Some(token) = auth.strip_prefix("Bearer ") {
-            match verify_jwt(token, &cfg) {
-                Ok(claims) => ready(Ok(AuthUser(claims.sub))),
-                Err(_) => ready(Err(AppError::Unauthorized)),
-            }
-        } else {
-            ready(Err(AppError::Unauthorized))
+        if bearer_token(req).is_some_and(|t| t.starts_with(api_token_service::TOKEN_PREFIX)) {
+            let fut = authenticate_api_token(req);
+            return Box::pin(async move { Ok(AuthUser(fut.await?)) });
         }
+        let fut = authenticate(req, Role::User);
+        Box::pin(async move { Ok(AuthUser(fut.await?.sub)) })
     }
 }
 
@@ -94,36 +254,103 @@ pub struct AdminUser(pub Uuid);
 
 impl FromRequest for AdminUser {
     type Error = AppError;
-    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self, Self::Error>>>>;
+    type Future = AuthFuture<Self>;
 
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let cfg = req.app_data::<actix_web::web::Data<AppConfig>>().cloned();
-        let pool = req
-            .app_data::<actix_web::web::Data<crate::db::DbPool>>()
-            .cloned();
-        let auth = req
-            .headers()
-            .get(header::AUTHORIZATION)
-            .and_then(|h| h.to_str().ok())
-            .map(|s| s.to_string());
+        let fut = authenticate(req, Role::Admin);
+        Box::pin(async move { Ok(AdminUser(fut.await?.sub)) })
+    }
+}
+
+/// Like `AuthUser`, but also exposes the session behind the token (for logout etc.).
+/// Only login JWTs carry a session, so personal access tokens are rejected.
+#[derive(Debug, Clone, Copy)]
+pub struct UserSession {
+    pub user_id: Uuid,
+    pub session_id: Uuid,
+}
 
+impl FromRequest for UserSession {
+    type Error = AppError;
+    type Future = AuthFuture<Self>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let fut = authenticate(req, Role::User);
         Box::pin(async move {
-            let cfg = cfg.ok_or(AppError::Internal)?;
-            let pool = pool.ok_or(AppError::Internal)?;
-            let auth = auth.ok_or(AppError::Unauthorized)?;
-            let token = auth.strip_prefix("Bearer ").ok_or(AppError::Unauthorized)?;
-            let claims = verify_jwt(token, &cfg)?;
-            let count: (i64,) = sqlx::query_as(
-                "SELECT COUNT(1) FROM admins WHERE id = $1",
-            )
-            .bind(claims.sub)
-            .fetch_one(pool.get_ref())
-            .await
-            .map_err(|_| AppError::Unauthorized)?;
-            if count.0 == 0 {
-                return Err(AppError::Forbidden);
-            }
-            Ok(AdminUser(claims.sub))
+            let claims = fut.await?;
+            Ok(UserSession { user_id: claims.sub, session_id: claims.sid })
         })
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+pub struct AdminSession {
+    pub admin_id: Uuid,
+    pub session_id: Uuid,
+}
+
+impl FromRequest for AdminSession {
+    type Error = AppError;
+    type Future = AuthFuture<Self>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let fut = authenticate(req, Role::Admin);
+        Box::pin(async move {
+            let claims = fut.await?;
+            Ok(AdminSession { admin_id: claims.sub, session_id: claims.sid })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test::TestRequest, web};
+
+    #[test]
+    fn access_tokens_only_verify_for_their_own_role() {
+        let cfg = AppConfig::for_tests();
+        let (subject, session) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let user_token = create_jwt(subject, session, Role::User, &cfg).unwrap();
+        let claims = verify_jwt(&user_token, Role::User, &cfg).unwrap();
+        assert_eq!((claims.sub, claims.sid), (subject, session));
+        assert!(matches!(verify_jwt(&user_token, Role::Admin, &cfg), Err(AppError::Unauthorized)));
+
+        let admin_token = create_jwt(subject, session, Role::Admin, &cfg).unwrap();
+        assert!(verify_jwt(&admin_token, Role::Admin, &cfg).is_ok());
+        assert!(matches!(verify_jwt(&admin_token, Role::User, &cfg), Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn tokens_from_another_issuer_are_rejected() {
+        let cfg = AppConfig::for_tests();
+        let other = AppConfig { jwt_issuer: "someone-else".to_string(), ..AppConfig::for_tests() };
+        let token = create_jwt(Uuid::new_v4(), Uuid::new_v4(), Role::User, &other).unwrap();
+        assert!(matches!(verify_jwt(&token, Role::User, &cfg), Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn purpose_tokens_are_not_access_tokens() {
+        let cfg = AppConfig::for_tests();
+        let subject = Uuid::new_v4();
+        let challenge = create_purpose_jwt(subject, Role::User.mfa_audience(), Duration::minutes(5), &cfg).unwrap();
+        assert_eq!(verify_purpose_jwt(&challenge, Role::User.mfa_audience(), &cfg).unwrap().sub, subject);
+        assert!(matches!(verify_jwt(&challenge, Role::User, &cfg), Err(AppError::Unauthorized)));
+        assert!(matches!(verify_jwt(&challenge, Role::Admin, &cfg), Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn admin_routes_refuse_user_tokens() {
+        let cfg = AppConfig::for_tests();
+        let token = create_jwt(Uuid::new_v4(), Uuid::new_v4(), Role::User, &cfg).unwrap();
+        // Rejected on the audience alone, before any session lookup
+        let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .app_data(web::Data::new(cfg))
+            .app_data(web::Data::new(pool))
+            .to_http_request();
+        assert!(matches!(AdminUser::extract(&req).await, Err(AppError::Unauthorized)));
+    }
+}
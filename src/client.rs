@@ -0,0 +1,49 @@
+use crate::config::AppConfig;
+use crate::errors::AppError;
+use actix_web::{dev::Payload, http::header, FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// Where a request came from, recorded on sessions and security-relevant events.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+fn client_ip(req: &HttpRequest, trust_proxy: bool) -> Option<String> {
+    if trust_proxy {
+        // Heroku's router appends the address it saw to X-Forwarded-For, so the last entry
+        // is the only one the client cannot spoof.
+        let forwarded = req
+            .headers()
+            .get("x-forwarded-for")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|v| v.rsplit(',').next())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+    req.peer_addr().map(|addr| addr.ip().to_string())
+}
+
+impl FromRequest for ClientInfo {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let trust_proxy = req
+            .app_data::<actix_web::web::Data<AppConfig>>()
+            .map(|c| c.trust_proxy)
+            .unwrap_or(false);
+        let user_agent = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.chars().take(MAX_USER_AGENT_LEN).collect::<String>());
+        ready(Ok(ClientInfo { ip: client_ip(req, trust_proxy), user_agent }))
+    }
+}
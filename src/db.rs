@@ -28,3 +28,26 @@ pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::migrate::MigrateE
         Err(e) => Err(e),
     }
 }
+
+/// Migrated pool for tests that need Postgres, from `TEST_DATABASE_URL`. Without it the
+/// test is skipped.
+#[cfg(test)]
+pub(crate) async fn test_pool() -> Option<PgPool> {
+    let Ok(url) = env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL not set; skipping database test");
+        return None;
+    };
+    let pool = init_pool(&url).await.expect("cannot connect to TEST_DATABASE_URL");
+    run_migrations(&pool).await.expect("migrations failed");
+    Some(pool)
+}
+
+/// A fresh local account for a database test.
+#[cfg(test)]
+pub(crate) async fn test_user(pool: &PgPool) -> uuid::Uuid {
+    let id = uuid::Uuid::new_v4();
+    crate::repositories::user_repo::insert_local(pool, id, "Test User", &format!("test-{}@example.com", id), "")
+        .await
+        .expect("cannot insert test user");
+    id
+}
@@ -0,0 +1,32 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeEmailRequest {
+    pub new_email: String,
+    /// Required when the account has a password.
+    pub current_password: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmEmailChange {
+    pub code: String,
+}
+
+/// Fields left out are unchanged.
+#[derive(Debug, Deserialize)]
+pub struct UpdateProfile {
+    pub name: Option<String>,
+    /// ISO 4217 code, e.g. "IDR"
+    pub preferred_currency: Option<String>,
+    /// BCP 47 tag, e.g. "id-ID"
+    pub locale: Option<String>,
+    /// IANA zone, e.g. "Asia/Jakarta"
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    /// Required unless the account has no password yet (Google/OIDC-only sign-up).
+    pub current_password: Option<String>,
+    pub new_password: String,
+}
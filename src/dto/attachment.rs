@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    /// Name to download the file under; defaults to `attachment`.
+    pub filename: Option<String>,
+}
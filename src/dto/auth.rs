@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
@@ -14,11 +15,51 @@ pub struct LoginRequest {
 }
 
 #[derive(Debug, Serialize)]
-pub struct AuthResponse<T> {
+pub struct TokenPair {
+    /// Short-lived access token (JWT) for the `Authorization: Bearer` header.
     pub token: String,
+    pub refresh_token: String,
+    /// Access token lifetime in seconds.
+    pub expires_in: u64,
+    #[serde(skip)]
+    pub session_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthResponse<T> {
+    #[serde(flatten)]
+    pub tokens: TokenPair,
     pub user: T,
 }
 
+/// Result of a first-factor login: either tokens, or a challenge to complete with a
+/// second factor at `/auth/2fa/verify`.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginOutcome<T> {
+    Authenticated(T),
+    MfaRequired(MfaChallenge),
+}
+
+#[derive(Debug, Serialize)]
+pub struct MfaChallenge {
+    pub mfa_required: bool,
+    pub challenge_token: String,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MfaVerifyRequest {
+    pub challenge_token: String,
+    /// Current authenticator code or an unused recovery code.
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RequestOtpPayload {
     pub email: String,
@@ -35,6 +76,29 @@ pub struct GoogleLoginRequest {
     pub id_token: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct MagicLinkRequest {
+    pub token: String,
+}
+
+/// Token from a new-login alert email.
+#[derive(Debug, Deserialize)]
+pub struct RevokeSessionRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcLoginRequest {
+    pub id_token: String,
+}
+
+/// Links `provider` ("google" or a configured OIDC provider) to the signed-in account.
+#[derive(Debug, Deserialize)]
+pub struct LinkIdentityRequest {
+    pub provider: String,
+    pub id_token: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ResetPasswordPayload {
     pub email: String,
@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct EventPageQuery {
+    pub limit: Option<i64>,
+    /// Only events older than this; pass the last `created_at` of the previous page.
+    pub before: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminEventQuery {
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+    pub event_type: Option<String>,
+    pub success: Option<bool>,
+    pub ip: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,18 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::models::exchange_rate::NewExchangeRate;
+
+#[derive(Debug, Deserialize)]
+pub struct RateQuery {
+    /// Rates with this currency on either side.
+    pub currency: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadRates {
+    pub rates: Vec<NewExchangeRate>,
+}
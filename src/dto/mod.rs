@@ -1,3 +1,14 @@
 pub mod auth;
 pub mod category;
 pub mod transaction;
+pub mod attachment;
+pub mod money_account;
+pub mod transfer;
+pub mod recurring;
+pub mod exchange_rate;
+pub mod page;
+pub mod two_factor;
+pub mod api_token;
+pub mod account;
+pub mod auth_event;
+pub mod passkey;
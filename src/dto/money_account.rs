@@ -0,0 +1,31 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateMoneyAccount {
+    pub name: String,
+    pub kind: String,
+    /// Defaults to the user's preferred currency.
+    pub currency: Option<String>,
+    pub opening_balance: Option<rust_decimal::Decimal>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateMoneyAccount {
+    pub name: Option<String>,
+    pub kind: Option<String>,
+    pub opening_balance: Option<rust_decimal::Decimal>,
+    pub archived: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountListQuery {
+    #[serde(default)]
+    pub include_archived: bool,
+    pub as_of: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceQuery {
+    pub as_of: Option<NaiveDate>,
+}
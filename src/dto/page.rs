@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+/// One page of a cursor-paginated listing.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass as `cursor` to get the next page; `null` on the last page.
+    pub next_cursor: Option<String>,
+    /// Rows matching the filters, across all pages.
+    pub total: i64,
+}
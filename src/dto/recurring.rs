@@ -0,0 +1,56 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRecurring {
+    pub category_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub amount: rust_decimal::Decimal,
+    pub currency: Option<String>,
+    pub description: Option<String>,
+    /// `daily`, `weekly`, `monthly` or `yearly`.
+    pub frequency: String,
+    /// Repeat every n periods (default 1).
+    pub every: Option<i32>,
+    /// With `week_of_month`, monthly/yearly series fall on e.g. the 2nd `tuesday`.
+    pub weekday: Option<String>,
+    /// 1-4, or -1 for the last such weekday of the month.
+    pub week_of_month: Option<i16>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub max_occurrences: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRecurring {
+    pub category_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub amount: Option<rust_decimal::Decimal>,
+    pub currency: Option<String>,
+    pub description: Option<String>,
+    pub frequency: Option<String>,
+    pub every: Option<i32>,
+    pub weekday: Option<String>,
+    pub week_of_month: Option<i16>,
+    /// First date of the changed schedule; defaults to the next occurrence.
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub max_occurrences: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OccurrenceQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Occurrence {
+    pub date: NaiveDate,
+    pub skipped: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SkipOccurrence {
+    pub occurrence_date: NaiveDate,
+}
@@ -1,27 +1,79 @@
 use chrono::NaiveDate;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
-pub struct CreateTransaction {
+pub struct SplitLine {
     pub category_id: Uuid,
     pub amount: rust_decimal::Decimal,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTransaction {
+    /// Required unless `splits` is given, in which case it is the first line's category.
+    pub category_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub amount: rust_decimal::Decimal,
+    /// Defaults to the account's currency, or the user's preferred one without an account.
+    pub currency: Option<String>,
     pub occurred_on: NaiveDate,
     pub description: Option<String>,
+    /// Spreads `amount` over several categories; the lines must add up to it.
+    pub splits: Option<Vec<SplitLine>>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateTransaction {
     pub category_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
     pub amount: Option<rust_decimal::Decimal>,
+    pub currency: Option<String>,
     pub occurred_on: Option<NaiveDate>,
     pub description: Option<String>,
+    /// Replaces the category lines; an empty list turns the split back into a single category.
+    pub splits: Option<Vec<SplitLine>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxnSort {
+    #[default]
+    Date,
+    Amount,
+    CreatedAt,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct TxnQuery {
     pub category_id: Option<Uuid>,
+    /// Comma-separated category ids; matches any of them (and `category_id`, if given).
+    pub category_ids: Option<String>,
+    pub account_id: Option<Uuid>,
     pub start_date: Option<NaiveDate>,
     pub end_date: Option<NaiveDate>,
+    pub min_amount: Option<rust_decimal::Decimal>,
+    pub max_amount: Option<rust_decimal::Decimal>,
+    /// `income` or `expense`, by category.
+    pub kind: Option<String>,
+    /// Case-insensitive substring of the description.
+    pub q: Option<String>,
+    pub has_description: Option<bool>,
+    pub has_attachment: Option<bool>,
+    pub limit: Option<i64>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub sort: TxnSort,
+    #[serde(default)]
+    pub order: SortOrder,
 }
 
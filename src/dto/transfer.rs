@@ -0,0 +1,35 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTransfer {
+    pub from_account_id: Uuid,
+    pub to_account_id: Uuid,
+    pub amount: rust_decimal::Decimal,
+    /// Charged to the source account on top of `amount`.
+    pub fee: Option<rust_decimal::Decimal>,
+    /// Expense category the fee is booked to; required with a fee.
+    pub fee_category_id: Option<Uuid>,
+    pub occurred_on: NaiveDate,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTransfer {
+    pub from_account_id: Option<Uuid>,
+    pub to_account_id: Option<Uuid>,
+    pub amount: Option<rust_decimal::Decimal>,
+    pub fee: Option<rust_decimal::Decimal>,
+    pub fee_category_id: Option<Uuid>,
+    pub occurred_on: Option<NaiveDate>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransferQuery {
+    /// Transfers out of or into this account.
+    pub account_id: Option<Uuid>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
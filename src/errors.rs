@@ -1,7 +1,19 @@
-use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use actix_web::{http::{header, StatusCode}, HttpResponse, ResponseError};
 use serde::Serialize;
 use thiserror::Error;
 
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self { field: field.to_string(), message: message.into() }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("Unauthorized")]
@@ -14,8 +26,12 @@ pub enum AppError {
     NotFound(String),
     #[error("Conflict: {0}")]
     Conflict(String),
+    /// Input that parsed but broke a rule, reported per field.
+    #[error("Validation failed")]
+    Validation(Vec<FieldError>),
+    /// Message and number of seconds the client should wait (sent as `Retry-After`).
     #[error("Too many requests: {0}")]
-    TooManyRequests(String),
+    TooManyRequests(String, u64),
     #[error("Database error: {0}")]
     Db(String),
     #[error("Internal server error")]
@@ -38,6 +54,8 @@ impl From<jsonwebtoken::errors::Error> for AppError {
 struct ErrorBody {
     code: &'static str,
     message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<FieldError>,
 }
 
 #[derive(Serialize)]
@@ -46,14 +64,15 @@ struct ErrorResponse {
     error: ErrorBody,
 }
 
-fn error_code(e: &AppError) -> &'static str {
+pub(crate) fn error_code(e: &AppError) -> &'static str {
     match e {
         AppError::Unauthorized => "UNAUTHORIZED",
         AppError::Forbidden => "FORBIDDEN",
         AppError::BadRequest(_) => "BAD_REQUEST",
         AppError::NotFound(_) => "NOT_FOUND",
         AppError::Conflict(_) => "CONFLICT",
-        AppError::TooManyRequests(_) => "TOO_MANY_REQUESTS",
+        AppError::Validation(_) => "VALIDATION_FAILED",
+        AppError::TooManyRequests(..) => "TOO_MANY_REQUESTS",
         AppError::Db(_) => "DB_ERROR",
         AppError::Internal => "INTERNAL",
     }
@@ -67,7 +86,8 @@ impl ResponseError for AppError {
             AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
             AppError::NotFound(_) => StatusCode::NOT_FOUND,
             AppError::Conflict(_) => StatusCode::CONFLICT,
-            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::TooManyRequests(..) => StatusCode::TOO_MANY_REQUESTS,
             AppError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -75,7 +95,15 @@ impl ResponseError for AppError {
 
     fn error_response(&self) -> HttpResponse {
         let msg = self.to_string();
-        let body = ErrorResponse { success: false, error: ErrorBody { code: error_code(self), message: msg } };
-        HttpResponse::build(self.status_code()).json(body)
+        let fields = match self {
+            AppError::Validation(fields) => fields.clone(),
+            _ => Vec::new(),
+        };
+        let body = ErrorResponse { success: false, error: ErrorBody { code: error_code(self), message: msg, fields } };
+        let mut res = HttpResponse::build(self.status_code());
+        if let AppError::TooManyRequests(_, retry_after) = self {
+            res.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+        }
+        res.json(body)
     }
 }
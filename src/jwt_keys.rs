@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters,
+    OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use ring::signature::{Ed25519KeyPair, KeyPair, RsaKeyPair};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::errors::AppError;
+
+// Only for local development, where no key pair has been set up
+const DEV_SECRET: &str = "dev-secret-change-me";
+
+/// Keys for the JWTs this service issues: one signing key plus every key whose tokens are
+/// still accepted, looked up by the `kid` header. Retired keys stay verifiable so a key can
+/// be rotated without logging everybody out.
+pub struct JwtKeys {
+    kid: String,
+    alg: Algorithm,
+    encoding: EncodingKey,
+    verifying: HashMap<String, (Algorithm, DecodingKey)>,
+    published: JwkSet,
+}
+
+impl fmt::Debug for JwtKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKeys")
+            .field("kid", &self.kid)
+            .field("alg", &self.alg)
+            .field("verifying", &self.verifying.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl JwtKeys {
+    /// Reads the signing key from JWT_PRIVATE_KEY (PEM text) or JWT_PRIVATE_KEY_FILE, and
+    /// retired public keys from JWT_VERIFICATION_KEYS (JWKS JSON) or JWT_VERIFICATION_KEYS_FILE.
+    /// Without a private key only development falls back to HS256 with JWT_SECRET.
+    pub fn from_env(development: bool) -> Result<Self, String> {
+        let pem = match (env_value("JWT_PRIVATE_KEY"), env_value("JWT_PRIVATE_KEY_FILE")) {
+            (Some(inline), _) => Some(inline.replace("\\n", "\n")),
+            (None, Some(path)) => Some(
+                std::fs::read_to_string(&path).map_err(|e| format!("cannot read JWT_PRIVATE_KEY_FILE {}: {}", path, e))?,
+            ),
+            (None, None) => None,
+        };
+        let mut keys = match pem {
+            Some(pem) => Self::from_private_pem(&pem, env_value("JWT_KEY_ID"))?,
+            None if development => {
+                log::warn!("No JWT_PRIVATE_KEY configured; signing tokens with HS256 for development.");
+                let secret = env_value("JWT_SECRET").unwrap_or_else(|| DEV_SECRET.to_string());
+                Self::hmac(secret.as_bytes())
+            }
+            None => return Err("JWT_PRIVATE_KEY or JWT_PRIVATE_KEY_FILE must be set outside development".into()),
+        };
+
+        let extra = match (env_value("JWT_VERIFICATION_KEYS"), env_value("JWT_VERIFICATION_KEYS_FILE")) {
+            (Some(inline), _) => Some(inline),
+            (None, Some(path)) => Some(
+                std::fs::read_to_string(&path)
+                    .map_err(|e| format!("cannot read JWT_VERIFICATION_KEYS_FILE {}: {}", path, e))?,
+            ),
+            (None, None) => None,
+        };
+        if let Some(raw) = extra {
+            let set: JwkSet = serde_json::from_str(&raw).map_err(|e| format!("invalid JWT verification keys: {}", e))?;
+            for jwk in set.keys {
+                keys.add_verification_key(jwk)?;
+            }
+        }
+        Ok(keys)
+    }
+
+    pub(crate) fn hmac(secret: &[u8]) -> Self {
+        let kid = "dev-hs256".to_string();
+        let verifying = HashMap::from([(kid.clone(), (Algorithm::HS256, DecodingKey::from_secret(secret)))]);
+        // A symmetric key is never published
+        Self { kid, alg: Algorithm::HS256, encoding: EncodingKey::from_secret(secret), verifying, published: JwkSet { keys: Vec::new() } }
+    }
+
+    // Accepts an RSA key (PKCS#1 or PKCS#8) for RS256 or an Ed25519 PKCS#8 key for EdDSA.
+    fn from_private_pem(pem_text: &str, kid: Option<String>) -> Result<Self, String> {
+        let parsed = pem::parse(pem_text.trim()).map_err(|e| format!("invalid JWT private key PEM: {}", e))?;
+        let der = parsed.contents();
+        let (alg, encoding, params) = if let Ok(pair) = RsaKeyPair::from_der(der).or_else(|_| RsaKeyPair::from_pkcs8(der)) {
+            let public = ring::rsa::PublicKeyComponents::<Vec<u8>>::from(pair.public());
+            let params = AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: URL_SAFE_NO_PAD.encode(&public.n),
+                e: URL_SAFE_NO_PAD.encode(&public.e),
+            });
+            let encoding = EncodingKey::from_rsa_pem(pem_text.trim().as_bytes()).map_err(|e| format!("unusable RSA key: {}", e))?;
+            (Algorithm::RS256, encoding, params)
+        } else if let Ok(pair) = Ed25519KeyPair::from_pkcs8_maybe_unchecked(der) {
+            let params = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: URL_SAFE_NO_PAD.encode(pair.public_key().as_ref()),
+            });
+            (Algorithm::EdDSA, EncodingKey::from_ed_der(der), params)
+        } else {
+            return Err("JWT private key must be an RSA or Ed25519 private key".into());
+        };
+
+        let kid = kid.unwrap_or_else(|| thumbprint(&params));
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(key_algorithm(alg)),
+                key_id: Some(kid.clone()),
+                ..Default::default()
+            },
+            algorithm: params,
+        };
+        let decoding = DecodingKey::from_jwk(&jwk).map_err(|e| format!("unusable JWT public key: {}", e))?;
+        Ok(Self {
+            kid: kid.clone(),
+            alg,
+            encoding,
+            verifying: HashMap::from([(kid, (alg, decoding))]),
+            published: JwkSet { keys: vec![jwk] },
+        })
+    }
+
+    fn add_verification_key(&mut self, mut jwk: Jwk) -> Result<(), String> {
+        let alg = match &jwk.algorithm {
+            AlgorithmParameters::RSA(_) => Algorithm::RS256,
+            AlgorithmParameters::OctetKeyPair(p) if p.curve == EllipticCurve::Ed25519 => Algorithm::EdDSA,
+            _ => return Err("JWT verification keys must be RSA or Ed25519 public keys".into()),
+        };
+        let kid = jwk.common.key_id.clone().unwrap_or_else(|| thumbprint(&jwk.algorithm));
+        if self.verifying.contains_key(&kid) {
+            return Err(format!("duplicate JWT key id {}", kid));
+        }
+        let decoding = DecodingKey::from_jwk(&jwk).map_err(|e| format!("unusable JWT verification key {}: {}", kid, e))?;
+        jwk.common = CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm(alg)),
+            key_id: Some(kid.clone()),
+            ..Default::default()
+        };
+        self.verifying.insert(kid, (alg, decoding));
+        self.published.keys.push(jwk);
+        Ok(())
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, AppError> {
+        let mut header = Header::new(self.alg);
+        header.kid = Some(self.kid.clone());
+        Ok(jsonwebtoken::encode(&header, claims, &self.encoding)?)
+    }
+
+    /// Verifies a token against the key named by its `kid`, with the algorithm pinned to
+    /// that key's. `validation` supplies the claim checks.
+    pub fn decode<T: DeserializeOwned>(&self, token: &str, mut validation: Validation) -> Result<T, AppError> {
+        let header = decode_header(token)?;
+        let kid = header.kid.ok_or(AppError::Unauthorized)?;
+        let (alg, key) = self.verifying.get(&kid).ok_or(AppError::Unauthorized)?;
+        if header.alg != *alg {
+            return Err(AppError::Unauthorized);
+        }
+        validation.algorithms = vec![*alg];
+        Ok(decode::<T>(token, key, &validation)?.claims)
+    }
+
+    /// Public keys for other services verifying our tokens.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.published
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|s| !s.trim().is_empty())
+}
+
+fn key_algorithm(alg: Algorithm) -> KeyAlgorithm {
+    match alg {
+        Algorithm::EdDSA => KeyAlgorithm::EdDSA,
+        _ => KeyAlgorithm::RS256,
+    }
+}
+
+// RFC 7638 JWK thumbprint, used as the key id unless JWT_KEY_ID overrides it
+fn thumbprint(params: &AlgorithmParameters) -> String {
+    let canonical = match params {
+        AlgorithmParameters::RSA(p) => format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, p.e, p.n),
+        AlgorithmParameters::OctetKeyPair(p) => format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, p.x),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    };
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use serde_json::{json, Value};
+
+    const RSA_KEY: &str = include_str!("../tests/fixtures/id_token_signing_key.pem");
+
+    fn ed25519_pem() -> String {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        pem::encode(&pem::Pem::new("PRIVATE KEY", pkcs8.as_ref().to_vec()))
+    }
+
+    fn claims() -> Value {
+        json!({ "sub": "someone", "exp": 4_000_000_000u64 })
+    }
+
+    fn kid_of(token: &str) -> String {
+        decode_header(token).unwrap().kid.unwrap()
+    }
+
+    #[test]
+    fn signs_with_the_configured_key_and_verifies_by_kid() {
+        let rsa = JwtKeys::from_private_pem(RSA_KEY, Some("rsa-1".into())).unwrap();
+        let token = rsa.encode(&claims()).unwrap();
+        assert_eq!(decode_header(&token).unwrap().alg, Algorithm::RS256);
+        assert_eq!(kid_of(&token), "rsa-1");
+        assert_eq!(rsa.decode::<Value>(&token, Validation::default()).unwrap(), claims());
+
+        let ed = JwtKeys::from_private_pem(&ed25519_pem(), None).unwrap();
+        let token = ed.encode(&claims()).unwrap();
+        assert_eq!(decode_header(&token).unwrap().alg, Algorithm::EdDSA);
+        // Without JWT_KEY_ID the kid is the published key's thumbprint
+        assert_eq!(ed.jwks().keys[0].common.key_id.as_deref(), Some(kid_of(&token).as_str()));
+        assert!(ed.decode::<Value>(&token, Validation::default()).is_ok());
+    }
+
+    #[test]
+    fn retired_keys_still_verify_but_unknown_ones_do_not() {
+        let old = JwtKeys::from_private_pem(&ed25519_pem(), Some("old".into())).unwrap();
+        let old_token = old.encode(&claims()).unwrap();
+
+        let mut current = JwtKeys::from_private_pem(RSA_KEY, Some("current".into())).unwrap();
+        assert!(matches!(current.decode::<Value>(&old_token, Validation::default()), Err(AppError::Unauthorized)));
+
+        current.add_verification_key(old.jwks().keys[0].clone()).unwrap();
+        assert!(current.decode::<Value>(&old_token, Validation::default()).is_ok());
+        assert_eq!(current.jwks().keys.len(), 2);
+        // New tokens are still signed with the current key
+        assert_eq!(kid_of(&current.encode(&claims()).unwrap()), "current");
+        assert!(current.add_verification_key(old.jwks().keys[0].clone()).is_err());
+    }
+
+    #[test]
+    fn tokens_without_a_kid_are_rejected() {
+        let keys = JwtKeys::from_private_pem(RSA_KEY, Some("rsa-1".into())).unwrap();
+        let token = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims(), &keys.encoding).unwrap();
+        assert!(matches!(keys.decode::<Value>(&token, Validation::default()), Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn algorithm_must_match_the_key_named_by_kid() {
+        let old = JwtKeys::from_private_pem(&ed25519_pem(), Some("old".into())).unwrap();
+        let mut keys = JwtKeys::from_private_pem(RSA_KEY, Some("current".into())).unwrap();
+        keys.add_verification_key(old.jwks().keys[0].clone()).unwrap();
+
+        // HS256 signed with a public key, naming the retired key
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("old".into());
+        let public = serde_json::to_vec(&old.jwks().keys[0]).unwrap();
+        let forged = jsonwebtoken::encode(&header, &claims(), &EncodingKey::from_secret(&public)).unwrap();
+        assert!(matches!(keys.decode::<Value>(&forged, Validation::default()), Err(AppError::Unauthorized)));
+
+        // A genuine RS256 token relabelled with the Ed25519 key's kid
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("old".into());
+        let relabelled = jsonwebtoken::encode(&header, &claims(), &keys.encoding).unwrap();
+        assert!(matches!(keys.decode::<Value>(&relabelled, Validation::default()), Err(AppError::Unauthorized)));
+    }
+}
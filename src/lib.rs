@@ -0,0 +1,13 @@
+pub mod auth;
+pub mod client;
+pub mod config;
+pub mod db;
+pub mod errors;
+pub mod jwt_keys;
+pub mod dto;
+pub mod repositories;
+pub mod services;
+pub mod models;
+pub mod routes;
+pub mod mailer;
+pub mod response;
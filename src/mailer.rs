@@ -1,4 +1,5 @@
 use crate::db::DbPool;
+use chrono::{DateTime, Utc};
 use crate::errors::AppError;
 use lettre::message::{header, Mailbox, Message};
 use lettre::{transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
@@ -77,8 +78,66 @@ pub async fn send_otp(
     pool: &DbPool,
     to_email: &str,
     code: &str,
+    expiry_minutes: i64,
 ) -> Result<(), AppError> {
     let subject = "Your verification code";
-    let body = format!("Your OTP code is: {}\nThis code expires in 10 minutes.", code);
+    let body = format!("Your OTP code is: {}\nThis code expires in {} minutes.", code, expiry_minutes);
     send_email(pool, to_email, subject, &body).await
 }
+
+pub async fn send_login_code(
+    pool: &DbPool,
+    to_email: &str,
+    code: &str,
+    link: Option<&str>,
+    expiry_minutes: i64,
+) -> Result<(), AppError> {
+    let subject = "Your sign-in code";
+    let mut body = format!("Your sign-in code is: {}\n", code);
+    if let Some(link) = link {
+        body.push_str(&format!("Or sign in with this link: {}\n", link));
+    }
+    body.push_str(&format!(
+        "This code expires in {} minutes. If you did not try to sign in, you can ignore this email.",
+        expiry_minutes
+    ));
+    send_email(pool, to_email, subject, &body).await
+}
+
+/// What a new-login alert reports about the login.
+pub struct LoginAlert<'a> {
+    pub when: DateTime<Utc>,
+    pub user_agent: Option<&'a str>,
+    pub ip: Option<&'a str>,
+    pub revoke_link: Option<&'a str>,
+    /// Same credential as the link, for clients that revoke by pasting it.
+    pub revoke_code: &'a str,
+}
+
+pub async fn send_new_login_alert(pool: &DbPool, to_email: &str, alert: &LoginAlert<'_>) -> Result<(), AppError> {
+    let subject = "New sign-in to your account";
+    let mut body = format!(
+        "Your account was just signed in to from a new device or location.\nTime: {} UTC\nDevice: {}\nIP address: {}\n\n",
+        alert.when.format("%Y-%m-%d %H:%M"),
+        alert.user_agent.unwrap_or("unknown"),
+        alert.ip.unwrap_or("unknown"),
+    );
+    body.push_str("If this was you, you can ignore this email. If not, sign that session out");
+    match alert.revoke_link {
+        Some(link) => body.push_str(&format!(" with this link: {}\n", link)),
+        None => body.push_str(&format!(" in the app with this code:\n{}\n", alert.revoke_code)),
+    }
+    body.push_str("and then change your password.");
+    send_email(pool, to_email, subject, &body).await
+}
+
+/// Tells the previous address that the account's email was changed, so an unexpected
+/// change does not go unnoticed.
+pub async fn send_email_changed_notice(pool: &DbPool, old_email: &str, new_email: &str) -> Result<(), AppError> {
+    let subject = "Your email address was changed";
+    let body = format!(
+        "The email address of your account was changed from {} to {}.\nIf you did not make this change, contact support immediately.",
+        old_email, new_email
+    );
+    send_email(pool, old_email, subject, &body).await
+}
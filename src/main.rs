@@ -1,19 +1,8 @@
-mod auth;
-mod config;
-mod db;
-mod errors;
-mod dto;
-mod repositories;
-mod services;
-mod models;
-mod routes;
-mod mailer;
-mod response;
-
 use actix_cors::Cors;
 use actix_web::{web, App, HttpServer};
-use config::AppConfig;
-use db::DbPool;
+use finance_backend::config::AppConfig;
+use finance_backend::db::{self, DbPool};
+use finance_backend::{routes, services};
 use std::net::SocketAddr;
 
 #[actix_web::main]
@@ -35,6 +24,14 @@ async fn main() -> std::io::Result<()> {
     // Run migrations at startup
     db::run_migrations(&pool).await.expect("migrations failed");
 
+    // One verifier/registry for all workers so provider keys are fetched and cached once
+    let google_verifier = web::Data::new(services::google::GoogleVerifier::from_config(&cfg));
+    let oidc_registry = web::Data::new(services::oidc::OidcRegistry::from_config(&cfg));
+    let rate_provider = web::Data::new(services::exchange_rates::RateProvider::from_config(&cfg));
+
+    // Books due recurring transactions; with several instances one of them does it per run
+    services::recurring_service::spawn_scheduler(pool.clone(), std::time::Duration::from_secs(cfg.recurring_interval_secs));
+
     log::info!("Starting server at http://{}", addr);
 
     HttpServer::new(move || {
@@ -58,6 +55,9 @@ async fn main() -> std::io::Result<()> {
             .wrap(cors)
             .app_data(web::Data::new(cfg.clone()))
             .app_data(web::Data::new(pool.clone()))
+            .app_data(google_verifier.clone())
+            .app_data(oidc_registry.clone())
+            .app_data(rate_provider.clone())
             .configure(routes::config)
     })
     .bind(addr)?
@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A file attached to a transaction, without its content.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An attachment's content, for download.
+#[derive(Debug, Clone, FromRow)]
+pub struct AttachmentFile {
+    pub filename: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
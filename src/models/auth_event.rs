@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct AuthEvent {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+    pub event_type: String,
+    pub success: bool,
+    /// Error code of a failed attempt, e.g. `UNAUTHORIZED` or `TOO_MANY_REQUESTS`.
+    pub failure_reason: Option<String>,
+    pub detail: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Column values for a new `auth_events` row.
+#[derive(Debug)]
+pub struct NewAuthEvent<'a> {
+    pub user_id: Option<Uuid>,
+    pub email: Option<&'a str>,
+    pub event_type: &'a str,
+    pub success: bool,
+    pub failure_reason: Option<&'a str>,
+    pub detail: Option<&'a str>,
+    pub ip: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+}
@@ -0,0 +1,24 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// 1 `base_currency` = `rate` `quote_currency` on `rate_date`.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ExchangeRate {
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate_date: NaiveDate,
+    pub rate: Decimal,
+    pub source: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A rate to store, from an upload or a rate source.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewExchangeRate {
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate_date: NaiveDate,
+    pub rate: Decimal,
+}
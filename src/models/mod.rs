@@ -1,5 +1,16 @@
 pub mod user;
 pub mod category;
 pub mod transaction;
+pub mod attachment;
+pub mod money_account;
+pub mod transfer;
+pub mod recurring;
+pub mod exchange_rate;
 pub mod admin;
 pub mod setting;
+pub mod session;
+pub mod totp;
+pub mod identity;
+pub mod api_token;
+pub mod auth_event;
+pub mod passkey;
@@ -0,0 +1,29 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A place money is kept (cash, a bank account, an e-wallet, ...), with its balance.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct MoneyAccount {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub kind: String, // see money_account_service::KINDS
+    pub currency: String,
+    pub opening_balance: Decimal,
+    pub archived: bool,
+    pub created_at: DateTime<Utc>,
+    /// Opening balance plus income minus expenses booked to the account, and transfers in/out.
+    pub balance: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountBalance {
+    pub account_id: Uuid,
+    pub currency: String,
+    /// Transactions up to and including this day; `null` means all of them.
+    pub as_of: Option<NaiveDate>,
+    pub balance: Decimal,
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Passkey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub public_key: Vec<u8>,
+    pub sign_count: i64,
+}
+
+/// A passkey as shown to its owner.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PublicPasskey {
+    pub id: Uuid,
+    pub name: String,
+    pub credential_id: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Column values for a newly registered passkey.
+#[derive(Debug)]
+pub struct NewPasskey<'a> {
+    pub user_id: Uuid,
+    pub name: &'a str,
+    pub credential_id: &'a str,
+    pub public_key: &'a [u8],
+    pub alg: i32,
+    pub sign_count: i64,
+}
+
+/// An outstanding WebAuthn challenge.
+#[derive(Debug, Clone, FromRow)]
+pub struct WebauthnChallenge {
+    pub user_id: Option<Uuid>,
+    pub challenge: String,
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A template the scheduler books into `transactions` on each occurrence.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct RecurringTransaction {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub category_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub description: Option<String>,
+    pub frequency: String,
+    pub every: i32,
+    pub weekday: Option<String>,
+    pub week_of_month: Option<i16>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub max_occurrences: Option<i32>,
+    /// Occurrences used up so far, booked or skipped.
+    pub occurrences_generated: i32,
+    pub last_occurrence: Option<NaiveDate>,
+    /// `None` once the series has ended.
+    pub next_occurrence: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A template with occurrences due, and the current date in its owner's timezone.
+#[derive(Debug, FromRow)]
+pub struct DueRecurring {
+    #[sqlx(flatten)]
+    pub template: RecurringTransaction,
+    pub today: NaiveDate,
+}
+
+/// Column values written on insert and update.
+#[derive(Debug)]
+pub struct RecurringValues<'a> {
+    pub category_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub amount: Decimal,
+    pub currency: &'a str,
+    pub description: Option<&'a str>,
+    pub frequency: &'a str,
+    pub every: i32,
+    pub weekday: Option<&'a str>,
+    pub week_of_month: Option<i16>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub max_occurrences: Option<i32>,
+    pub next_occurrence: Option<NaiveDate>,
+}
+
+/// Where a series stands after booking its due occurrences.
+#[derive(Debug)]
+pub struct RecurringProgress {
+    pub occurrences_generated: i32,
+    pub last_occurrence: Option<NaiveDate>,
+    pub next_occurrence: Option<NaiveDate>,
+}
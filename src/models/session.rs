@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Session {
+    pub user_id: Option<Uuid>,
+    pub admin_id: Option<Uuid>,
+    pub refresh_token_hash: String,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+/// A login as shown to its owner in the active sessions list.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PublicSession {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// True for the session the request was made with.
+    #[sqlx(default)]
+    pub current: bool,
+}
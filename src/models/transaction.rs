@@ -1,4 +1,4 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use serde::Serialize;
 use sqlx::FromRow;
@@ -8,10 +8,52 @@ use uuid::Uuid;
 pub struct Transaction {
     pub id: Uuid,
     pub user_id: Uuid,
-    pub category_id: Uuid,
+    /// `None` for the legs of a transfer, which are neither income nor expense.
+    pub category_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    /// Set when the row is booked by a transfer; edit it through the transfer.
+    pub transfer_id: Option<Uuid>,
+    /// Set when the row was booked by a recurring transaction.
+    pub recurring_id: Option<Uuid>,
     pub amount: Decimal,
+    /// Always the account's currency when `account_id` is set.
+    pub currency: String,
     pub occurred_on: NaiveDate,
     pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Category lines when the amount is split over several categories; empty otherwise.
+    #[sqlx(skip)]
+    pub splits: Vec<TransactionSplit>,
+}
+
+/// One category line of a split transaction.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct TransactionSplit {
+    pub id: Uuid,
+    #[serde(skip)]
+    pub transaction_id: Uuid,
+    pub category_id: Uuid,
+    pub amount: Decimal,
+    pub description: Option<String>,
+}
+
+/// Column values written on insert and update.
+#[derive(Debug)]
+pub struct TransactionValues<'a> {
+    pub category_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub amount: Decimal,
+    pub currency: &'a str,
+    pub occurred_on: NaiveDate,
+    pub description: Option<&'a str>,
 }
 
 // DTOs moved to src/dto/transaction.rs
+
+/// A split line to write, in order.
+#[derive(Debug)]
+pub struct SplitValues<'a> {
+    pub category_id: Uuid,
+    pub amount: Decimal,
+    pub description: Option<&'a str>,
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Transfer {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub from_account_id: Uuid,
+    pub to_account_id: Uuid,
+    pub amount: Decimal,
+    pub fee: Decimal,
+    pub fee_category_id: Option<Uuid>,
+    pub occurred_on: NaiveDate,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Column values written on insert and update; the legs are derived from them.
+#[derive(Debug)]
+pub struct TransferValues<'a> {
+    pub from_account_id: Uuid,
+    pub to_account_id: Uuid,
+    pub amount: Decimal,
+    pub fee: Decimal,
+    pub fee_category_id: Option<Uuid>,
+    pub occurred_on: NaiveDate,
+    pub description: Option<&'a str>,
+}
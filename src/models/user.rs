@@ -11,8 +11,10 @@ pub struct User {
     pub password_hash: String,
     #[allow(dead_code)]
     pub auth_provider: String,
-    pub google_sub: Option<String>,
     pub is_verified: bool,
+    pub preferred_currency: String,
+    pub locale: String,
+    pub timezone: String,
     pub created_at: DateTime<Utc>,
 }
 
@@ -22,6 +24,9 @@ pub struct PublicUser {
     pub name: String,
     pub email: String,
     pub is_verified: bool,
+    pub preferred_currency: String,
+    pub locale: String,
+    pub timezone: String,
     pub created_at: DateTime<Utc>,
 }
 
@@ -32,6 +37,9 @@ impl From<User> for PublicUser {
             name: u.name,
             email: u.email,
             is_verified: u.is_verified,
+            preferred_currency: u.preferred_currency,
+            locale: u.locale,
+            timezone: u.timezone,
             created_at: u.created_at,
         }
     }
@@ -44,3 +44,13 @@ pub async fn insert(pool: &DbPool, id: Uuid, name: &str, email: &str, password_h
     Ok(row)
 }
 
+// Rehash on login; does nothing if the admin's password was changed after `old_hash` was read.
+pub async fn replace_password_hash(pool: &DbPool, id: Uuid, old_hash: &str, new_hash: &str) -> Result<(), AppError> {
+    sqlx::query("UPDATE admins SET password_hash=$3 WHERE id=$1 AND password_hash=$2")
+        .bind(id)
+        .bind(old_hash)
+        .bind(new_hash)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::api_token::{ApiToken, PublicApiToken};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+pub async fn create(
+    pool: &DbPool,
+    user_id: Uuid,
+    name: &str,
+    token_hash: &str,
+    token_prefix: &str,
+    scopes: &[String],
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<PublicApiToken, AppError> {
+    let row = sqlx::query_as::<_, PublicApiToken>(
+        "INSERT INTO api_tokens (id, user_id, name, token_hash, token_prefix, scopes, expires_at)
+         VALUES ($1,$2,$3,$4,$5,$6,$7)
+         RETURNING id, name, token_prefix, scopes, expires_at, last_used_at, created_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(name)
+    .bind(token_hash)
+    .bind(token_prefix)
+    .bind(scopes)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn find_active_by_hash(pool: &DbPool, token_hash: &str) -> Result<Option<ApiToken>, AppError> {
+    let row = sqlx::query_as::<_, ApiToken>(
+        "SELECT id, user_id, scopes, last_used_at FROM api_tokens
+         WHERE token_hash=$1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > now())",
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn touch(pool: &DbPool, id: Uuid) -> Result<(), AppError> {
+    sqlx::query("UPDATE api_tokens SET last_used_at = now() WHERE id=$1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn list_active_for_user(pool: &DbPool, user_id: Uuid) -> Result<Vec<PublicApiToken>, AppError> {
+    let rows = sqlx::query_as::<_, PublicApiToken>(
+        "SELECT id, name, token_prefix, scopes, expires_at, last_used_at, created_at FROM api_tokens
+         WHERE user_id=$1 AND revoked_at IS NULL ORDER BY created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn revoke(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<u64, AppError> {
+    let res = sqlx::query("UPDATE api_tokens SET revoked_at = now() WHERE id=$1 AND user_id=$2 AND revoked_at IS NULL")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
@@ -0,0 +1,55 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::attachment::{Attachment, AttachmentFile};
+use uuid::Uuid;
+
+const COLUMNS: &str = "id, transaction_id, filename, content_type, size_bytes, created_at";
+
+pub async fn list_for_transaction(pool: &DbPool, transaction_id: Uuid) -> Result<Vec<Attachment>, AppError> {
+    let rows = sqlx::query_as::<_, Attachment>(&format!(
+        "SELECT {} FROM transaction_attachments WHERE transaction_id=$1 ORDER BY created_at, id",
+        COLUMNS
+    ))
+    .bind(transaction_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn insert(pool: &DbPool, id: Uuid, transaction_id: Uuid, filename: &str, content_type: &str, content: &[u8]) -> Result<Attachment, AppError> {
+    let row = sqlx::query_as::<_, Attachment>(&format!(
+        "INSERT INTO transaction_attachments (id, transaction_id, filename, content_type, size_bytes, content)
+         VALUES ($1,$2,$3,$4,$5,$6)
+         RETURNING {}",
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(transaction_id)
+    .bind(filename)
+    .bind(content_type)
+    .bind(content.len() as i32)
+    .bind(content)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_file(pool: &DbPool, id: Uuid, transaction_id: Uuid) -> Result<Option<AttachmentFile>, AppError> {
+    let row = sqlx::query_as::<_, AttachmentFile>(
+        "SELECT filename, content_type, content FROM transaction_attachments WHERE id=$1 AND transaction_id=$2",
+    )
+    .bind(id)
+    .bind(transaction_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn delete(pool: &DbPool, id: Uuid, transaction_id: Uuid) -> Result<u64, AppError> {
+    let res = sqlx::query("DELETE FROM transaction_attachments WHERE id=$1 AND transaction_id=$2")
+        .bind(id)
+        .bind(transaction_id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
@@ -0,0 +1,85 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::auth_event::{AuthEvent, NewAuthEvent};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+const COLUMNS: &str = "id, user_id, email, event_type, success, failure_reason, detail, ip, user_agent, created_at";
+
+pub async fn insert(pool: &DbPool, event: &NewAuthEvent<'_>) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO auth_events (id, user_id, email, event_type, success, failure_reason, detail, ip, user_agent)
+         VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(event.user_id)
+    .bind(event.email)
+    .bind(event.event_type)
+    .bind(event.success)
+    .bind(event.failure_reason)
+    .bind(event.detail)
+    .bind(event.ip)
+    .bind(event.user_agent)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Newest first; `before` continues from the oldest event of the previous page.
+pub async fn list_for_user(pool: &DbPool, user_id: Uuid, before: Option<DateTime<Utc>>, limit: i64) -> Result<Vec<AuthEvent>, AppError> {
+    let rows = sqlx::query_as::<_, AuthEvent>(&format!(
+        "SELECT {} FROM auth_events
+         WHERE user_id=$1 AND ($2::timestamptz IS NULL OR created_at < $2)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3",
+        COLUMNS
+    ))
+    .bind(user_id)
+    .bind(before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Filters for the admin view; `None` matches everything.
+#[derive(Debug, Default)]
+pub struct EventFilter {
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+    pub event_type: Option<String>,
+    pub success: Option<bool>,
+    pub ip: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+pub async fn search(pool: &DbPool, filter: &EventFilter, limit: i64, offset: i64) -> Result<Vec<AuthEvent>, AppError> {
+    let rows = sqlx::query_as::<_, AuthEvent>(&format!(
+        r#"
+        SELECT {} FROM auth_events
+        WHERE ($1::uuid IS NULL OR user_id = $1)
+          AND ($2::text IS NULL OR email = $2)
+          AND ($3::text IS NULL OR event_type = $3)
+          AND ($4::bool IS NULL OR success = $4)
+          AND ($5::text IS NULL OR ip = $5)
+          AND ($6::timestamptz IS NULL OR created_at >= $6)
+          AND ($7::timestamptz IS NULL OR created_at < $7)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $8 OFFSET $9
+        "#,
+        COLUMNS
+    ))
+    .bind(filter.user_id)
+    .bind(&filter.email)
+    .bind(&filter.event_type)
+    .bind(filter.success)
+    .bind(&filter.ip)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -55,6 +55,31 @@ pub async fn update(pool: &DbPool, id: Uuid, user_id: Uuid, name: &str, kind: &s
     Ok(row)
 }
 
+/// Categories among `ids` that belong to the user.
+pub async fn list_by_ids_user(pool: &DbPool, ids: &[Uuid], user_id: Uuid) -> Result<Vec<Category>, AppError> {
+    let rows = sqlx::query_as::<_, Category>(
+        "SELECT id, user_id, name, kind, color, created_at FROM categories WHERE id = ANY($1) AND user_id=$2",
+    )
+    .bind(ids)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Whether any line of one of the user's split transactions is booked in the category.
+pub async fn used_by_splits(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+    let (used,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM transaction_splits s JOIN transactions t ON t.id = s.transaction_id
+                        WHERE s.category_id=$1 AND t.user_id=$2)",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(used)
+}
+
 pub async fn delete(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<u64, AppError> {
     let res = sqlx::query("DELETE FROM categories WHERE id=$1 AND user_id=$2")
         .bind(id)
@@ -63,4 +88,3 @@ pub async fn delete(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<u64, AppEr
         .await?;
     Ok(res.rows_affected())
 }
-
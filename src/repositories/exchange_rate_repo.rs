@@ -0,0 +1,78 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::exchange_rate::{ExchangeRate, NewExchangeRate};
+use chrono::NaiveDate;
+
+/// Inserts or replaces rates; returns how many were written.
+pub async fn upsert_many(pool: &DbPool, rates: &[NewExchangeRate], source: &str) -> Result<u64, AppError> {
+    let mut tx = pool.begin().await?;
+    let mut written = 0;
+    for r in rates {
+        written += sqlx::query(
+            "INSERT INTO exchange_rates (base_currency, quote_currency, rate_date, rate, source)
+             VALUES ($1,$2,$3,$4,$5)
+             ON CONFLICT (base_currency, quote_currency, rate_date)
+             DO UPDATE SET rate = EXCLUDED.rate, source = EXCLUDED.source, updated_at = now()",
+        )
+        .bind(&r.base_currency)
+        .bind(&r.quote_currency)
+        .bind(r.rate_date)
+        .bind(r.rate)
+        .bind(source)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    tx.commit().await?;
+    Ok(written)
+}
+
+pub async fn list(
+    pool: &DbPool,
+    currency: Option<&str>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    limit: i64,
+) -> Result<Vec<ExchangeRate>, AppError> {
+    let rows = sqlx::query_as::<_, ExchangeRate>(
+        r#"
+        SELECT base_currency, quote_currency, rate_date, rate, source, updated_at
+        FROM exchange_rates
+        WHERE ($1::text IS NULL OR base_currency = $1 OR quote_currency = $1)
+          AND ($2::date IS NULL OR rate_date >= $2)
+          AND ($3::date IS NULL OR rate_date <= $3)
+        ORDER BY rate_date DESC, base_currency, quote_currency
+        LIMIT $4
+        "#,
+    )
+    .bind(currency)
+    .bind(from)
+    .bind(to)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Every rate published between `from` and `to`, both inclusive.
+pub async fn between(pool: &DbPool, from: NaiveDate, to: NaiveDate) -> Result<Vec<ExchangeRate>, AppError> {
+    let rows = sqlx::query_as::<_, ExchangeRate>(
+        "SELECT base_currency, quote_currency, rate_date, rate, source, updated_at
+         FROM exchange_rates WHERE rate_date >= $1 AND rate_date <= $2",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn delete(pool: &DbPool, base: &str, quote: &str, rate_date: NaiveDate) -> Result<u64, AppError> {
+    let res = sqlx::query("DELETE FROM exchange_rates WHERE base_currency=$1 AND quote_currency=$2 AND rate_date=$3")
+        .bind(base)
+        .bind(quote)
+        .bind(rate_date)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
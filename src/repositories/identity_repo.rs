@@ -0,0 +1,60 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::identity::UserIdentity;
+use uuid::Uuid;
+
+/// Returns the user an external identity is linked to, marking it as used.
+pub async fn find_user_id(pool: &DbPool, provider: &str, subject: &str) -> Result<Option<Uuid>, AppError> {
+    let row: Option<(Uuid,)> = sqlx::query_as(
+        "UPDATE user_identities SET last_used_at = now() WHERE provider=$1 AND subject=$2 RETURNING user_id",
+    )
+    .bind(provider)
+    .bind(subject)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| r.0))
+}
+
+pub async fn create(pool: &DbPool, user_id: Uuid, provider: &str, subject: &str, email: Option<&str>) -> Result<UserIdentity, AppError> {
+    let row = sqlx::query_as::<_, UserIdentity>(
+        "INSERT INTO user_identities (id, user_id, provider, subject, email, last_used_at)
+         VALUES ($1,$2,$3,$4,$5, now())
+         RETURNING id, provider, email, created_at, last_used_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(provider)
+    .bind(subject)
+    .bind(email)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn list_for_user(pool: &DbPool, user_id: Uuid) -> Result<Vec<UserIdentity>, AppError> {
+    let rows = sqlx::query_as::<_, UserIdentity>(
+        "SELECT id, provider, email, created_at, last_used_at FROM user_identities
+         WHERE user_id=$1 ORDER BY created_at",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn count_for_user(pool: &DbPool, user_id: Uuid) -> Result<i64, AppError> {
+    let (n,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM user_identities WHERE user_id=$1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(n)
+}
+
+pub async fn delete(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<u64, AppError> {
+    let res = sqlx::query("DELETE FROM user_identities WHERE id=$1 AND user_id=$2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
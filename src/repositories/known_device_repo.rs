@@ -0,0 +1,41 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use uuid::Uuid;
+
+/// Whether the user has signed in before at all, from this device, and from this network.
+#[derive(Debug, Clone, Copy)]
+pub struct Familiarity {
+    pub any: bool,
+    pub device: bool,
+    pub network: bool,
+}
+
+pub async fn familiarity(pool: &DbPool, user_id: Uuid, fingerprint: &str, ip_range: &str) -> Result<Familiarity, AppError> {
+    let (any, device, network): (bool, Option<bool>, Option<bool>) = sqlx::query_as(
+        "SELECT count(*) > 0, bool_or(fingerprint = $2), bool_or(ip_range = $3)
+         FROM known_devices WHERE user_id=$1",
+    )
+    .bind(user_id)
+    .bind(fingerprint)
+    .bind(ip_range)
+    .fetch_one(pool)
+    .await?;
+    Ok(Familiarity { any, device: device.unwrap_or(false), network: network.unwrap_or(false) })
+}
+
+pub async fn remember(pool: &DbPool, user_id: Uuid, fingerprint: &str, ip_range: &str, user_agent: Option<&str>) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO known_devices (id, user_id, fingerprint, ip_range, user_agent)
+         VALUES ($1,$2,$3,$4,$5)
+         ON CONFLICT (user_id, fingerprint, ip_range)
+         DO UPDATE SET last_seen_at = now(), user_agent = EXCLUDED.user_agent",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(fingerprint)
+    .bind(ip_range)
+    .bind(user_agent)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
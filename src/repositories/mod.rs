@@ -2,5 +2,18 @@ pub mod user_repo;
 pub mod otp_repo;
 pub mod category_repo;
 pub mod transaction_repo;
+pub mod attachment_repo;
+pub mod money_account_repo;
+pub mod transfer_repo;
+pub mod recurring_repo;
+pub mod exchange_rate_repo;
 pub mod admin_repo;
 pub mod settings_repo;
+pub mod session_repo;
+pub mod totp_repo;
+pub mod throttle_repo;
+pub mod identity_repo;
+pub mod api_token_repo;
+pub mod auth_event_repo;
+pub mod known_device_repo;
+pub mod passkey_repo;
@@ -0,0 +1,90 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::money_account::MoneyAccount;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+// Accounts with their balance from transactions on or before $2 (all of them when NULL)
+const SELECT_WITH_BALANCE: &str = r#"
+    SELECT a.id, a.user_id, a.name, a.kind, a.currency, a.opening_balance, a.archived, a.created_at,
+           a.opening_balance + COALESCE(SUM(CASE
+               WHEN t.category_id IS NULL THEN t.amount -- transfer legs carry their sign
+               WHEN c.kind = 'income' THEN t.amount
+               ELSE -t.amount
+           END), 0) AS balance
+    FROM accounts a
+    LEFT JOIN transactions t ON t.account_id = a.id AND ($2::date IS NULL OR t.occurred_on <= $2)
+    LEFT JOIN categories c ON c.id = t.category_id
+"#;
+
+pub async fn list_by_user(pool: &DbPool, user_id: Uuid, as_of: Option<NaiveDate>, include_archived: bool) -> Result<Vec<MoneyAccount>, AppError> {
+    let rows = sqlx::query_as::<_, MoneyAccount>(&format!(
+        "{} WHERE a.user_id=$1 AND ($3 OR NOT a.archived) GROUP BY a.id ORDER BY a.archived, a.name",
+        SELECT_WITH_BALANCE
+    ))
+    .bind(user_id)
+    .bind(as_of)
+    .bind(include_archived)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_by_id_user(pool: &DbPool, id: Uuid, user_id: Uuid, as_of: Option<NaiveDate>) -> Result<Option<MoneyAccount>, AppError> {
+    let row = sqlx::query_as::<_, MoneyAccount>(&format!(
+        "{} WHERE a.user_id=$1 AND a.id=$3 GROUP BY a.id",
+        SELECT_WITH_BALANCE
+    ))
+    .bind(user_id)
+    .bind(as_of)
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// The account's archived flag and currency, if it belongs to the user.
+pub async fn find_state(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<Option<(bool, String)>, AppError> {
+    let row = sqlx::query_as("SELECT archived, currency FROM accounts WHERE id=$1 AND user_id=$2")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+pub async fn insert(pool: &DbPool, id: Uuid, user_id: Uuid, name: &str, kind: &str, currency: &str, opening_balance: Decimal) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO accounts (id, user_id, name, kind, currency, opening_balance) VALUES ($1,$2,$3,$4,$5,$6)")
+        .bind(id)
+        .bind(user_id)
+        .bind(name)
+        .bind(kind)
+        .bind(currency)
+        .bind(opening_balance)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn update(pool: &DbPool, id: Uuid, user_id: Uuid, name: &str, kind: &str, opening_balance: Decimal, archived: bool) -> Result<u64, AppError> {
+    let res = sqlx::query("UPDATE accounts SET name=$1, kind=$2, opening_balance=$3, archived=$4 WHERE id=$5 AND user_id=$6")
+        .bind(name)
+        .bind(kind)
+        .bind(opening_balance)
+        .bind(archived)
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn delete(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<u64, AppError> {
+    let res = sqlx::query("DELETE FROM accounts WHERE id=$1 AND user_id=$2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
@@ -2,7 +2,7 @@ use crate::db::DbPool;
 use crate::errors::AppError;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use sqlx::{self};
+use sqlx::{self, PgConnection};
 
 pub async fn last_created_at(pool: &DbPool, user_id: Uuid, purpose: &str) -> Result<Option<DateTime<Utc>>, AppError> {
     let row: Option<(DateTime<Utc>,)> = sqlx::query_as(
@@ -15,31 +15,97 @@ pub async fn last_created_at(pool: &DbPool, user_id: Uuid, purpose: &str) -> Res
     Ok(row.map(|r| r.0))
 }
 
-pub async fn create(pool: &DbPool, user_id: Uuid, code: &str, purpose: &str, expires_at: DateTime<Utc>) -> Result<(), AppError> {
+/// Stores a new code and invalidates any still-unused codes of the same purpose.
+pub async fn create(
+    pool: &DbPool,
+    user_id: Uuid,
+    code_hash: &str,
+    purpose: &str,
+    target_email: Option<&str>,
+    expires_at: DateTime<Utc>,
+) -> Result<Uuid, AppError> {
     let id = Uuid::new_v4();
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE user_otp_codes SET used_at = now() WHERE user_id=$1 AND purpose=$2 AND used_at IS NULL")
+        .bind(user_id)
+        .bind(purpose)
+        .execute(&mut *tx)
+        .await?;
     sqlx::query(
-        "INSERT INTO user_otp_codes (id, user_id, code, expires_at, purpose) VALUES ($1,$2,$3,$4,$5)",
+        "INSERT INTO user_otp_codes (id, user_id, code_hash, expires_at, purpose, target_email) VALUES ($1,$2,$3,$4,$5,$6)",
     )
     .bind(id)
     .bind(user_id)
-    .bind(code)
+    .bind(code_hash)
     .bind(expires_at)
     .bind(purpose)
+    .bind(target_email)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub async fn find_valid(pool: &DbPool, user_id: Uuid, code_hash: &str, purpose: &str) -> Result<Option<Uuid>, AppError> {
+    let row: Option<(Uuid,)> = sqlx::query_as(
+        "SELECT id FROM user_otp_codes WHERE user_id=$1 AND code_hash=$2 AND purpose=$3 AND used_at IS NULL AND expires_at > now() ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .bind(purpose)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| r.0))
+}
+
+pub async fn target_email(pool: &DbPool, id: Uuid) -> Result<Option<String>, AppError> {
+    let row: Option<(Option<String>,)> = sqlx::query_as("SELECT target_email FROM user_otp_codes WHERE id=$1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.and_then(|r| r.0))
+}
+
+/// Counts a wrong guess against every outstanding code of the purpose and burns codes
+/// that reached `max_attempts`.
+pub async fn register_failed_attempt(pool: &DbPool, user_id: Uuid, purpose: &str, max_attempts: i32) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE user_otp_codes
+         SET attempts = attempts + 1,
+             used_at = CASE WHEN attempts + 1 >= $3 THEN now() ELSE used_at END
+         WHERE user_id=$1 AND purpose=$2 AND used_at IS NULL AND expires_at > now()",
+    )
+    .bind(user_id)
+    .bind(purpose)
+    .bind(max_attempts)
     .execute(pool)
     .await?;
     Ok(())
 }
 
-pub async fn find_valid(pool: &DbPool, user_id: Uuid, code: &str, purpose: &str) -> Result<Option<Uuid>, AppError> {
+/// Marks one specific outstanding code used, returning its owner. Used where the code's id
+/// travels in a signed link rather than the code itself being typed in.
+pub async fn consume_by_id(pool: &DbPool, id: Uuid, purpose: &str) -> Result<Option<Uuid>, AppError> {
     let row: Option<(Uuid,)> = sqlx::query_as(
-        "SELECT id FROM user_otp_codes WHERE user_id=$1 AND code=$2 AND purpose=$3 AND used_at IS NULL AND expires_at > now() ORDER BY created_at DESC LIMIT 1",
+        "UPDATE user_otp_codes SET used_at = now()
+         WHERE id=$1 AND purpose=$2 AND used_at IS NULL AND expires_at > now()
+         RETURNING user_id",
     )
-    .bind(user_id)
-    .bind(code)
+    .bind(id)
     .bind(purpose)
     .fetch_optional(pool)
     .await?;
     Ok(row.map(|r| r.0))
 }
 
-// mark_used operation is inlined in service to simplify executor types.
+/// Marks a code found by `find_valid` used. Returns false if another request used it first
+/// or it expired in between, so a code can only ever be redeemed once.
+pub async fn mark_used(conn: &mut PgConnection, id: Uuid) -> Result<bool, AppError> {
+    let row: Option<(Uuid,)> = sqlx::query_as(
+        "UPDATE user_otp_codes SET used_at = now() WHERE id=$1 AND used_at IS NULL AND expires_at > now() RETURNING id",
+    )
+    .bind(id)
+    .fetch_optional(conn)
+    .await?;
+    Ok(row.is_some())
+}
@@ -0,0 +1,106 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::passkey::{NewPasskey, Passkey, PublicPasskey, WebauthnChallenge};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+pub async fn create(pool: &DbPool, passkey: &NewPasskey<'_>) -> Result<PublicPasskey, AppError> {
+    let row = sqlx::query_as::<_, PublicPasskey>(
+        "INSERT INTO passkeys (id, user_id, name, credential_id, public_key, alg, sign_count)
+         VALUES ($1,$2,$3,$4,$5,$6,$7)
+         RETURNING id, name, credential_id, created_at, last_used_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(passkey.user_id)
+    .bind(passkey.name)
+    .bind(passkey.credential_id)
+    .bind(passkey.public_key)
+    .bind(passkey.alg)
+    .bind(passkey.sign_count)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn find_by_credential_id(pool: &DbPool, credential_id: &str) -> Result<Option<Passkey>, AppError> {
+    let row = sqlx::query_as::<_, Passkey>(
+        "SELECT id, user_id, public_key, sign_count FROM passkeys WHERE credential_id=$1",
+    )
+    .bind(credential_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn list_for_user(pool: &DbPool, user_id: Uuid) -> Result<Vec<PublicPasskey>, AppError> {
+    let rows = sqlx::query_as::<_, PublicPasskey>(
+        "SELECT id, name, credential_id, created_at, last_used_at FROM passkeys WHERE user_id=$1 ORDER BY created_at",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn count_for_user(pool: &DbPool, user_id: Uuid) -> Result<i64, AppError> {
+    let (count,): (i64,) = sqlx::query_as("SELECT count(*) FROM passkeys WHERE user_id=$1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(count)
+}
+
+/// Stores the new signature counter, but only if nobody used the passkey in the meantime.
+pub async fn record_use(pool: &DbPool, id: Uuid, old_count: i64, new_count: i64) -> Result<u64, AppError> {
+    let res = sqlx::query("UPDATE passkeys SET sign_count=$3, last_used_at=now() WHERE id=$1 AND sign_count=$2")
+        .bind(id)
+        .bind(old_count)
+        .bind(new_count)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn delete(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<u64, AppError> {
+    let res = sqlx::query("DELETE FROM passkeys WHERE id=$1 AND user_id=$2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn create_challenge(
+    pool: &DbPool,
+    user_id: Option<Uuid>,
+    purpose: &str,
+    challenge: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<Uuid, AppError> {
+    // Abandoned ceremonies would otherwise pile up
+    sqlx::query("DELETE FROM webauthn_challenges WHERE expires_at < now()")
+        .execute(pool)
+        .await?;
+    let id = Uuid::new_v4();
+    sqlx::query("INSERT INTO webauthn_challenges (id, user_id, purpose, challenge, expires_at) VALUES ($1,$2,$3,$4,$5)")
+        .bind(id)
+        .bind(user_id)
+        .bind(purpose)
+        .bind(challenge)
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+    Ok(id)
+}
+
+/// Removes and returns an unexpired challenge, so each one can be answered only once.
+pub async fn take_challenge(pool: &DbPool, id: Uuid, purpose: &str) -> Result<Option<WebauthnChallenge>, AppError> {
+    let row = sqlx::query_as::<_, WebauthnChallenge>(
+        "DELETE FROM webauthn_challenges WHERE id=$1 AND purpose=$2 AND expires_at > now() RETURNING user_id, challenge",
+    )
+    .bind(id)
+    .bind(purpose)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
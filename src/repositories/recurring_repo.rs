@@ -0,0 +1,202 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::recurring::{DueRecurring, RecurringProgress, RecurringTransaction, RecurringValues};
+use chrono::NaiveDate;
+use sqlx::pool::PoolConnection;
+use sqlx::Postgres;
+use uuid::Uuid;
+
+const COLUMNS: &str = "id, user_id, category_id, account_id, amount, currency, description, frequency, every, weekday, week_of_month, \
+                       start_date, end_date, max_occurrences, occurrences_generated, last_occurrence, next_occurrence, created_at";
+
+// Held by the instance currently booking occurrences, so several servers can run the scheduler
+const SCHEDULER_LOCK_KEY: i64 = 0x7265_6375_7272;
+
+pub async fn list_by_user(pool: &DbPool, user_id: Uuid) -> Result<Vec<RecurringTransaction>, AppError> {
+    let rows = sqlx::query_as::<_, RecurringTransaction>(&format!(
+        "SELECT {} FROM recurring_transactions WHERE user_id=$1 ORDER BY next_occurrence NULLS LAST, created_at",
+        COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_by_id_user(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<Option<RecurringTransaction>, AppError> {
+    let row = sqlx::query_as::<_, RecurringTransaction>(&format!("SELECT {} FROM recurring_transactions WHERE id=$1 AND user_id=$2", COLUMNS))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+pub async fn insert(pool: &DbPool, id: Uuid, user_id: Uuid, values: &RecurringValues<'_>) -> Result<RecurringTransaction, AppError> {
+    let row = sqlx::query_as::<_, RecurringTransaction>(&format!(
+        r#"INSERT INTO recurring_transactions (id, user_id, category_id, account_id, amount, currency, description, frequency,
+               every, weekday, week_of_month, start_date, end_date, max_occurrences, next_occurrence)
+           VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15)
+           RETURNING {}"#,
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(user_id)
+    .bind(values.category_id)
+    .bind(values.account_id)
+    .bind(values.amount)
+    .bind(values.currency)
+    .bind(values.description)
+    .bind(values.frequency)
+    .bind(values.every)
+    .bind(values.weekday)
+    .bind(values.week_of_month)
+    .bind(values.start_date)
+    .bind(values.end_date)
+    .bind(values.max_occurrences)
+    .bind(values.next_occurrence)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn update(pool: &DbPool, id: Uuid, user_id: Uuid, values: &RecurringValues<'_>) -> Result<RecurringTransaction, AppError> {
+    let row = sqlx::query_as::<_, RecurringTransaction>(&format!(
+        r#"UPDATE recurring_transactions SET category_id=$1, account_id=$2, amount=$3, currency=$4, description=$5, frequency=$6,
+               every=$7, weekday=$8, week_of_month=$9, start_date=$10, end_date=$11, max_occurrences=$12, next_occurrence=$13
+           WHERE id=$14 AND user_id=$15
+           RETURNING {}"#,
+        COLUMNS
+    ))
+    .bind(values.category_id)
+    .bind(values.account_id)
+    .bind(values.amount)
+    .bind(values.currency)
+    .bind(values.description)
+    .bind(values.frequency)
+    .bind(values.every)
+    .bind(values.weekday)
+    .bind(values.week_of_month)
+    .bind(values.start_date)
+    .bind(values.end_date)
+    .bind(values.max_occurrences)
+    .bind(values.next_occurrence)
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Booked transactions stay; they just lose the link to the template.
+pub async fn delete(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<u64, AppError> {
+    let res = sqlx::query("DELETE FROM recurring_transactions WHERE id=$1 AND user_id=$2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn skipped_dates(pool: &DbPool, id: Uuid) -> Result<Vec<NaiveDate>, AppError> {
+    let rows = sqlx::query_scalar::<_, NaiveDate>("SELECT occurrence_date FROM recurring_skips WHERE recurring_id=$1 ORDER BY occurrence_date")
+        .bind(id)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+pub async fn add_skip(pool: &DbPool, id: Uuid, occurrence_date: NaiveDate) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO recurring_skips (recurring_id, occurrence_date) VALUES ($1,$2) ON CONFLICT DO NOTHING")
+        .bind(id)
+        .bind(occurrence_date)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn remove_skip(pool: &DbPool, id: Uuid, occurrence_date: NaiveDate) -> Result<u64, AppError> {
+    let res = sqlx::query("DELETE FROM recurring_skips WHERE recurring_id=$1 AND occurrence_date=$2")
+        .bind(id)
+        .bind(occurrence_date)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Templates with an occurrence due by today in their owner's timezone.
+pub async fn due(pool: &DbPool, limit: i64) -> Result<Vec<DueRecurring>, AppError> {
+    let rows = sqlx::query_as::<_, DueRecurring>(
+        r#"SELECT r.*, (now() AT TIME ZONE u.timezone)::date AS today
+           FROM recurring_transactions r
+           JOIN users u ON u.id = r.user_id
+           WHERE r.next_occurrence IS NOT NULL AND r.next_occurrence <= (now() AT TIME ZONE u.timezone)::date
+           ORDER BY r.next_occurrence
+           LIMIT $1"#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Books `dates` with the template's current values and moves the series to `progress`, in
+/// one transaction. Nothing happens (and 0 is returned) if the series has moved on since
+/// `template` was read, e.g. because another instance booked it or the schedule was edited.
+pub async fn book(pool: &DbPool, template: &RecurringTransaction, dates: &[NaiveDate], progress: &RecurringProgress) -> Result<u64, AppError> {
+    let mut tx = pool.begin().await?;
+    let moved = sqlx::query(
+        "UPDATE recurring_transactions SET occurrences_generated=$1, last_occurrence=$2, next_occurrence=$3
+         WHERE id=$4 AND occurrences_generated=$5 AND next_occurrence IS NOT DISTINCT FROM $6",
+    )
+    .bind(progress.occurrences_generated)
+    .bind(progress.last_occurrence)
+    .bind(progress.next_occurrence)
+    .bind(template.id)
+    .bind(template.occurrences_generated)
+    .bind(template.next_occurrence)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if moved == 0 {
+        tx.rollback().await?;
+        return Ok(0);
+    }
+    let mut booked = 0;
+    for date in dates {
+        // Values are read under the row lock taken above, so an edit made meanwhile is honoured
+        booked += sqlx::query(
+            "INSERT INTO transactions (id, user_id, category_id, account_id, amount, currency, occurred_on, description, recurring_id, occurrence_date)
+             SELECT $1, user_id, category_id, account_id, amount, currency, $2, description, id, $2
+             FROM recurring_transactions WHERE id=$3
+             ON CONFLICT (recurring_id, occurrence_date) DO NOTHING",
+        )
+        .bind(Uuid::new_v4())
+        .bind(date)
+        .bind(template.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    tx.commit().await?;
+    Ok(booked)
+}
+
+/// Takes the scheduler lock on a dedicated connection, or returns `None` if another
+/// instance holds it. The lock goes away with the connection if this process dies.
+pub async fn try_lock_scheduler(pool: &DbPool) -> Result<Option<PoolConnection<Postgres>>, AppError> {
+    let mut conn = pool.acquire().await?;
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+        .bind(SCHEDULER_LOCK_KEY)
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok(locked.then_some(conn))
+}
+
+pub async fn unlock_scheduler(mut conn: PoolConnection<Postgres>) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(SCHEDULER_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
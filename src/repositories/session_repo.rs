@@ -0,0 +1,116 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::client::ClientInfo;
+use crate::models::session::{PublicSession, Session};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+pub async fn create(
+    pool: &DbPool,
+    id: Uuid,
+    user_id: Option<Uuid>,
+    admin_id: Option<Uuid>,
+    refresh_token_hash: &str,
+    expires_at: DateTime<Utc>,
+    client: &ClientInfo,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO sessions (id, user_id, admin_id, refresh_token_hash, expires_at, user_agent, ip)
+         VALUES ($1,$2,$3,$4,$5,$6,$7)",
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(admin_id)
+    .bind(refresh_token_hash)
+    .bind(expires_at)
+    .bind(&client.user_agent)
+    .bind(&client.ip)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn find_active(pool: &DbPool, id: Uuid) -> Result<Option<Session>, AppError> {
+    let row = sqlx::query_as::<_, Session>(
+        "SELECT user_id, admin_id, refresh_token_hash, last_seen_at FROM sessions
+         WHERE id=$1 AND revoked_at IS NULL AND expires_at > now()",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+// Compare-and-swap on the old hash so two concurrent refreshes cannot both succeed.
+pub async fn rotate(
+    pool: &DbPool,
+    id: Uuid,
+    old_hash: &str,
+    new_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<u64, AppError> {
+    let res = sqlx::query(
+        "UPDATE sessions SET refresh_token_hash=$3, expires_at=$4, last_seen_at=now()
+         WHERE id=$1 AND refresh_token_hash=$2 AND revoked_at IS NULL",
+    )
+    .bind(id)
+    .bind(old_hash)
+    .bind(new_hash)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn touch(pool: &DbPool, id: Uuid) -> Result<(), AppError> {
+    sqlx::query("UPDATE sessions SET last_seen_at=now() WHERE id=$1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn list_active_for_user(pool: &DbPool, user_id: Uuid) -> Result<Vec<PublicSession>, AppError> {
+    let rows = sqlx::query_as::<_, PublicSession>(
+        "SELECT id, user_agent, ip, created_at, last_seen_at, expires_at FROM sessions
+         WHERE user_id=$1 AND revoked_at IS NULL AND expires_at > now()
+         ORDER BY last_seen_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn revoke(pool: &DbPool, id: Uuid) -> Result<u64, AppError> {
+    let res = sqlx::query("UPDATE sessions SET revoked_at=now() WHERE id=$1 AND revoked_at IS NULL")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn revoke_owned(pool: &DbPool, id: Uuid, user_id: Option<Uuid>, admin_id: Option<Uuid>) -> Result<u64, AppError> {
+    let res = sqlx::query(
+        "UPDATE sessions SET revoked_at=now()
+         WHERE id=$1 AND user_id IS NOT DISTINCT FROM $2 AND admin_id IS NOT DISTINCT FROM $3 AND revoked_at IS NULL",
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(admin_id)
+    .execute(pool)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn revoke_all_for_user(pool: &DbPool, user_id: Uuid, except: Option<Uuid>) -> Result<u64, AppError> {
+    let res = sqlx::query(
+        "UPDATE sessions SET revoked_at=now()
+         WHERE user_id=$1 AND revoked_at IS NULL AND ($2::uuid IS NULL OR id <> $2)",
+    )
+    .bind(user_id)
+    .bind(except)
+    .execute(pool)
+    .await?;
+    Ok(res.rows_affected())
+}
@@ -35,3 +35,10 @@ pub async fn get_value(pool: &DbPool, key: &str) -> Result<Option<String>, AppEr
     Ok(row.map(|r| r.0))
 }
 
+/// Parses a setting, falling back to `default` when it is missing or malformed.
+pub async fn get_or<T: std::str::FromStr>(pool: &DbPool, key: &str, default: T) -> Result<T, AppError> {
+    Ok(get_value(pool, key)
+        .await?
+        .and_then(|v| v.trim().parse::<T>().ok())
+        .unwrap_or(default))
+}
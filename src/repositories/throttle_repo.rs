@@ -0,0 +1,54 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use chrono::{DateTime, Utc};
+
+/// Latest lock expiry among `keys` for `action`, if any of them is currently locked.
+pub async fn locked_until(pool: &DbPool, action: &str, keys: &[String]) -> Result<Option<DateTime<Utc>>, AppError> {
+    let row: (Option<DateTime<Utc>>,) = sqlx::query_as(
+        "SELECT MAX(locked_until) FROM auth_throttles WHERE action=$1 AND key = ANY($2) AND locked_until > now()",
+    )
+    .bind(action)
+    .bind(keys)
+    .fetch_one(pool)
+    .await?;
+    Ok(row.0)
+}
+
+/// Counts one more failure and returns the new total. Counters older than `window_hours`
+/// start over.
+pub async fn record_failure(pool: &DbPool, action: &str, key: &str, window_hours: i32) -> Result<i32, AppError> {
+    let (failures,): (i32,) = sqlx::query_as(
+        r#"INSERT INTO auth_throttles (action, key, failures, last_failure_at)
+           VALUES ($1,$2,1, now())
+           ON CONFLICT (action, key) DO UPDATE SET
+               failures = CASE WHEN auth_throttles.last_failure_at < now() - make_interval(hours => $3)
+                               THEN 1 ELSE auth_throttles.failures + 1 END,
+               last_failure_at = now()
+           RETURNING failures"#,
+    )
+    .bind(action)
+    .bind(key)
+    .bind(window_hours)
+    .fetch_one(pool)
+    .await?;
+    Ok(failures)
+}
+
+pub async fn lock(pool: &DbPool, action: &str, key: &str, until: DateTime<Utc>) -> Result<(), AppError> {
+    sqlx::query("UPDATE auth_throttles SET locked_until=$3 WHERE action=$1 AND key=$2")
+        .bind(action)
+        .bind(key)
+        .bind(until)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn clear(pool: &DbPool, action: &str, key: &str) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM auth_throttles WHERE action=$1 AND key=$2")
+        .bind(action)
+        .bind(key)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::totp::TotpCredential;
+use uuid::Uuid;
+
+// Credentials belong to exactly one of user_id / admin_id, like sessions.
+pub async fn get_by_owner(pool: &DbPool, user_id: Option<Uuid>, admin_id: Option<Uuid>) -> Result<Option<TotpCredential>, AppError> {
+    let row = sqlx::query_as::<_, TotpCredential>(
+        "SELECT id, secret, confirmed_at, last_used_step FROM totp_credentials
+         WHERE user_id IS NOT DISTINCT FROM $1 AND admin_id IS NOT DISTINCT FROM $2",
+    )
+    .bind(user_id)
+    .bind(admin_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Creates or replaces a pending (unconfirmed) credential.
+pub async fn upsert_pending(pool: &DbPool, user_id: Option<Uuid>, admin_id: Option<Uuid>, secret: &str) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "DELETE FROM totp_credentials
+         WHERE user_id IS NOT DISTINCT FROM $1 AND admin_id IS NOT DISTINCT FROM $2 AND confirmed_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(admin_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("INSERT INTO totp_credentials (id, user_id, admin_id, secret) VALUES ($1,$2,$3,$4)")
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(admin_id)
+        .bind(secret)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Records the time step a code was accepted for. Returns 0 if an equal or later step was
+/// already used, i.e. the code is being replayed.
+pub async fn record_step(pool: &DbPool, id: Uuid, step: i64) -> Result<u64, AppError> {
+    let res = sqlx::query(
+        "UPDATE totp_credentials SET last_used_step=$2
+         WHERE id=$1 AND (last_used_step IS NULL OR last_used_step < $2)",
+    )
+    .bind(id)
+    .bind(step)
+    .execute(pool)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Marks the credential confirmed and replaces its recovery codes.
+pub async fn confirm(pool: &DbPool, id: Uuid, recovery_code_hashes: &[String]) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE totp_credentials SET confirmed_at=now() WHERE id=$1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE credential_id=$1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for hash in recovery_code_hashes {
+        sqlx::query("INSERT INTO totp_recovery_codes (id, credential_id, code_hash) VALUES ($1,$2,$3)")
+            .bind(Uuid::new_v4())
+            .bind(id)
+            .bind(hash)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn use_recovery_code(pool: &DbPool, credential_id: Uuid, code_hash: &str) -> Result<u64, AppError> {
+    let res = sqlx::query(
+        "UPDATE totp_recovery_codes SET used_at=now()
+         WHERE credential_id=$1 AND code_hash=$2 AND used_at IS NULL",
+    )
+    .bind(credential_id)
+    .bind(code_hash)
+    .execute(pool)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn delete(pool: &DbPool, id: Uuid) -> Result<u64, AppError> {
+    let res = sqlx::query("DELETE FROM totp_credentials WHERE id=$1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
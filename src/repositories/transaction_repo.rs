@@ -1,41 +1,136 @@
 use crate::db::DbPool;
 use crate::errors::AppError;
-use crate::models::transaction::Transaction;
+use crate::models::transaction::{SplitValues, Transaction, TransactionSplit, TransactionValues};
 use uuid::Uuid;
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryAs;
+use sqlx::{PgConnection, Postgres};
 
-pub async fn list(
-    pool: &DbPool,
-    user_id: Uuid,
-    category_id: Option<Uuid>,
-    start_date: Option<NaiveDate>,
-    end_date: Option<NaiveDate>,
-) -> Result<Vec<Transaction>, AppError> {
-    let rows = sqlx::query_as::<_, Transaction>(
-        r#"
-        SELECT id, user_id, category_id, amount, occurred_on, description
-        FROM transactions
-        WHERE user_id = $1
-          AND ($2::uuid IS NULL OR category_id = $2)
+const COLUMNS: &str = "id, user_id, category_id, account_id, transfer_id, recurring_id, amount, currency, occurred_on, description, created_at";
+
+#[derive(Debug, Default)]
+pub struct TxnFilter {
+    /// Any of these categories; `None` for all.
+    pub category_ids: Option<Vec<Uuid>>,
+    pub account_id: Option<Uuid>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    /// Category kind, `income` or `expense`.
+    pub kind: Option<String>,
+    /// ILIKE pattern for the description, wildcards already escaped.
+    pub description_pattern: Option<String>,
+    pub has_description: Option<bool>,
+    pub has_attachment: Option<bool>,
+    /// Leaves out the signed rows a transfer books on its two accounts (not its fee).
+    pub exclude_transfer_legs: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SortColumn {
+    OccurredOn,
+    Amount,
+    CreatedAt,
+}
+
+impl SortColumn {
+    fn as_sql(self) -> &'static str {
+        match self {
+            SortColumn::OccurredOn => "occurred_on",
+            SortColumn::Amount => "amount",
+            SortColumn::CreatedAt => "created_at",
+        }
+    }
+}
+
+/// Sort column value of the last row of the previous page.
+#[derive(Debug, Clone)]
+pub enum SortValue {
+    Date(NaiveDate),
+    Amount(Decimal),
+    Time(DateTime<Utc>),
+}
+
+/// Which slice of the ordered rows to return: the `limit` rows after `after` (a sort value
+/// plus the id that breaks ties), ordered by `sort` and then id.
+#[derive(Debug)]
+pub struct Keyset {
+    pub sort: SortColumn,
+    pub descending: bool,
+    pub after: Option<(SortValue, Uuid)>,
+    pub limit: i64,
+}
+
+// Binds $1-$12, see `bind_filter`. The description search is served by a trigram index.
+const FILTER: &str = r#"user_id = $1
+          AND ($2::uuid[] IS NULL OR category_id = ANY($2)
+               OR id IN (SELECT transaction_id FROM transaction_splits WHERE category_id = ANY($2)))
           AND ($3::date IS NULL OR occurred_on >= $3)
           AND ($4::date IS NULL OR occurred_on <= $4)
-        ORDER BY occurred_on DESC, id DESC
-        "#,
-    )
-    .bind(user_id)
-    .bind(category_id)
-    .bind(start_date)
-    .bind(end_date)
-    .fetch_all(pool)
-    .await?;
+          AND ($5::numeric IS NULL OR amount >= $5)
+          AND ($6::numeric IS NULL OR amount <= $6)
+          AND ($7::text IS NULL OR category_id IN (SELECT id FROM categories WHERE user_id = $1 AND kind = $7))
+          AND ($8::text IS NULL OR description ILIKE $8)
+          AND ($9::bool IS NULL OR (description IS NOT NULL AND btrim(description) <> '') = $9)
+          AND ($10::uuid IS NULL OR account_id = $10)
+          AND ($11::bool IS NULL OR EXISTS (SELECT 1 FROM transaction_attachments a WHERE a.transaction_id = transactions.id) = $11)
+          AND (NOT $12 OR transfer_id IS NULL OR category_id IS NOT NULL)"#;
+
+fn bind_filter<'q, O>(
+    query: QueryAs<'q, Postgres, O, PgArguments>,
+    user_id: Uuid,
+    filter: &'q TxnFilter,
+) -> QueryAs<'q, Postgres, O, PgArguments> {
+    query
+        .bind(user_id)
+        .bind(filter.category_ids.as_deref())
+        .bind(filter.start_date)
+        .bind(filter.end_date)
+        .bind(filter.min_amount)
+        .bind(filter.max_amount)
+        .bind(filter.kind.as_deref())
+        .bind(filter.description_pattern.as_deref())
+        .bind(filter.has_description)
+        .bind(filter.account_id)
+        .bind(filter.has_attachment)
+        .bind(filter.exclude_transfer_legs)
+}
+
+pub async fn list(pool: &DbPool, user_id: Uuid, filter: &TxnFilter, page: &Keyset) -> Result<Vec<Transaction>, AppError> {
+    let column = page.sort.as_sql();
+    let (direction, comparison) = if page.descending { ("DESC", "<") } else { ("ASC", ">") };
+    let (after, limit_param) = match page.after {
+        Some(_) => (format!("AND ({}, id) {} ($13, $14)", column, comparison), "$15"),
+        None => (String::new(), "$13"),
+    };
+    let sql = format!(
+        "SELECT {} FROM transactions WHERE {} {} ORDER BY {} {}, id {} LIMIT {}",
+        COLUMNS, FILTER, after, column, direction, direction, limit_param
+    );
+    let mut query = bind_filter(sqlx::query_as::<_, Transaction>(&sql), user_id, filter);
+    if let Some((value, id)) = &page.after {
+        query = match value {
+            SortValue::Date(d) => query.bind(*d),
+            SortValue::Amount(a) => query.bind(*a),
+            SortValue::Time(t) => query.bind(*t),
+        }
+        .bind(*id);
+    }
+    let rows = query.bind(page.limit).fetch_all(pool).await?;
     Ok(rows)
 }
 
+pub async fn count(pool: &DbPool, user_id: Uuid, filter: &TxnFilter) -> Result<i64, AppError> {
+    let sql = format!("SELECT count(*) FROM transactions WHERE {}", FILTER);
+    let (count,): (i64,) = bind_filter(sqlx::query_as(&sql), user_id, filter).fetch_one(pool).await?;
+    Ok(count)
+}
+
 pub async fn get_by_id_user(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<Option<Transaction>, AppError> {
-    let row = sqlx::query_as::<_, Transaction>(
-        "SELECT id, user_id, category_id, amount, occurred_on, description FROM transactions WHERE id=$1 AND user_id=$2",
-    )
+    let row = sqlx::query_as::<_, Transaction>(&format!("SELECT {} FROM transactions WHERE id=$1 AND user_id=$2", COLUMNS))
     .bind(id)
     .bind(user_id)
     .fetch_optional(pool)
@@ -43,58 +138,100 @@ pub async fn get_by_id_user(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<Op
     Ok(row)
 }
 
-pub async fn insert(
-    pool: &DbPool,
-    id: Uuid,
-    user_id: Uuid,
-    category_id: Uuid,
-    amount: &Decimal,
-    occurred_on: NaiveDate,
-    description: &Option<String>,
-) -> Result<Transaction, AppError> {
-    let row = sqlx::query_as::<_, Transaction>(
-        r#"INSERT INTO transactions (id, user_id, category_id, amount, occurred_on, description)
-            VALUES ($1,$2,$3,$4,$5,$6)
-            RETURNING id, user_id, category_id, amount, occurred_on, description"#,
+// Replaces the split lines of a transaction; no lines leaves it with a single category.
+async fn replace_splits(conn: &mut PgConnection, id: Uuid, splits: &[SplitValues<'_>]) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM transaction_splits WHERE transaction_id=$1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    for (position, line) in splits.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO transaction_splits (id, transaction_id, category_id, amount, description, position)
+             VALUES ($1,$2,$3,$4,$5,$6)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(id)
+        .bind(line.category_id)
+        .bind(line.amount)
+        .bind(line.description)
+        .bind(position as i16)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Split lines of the given transactions, in order.
+pub async fn splits_for(pool: &DbPool, ids: &[Uuid]) -> Result<Vec<TransactionSplit>, AppError> {
+    let rows = sqlx::query_as::<_, TransactionSplit>(
+        "SELECT id, transaction_id, category_id, amount, description FROM transaction_splits
+         WHERE transaction_id = ANY($1) ORDER BY transaction_id, position",
     )
+    .bind(ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn insert(pool: &DbPool, id: Uuid, user_id: Uuid, values: &TransactionValues<'_>, splits: &[SplitValues<'_>]) -> Result<Transaction, AppError> {
+    let mut tx = pool.begin().await?;
+    let row = sqlx::query_as::<_, Transaction>(&format!(
+        r#"INSERT INTO transactions (id, user_id, category_id, account_id, amount, currency, occurred_on, description)
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+            RETURNING {}"#,
+        COLUMNS
+    ))
     .bind(id)
     .bind(user_id)
-    .bind(category_id)
-    .bind(amount)
-    .bind(occurred_on)
-    .bind(description)
-    .fetch_one(pool)
+    .bind(values.category_id)
+    .bind(values.account_id)
+    .bind(values.amount)
+    .bind(values.currency)
+    .bind(values.occurred_on)
+    .bind(values.description)
+    .fetch_one(&mut *tx)
     .await?;
+    if !splits.is_empty() {
+        replace_splits(&mut tx, id, splits).await?;
+    }
+    tx.commit().await?;
     Ok(row)
 }
 
+/// Updates the row and, when `splits` is given, replaces its split lines.
 pub async fn update(
     pool: &DbPool,
     id: Uuid,
     user_id: Uuid,
-    category_id: Uuid,
-    amount: &Decimal,
-    occurred_on: NaiveDate,
-    description: &Option<String>,
+    values: &TransactionValues<'_>,
+    splits: Option<&[SplitValues<'_>]>,
 ) -> Result<Transaction, AppError> {
-    let row = sqlx::query_as::<_, Transaction>(
-        r#"UPDATE transactions SET category_id=$1, amount=$2, occurred_on=$3, description=$4
-           WHERE id=$5 AND user_id=$6
-           RETURNING id, user_id, category_id, amount, occurred_on, description"#,
-    )
-    .bind(category_id)
-    .bind(amount)
-    .bind(occurred_on)
-    .bind(description)
+    let mut tx = pool.begin().await?;
+    let row = sqlx::query_as::<_, Transaction>(&format!(
+        r#"UPDATE transactions SET category_id=$1, account_id=$2, amount=$3, currency=$4, occurred_on=$5, description=$6
+           WHERE id=$7 AND user_id=$8 AND transfer_id IS NULL
+           RETURNING {}"#,
+        COLUMNS
+    ))
+    .bind(values.category_id)
+    .bind(values.account_id)
+    .bind(values.amount)
+    .bind(values.currency)
+    .bind(values.occurred_on)
+    .bind(values.description)
     .bind(id)
     .bind(user_id)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
+    if let Some(splits) = splits {
+        replace_splits(&mut tx, id, splits).await?;
+    }
+    tx.commit().await?;
     Ok(row)
 }
 
 pub async fn delete(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<u64, AppError> {
-    let res = sqlx::query("DELETE FROM transactions WHERE id=$1 AND user_id=$2")
+    let res = sqlx::query("DELETE FROM transactions WHERE id=$1 AND user_id=$2 AND transfer_id IS NULL")
         .bind(id)
         .bind(user_id)
         .execute(pool)
@@ -0,0 +1,134 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::transfer::{Transfer, TransferValues};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+const COLUMNS: &str = "id, user_id, from_account_id, to_account_id, amount, fee, fee_category_id, occurred_on, description, created_at";
+
+pub async fn list(
+    pool: &DbPool,
+    user_id: Uuid,
+    account_id: Option<Uuid>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> Result<Vec<Transfer>, AppError> {
+    let rows = sqlx::query_as::<_, Transfer>(&format!(
+        r#"
+        SELECT {} FROM transfers
+        WHERE user_id = $1
+          AND ($2::uuid IS NULL OR from_account_id = $2 OR to_account_id = $2)
+          AND ($3::date IS NULL OR occurred_on >= $3)
+          AND ($4::date IS NULL OR occurred_on <= $4)
+        ORDER BY occurred_on DESC, created_at DESC
+        "#,
+        COLUMNS
+    ))
+    .bind(user_id)
+    .bind(account_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_by_id_user(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<Option<Transfer>, AppError> {
+    let row = sqlx::query_as::<_, Transfer>(&format!("SELECT {} FROM transfers WHERE id=$1 AND user_id=$2", COLUMNS))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row)
+}
+
+// Books the transfer's legs as transactions: out of the source, into the destination and
+// the fee as an expense of the source.
+async fn insert_legs(conn: &mut PgConnection, id: Uuid, user_id: Uuid, values: &TransferValues<'_>) -> Result<(), AppError> {
+    let mut legs = vec![(values.from_account_id, None, -values.amount), (values.to_account_id, None, values.amount)];
+    if values.fee > Decimal::ZERO {
+        legs.push((values.from_account_id, values.fee_category_id, values.fee));
+    }
+    for (account_id, category_id, amount) in legs {
+        sqlx::query(
+            "INSERT INTO transactions (id, user_id, category_id, account_id, transfer_id, amount, currency, occurred_on, description)
+             VALUES ($1,$2,$3,$4,$5,$6,(SELECT currency FROM accounts WHERE id=$4),$7,$8)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(category_id)
+        .bind(account_id)
+        .bind(id)
+        .bind(amount)
+        .bind(values.occurred_on)
+        .bind(values.description)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn insert(pool: &DbPool, id: Uuid, user_id: Uuid, values: &TransferValues<'_>) -> Result<Transfer, AppError> {
+    let mut tx = pool.begin().await?;
+    let row = sqlx::query_as::<_, Transfer>(&format!(
+        r#"INSERT INTO transfers (id, user_id, from_account_id, to_account_id, amount, fee, fee_category_id, occurred_on, description)
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9)
+            RETURNING {}"#,
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(user_id)
+    .bind(values.from_account_id)
+    .bind(values.to_account_id)
+    .bind(values.amount)
+    .bind(values.fee)
+    .bind(values.fee_category_id)
+    .bind(values.occurred_on)
+    .bind(values.description)
+    .fetch_one(&mut *tx)
+    .await?;
+    insert_legs(&mut tx, id, user_id, values).await?;
+    tx.commit().await?;
+    Ok(row)
+}
+
+/// Updates the transfer and rebooks its legs in one go.
+pub async fn update(pool: &DbPool, id: Uuid, user_id: Uuid, values: &TransferValues<'_>) -> Result<Transfer, AppError> {
+    let mut tx = pool.begin().await?;
+    let row = sqlx::query_as::<_, Transfer>(&format!(
+        r#"UPDATE transfers SET from_account_id=$1, to_account_id=$2, amount=$3, fee=$4, fee_category_id=$5, occurred_on=$6, description=$7
+           WHERE id=$8 AND user_id=$9
+           RETURNING {}"#,
+        COLUMNS
+    ))
+    .bind(values.from_account_id)
+    .bind(values.to_account_id)
+    .bind(values.amount)
+    .bind(values.fee)
+    .bind(values.fee_category_id)
+    .bind(values.occurred_on)
+    .bind(values.description)
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM transactions WHERE transfer_id=$1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    insert_legs(&mut tx, id, user_id, values).await?;
+    tx.commit().await?;
+    Ok(row)
+}
+
+/// Deletes the transfer; its legs go with it.
+pub async fn delete(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<u64, AppError> {
+    let res = sqlx::query("DELETE FROM transfers WHERE id=$1 AND user_id=$2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected())
+}
@@ -5,7 +5,7 @@ use uuid::Uuid;
 
 pub async fn get_by_email(pool: &DbPool, email: &str) -> Result<Option<User>, AppError> {
     let u = sqlx::query_as::<_, User>(
-        "SELECT id, name, email, password_hash, auth_provider, google_sub, is_verified, created_at FROM users WHERE email=$1",
+        "SELECT id, name, email, password_hash, auth_provider, is_verified, preferred_currency, locale, timezone, created_at FROM users WHERE email=$1",
     )
     .bind(email)
     .fetch_optional(pool)
@@ -15,7 +15,7 @@ pub async fn get_by_email(pool: &DbPool, email: &str) -> Result<Option<User>, Ap
 
 pub async fn get_by_id(pool: &DbPool, id: Uuid) -> Result<User, AppError> {
     let u = sqlx::query_as::<_, User>(
-        "SELECT id, name, email, password_hash, auth_provider, google_sub, is_verified, created_at FROM users WHERE id=$1",
+        "SELECT id, name, email, password_hash, auth_provider, is_verified, preferred_currency, locale, timezone, created_at FROM users WHERE id=$1",
     )
     .bind(id)
     .fetch_one(pool)
@@ -25,9 +25,9 @@ pub async fn get_by_id(pool: &DbPool, id: Uuid) -> Result<User, AppError> {
 
 pub async fn insert_local(pool: &DbPool, id: Uuid, name: &str, email: &str, password_hash: &str) -> Result<User, AppError> {
     let u = sqlx::query_as::<_, User>(
-        "INSERT INTO users (id, name, email, password_hash, auth_provider, google_sub, is_verified)
-         VALUES ($1,$2,$3,$4,'local', NULL, false)
-         RETURNING id, name, email, password_hash, auth_provider, google_sub, is_verified, created_at",
+        "INSERT INTO users (id, name, email, password_hash, auth_provider, is_verified)
+         VALUES ($1,$2,$3,$4,'local', false)
+         RETURNING id, name, email, password_hash, auth_provider, is_verified, preferred_currency, locale, timezone, created_at",
     )
     .bind(id)
     .bind(name)
@@ -38,33 +38,57 @@ pub async fn insert_local(pool: &DbPool, id: Uuid, name: &str, email: &str, pass
     Ok(u)
 }
 
-pub async fn insert_google(pool: &DbPool, id: Uuid, name: &str, email: &str, sub: &str) -> Result<User, AppError> {
+/// Creates a passwordless account for someone signing up through an external provider
+/// that has already verified their email address.
+pub async fn insert_external(pool: &DbPool, id: Uuid, name: &str, email: &str, provider: &str) -> Result<User, AppError> {
     let u = sqlx::query_as::<_, User>(
-        "INSERT INTO users (id, name, email, password_hash, auth_provider, google_sub, is_verified)
-         VALUES ($1,$2,$3,'', 'google', $4, false)
-         RETURNING id, name, email, password_hash, auth_provider, google_sub, is_verified, created_at",
+        "INSERT INTO users (id, name, email, password_hash, auth_provider, is_verified)
+         VALUES ($1,$2,$3,'', $4, true)
+         RETURNING id, name, email, password_hash, auth_provider, is_verified, preferred_currency, locale, timezone, created_at",
     )
     .bind(id)
     .bind(name)
     .bind(email)
-    .bind(sub)
+    .bind(provider)
     .fetch_one(pool)
     .await?;
     Ok(u)
 }
 
-pub async fn link_google_sub(pool: &DbPool, id: Uuid, sub: &str) -> Result<User, AppError> {
+pub async fn update_profile(pool: &DbPool, id: Uuid, name: &str, preferred_currency: &str, locale: &str, timezone: &str) -> Result<User, AppError> {
     let u = sqlx::query_as::<_, User>(
-        "UPDATE users SET google_sub=$1, auth_provider='google' WHERE id=$2
-         RETURNING id, name, email, password_hash, auth_provider, google_sub, is_verified, created_at",
+        "UPDATE users SET name=$1, preferred_currency=$2, locale=$3, timezone=$4 WHERE id=$5
+         RETURNING id, name, email, password_hash, auth_provider, is_verified, preferred_currency, locale, timezone, created_at",
     )
-    .bind(sub)
+    .bind(name)
+    .bind(preferred_currency)
+    .bind(locale)
+    .bind(timezone)
     .bind(id)
     .fetch_one(pool)
     .await?;
     Ok(u)
 }
 
+// Only replaces the hash it was computed from, so a concurrent password change wins.
+pub async fn replace_password_hash(pool: &DbPool, id: Uuid, old_hash: &str, new_hash: &str) -> Result<(), AppError> {
+    sqlx::query("UPDATE users SET password_hash=$3 WHERE id=$1 AND password_hash=$2")
+        .bind(id)
+        .bind(old_hash)
+        .bind(new_hash)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn is_known_timezone(pool: &DbPool, name: &str) -> Result<bool, AppError> {
+    let (known,): (bool,) = sqlx::query_as("SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name=$1)")
+        .bind(name)
+        .fetch_one(pool)
+        .await?;
+    Ok(known)
+}
+
 // Note: set_verified and set_password moved into services.
 
 pub async fn delete_by_id(pool: &DbPool, id: Uuid) -> Result<u64, AppError> {
@@ -0,0 +1,64 @@
+use actix_web::{patch, post, web, HttpResponse};
+
+use crate::auth::{AuthUser, UserSession};
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::account::{ChangeEmailRequest, ChangePasswordRequest, ConfirmEmailChange, UpdateProfile};
+use crate::errors::AppError;
+use crate::services::account_service as svc;
+use crate::response as resp;
+
+#[patch("/me")]
+pub async fn update_profile(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    payload: web::Json<UpdateProfile>,
+) -> Result<HttpResponse, AppError> {
+    let res = svc::update_profile(pool.get_ref(), user.0, payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
+// Needs the session so the current login survives the sign-out of all others
+#[post("/me/password")]
+pub async fn change_password(
+    pool: web::Data<DbPool>,
+    cfg: web::Data<AppConfig>,
+    client: ClientInfo,
+    session: UserSession,
+    payload: web::Json<ChangePasswordRequest>,
+) -> Result<HttpResponse, AppError> {
+    let revoked = svc::change_password(pool.get_ref(), cfg.get_ref(), &client, session.user_id, session.session_id, payload.into_inner()).await?;
+    Ok(resp::message(format!("Password changed; {} other session(s) signed out", revoked)))
+}
+
+#[post("/me/email")]
+pub async fn request_email_change(
+    pool: web::Data<DbPool>,
+    cfg: web::Data<AppConfig>,
+    client: ClientInfo,
+    user: AuthUser,
+    payload: web::Json<ChangeEmailRequest>,
+) -> Result<HttpResponse, AppError> {
+    svc::request_email_change(pool.get_ref(), cfg.get_ref(), &client, user.0, payload.into_inner()).await?;
+    Ok(resp::message("Confirmation code sent to the new address"))
+}
+
+#[post("/me/email/confirm")]
+pub async fn confirm_email_change(
+    pool: web::Data<DbPool>,
+    cfg: web::Data<AppConfig>,
+    client: ClientInfo,
+    user: AuthUser,
+    payload: web::Json<ConfirmEmailChange>,
+) -> Result<HttpResponse, AppError> {
+    let res = svc::confirm_email_change(pool.get_ref(), cfg.get_ref(), &client, user.0, payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(update_profile)
+        .service(change_password)
+        .service(request_email_change)
+        .service(confirm_email_change);
+}
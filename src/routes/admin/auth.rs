@@ -1,5 +1,7 @@
 use actix_web::{get, post, web, HttpResponse};
-use crate::auth::AdminUser;
+use crate::auth::{AdminSession, AdminUser};
+use crate::dto::auth::{MfaVerifyRequest, RefreshRequest};
+use crate::client::ClientInfo;
 use crate::config::AppConfig;
 use crate::db::DbPool;
 use crate::errors::AppError;
@@ -11,10 +13,11 @@ use crate::response as resp;
 pub async fn register_admin(
     pool: web::Data<DbPool>,
     cfg: web::Data<AppConfig>,
+    client: ClientInfo,
     payload: web::Json<svc::AdminRegisterRequest>,
     maybe_admin: Option<AdminUser>,
 ) -> Result<HttpResponse, AppError> {
-    let res = svc::register(pool.get_ref(), cfg.get_ref(), payload.into_inner(), maybe_admin.is_some()).await?;
+    let res = svc::register(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner(), maybe_admin.is_some()).await?;
     Ok(resp::created(res))
 }
 
@@ -22,12 +25,43 @@ pub async fn register_admin(
 pub async fn login_admin(
     pool: web::Data<DbPool>,
     cfg: web::Data<AppConfig>,
+    client: ClientInfo,
     payload: web::Json<svc::AdminLoginRequest>,
 ) -> Result<HttpResponse, AppError> {
-    let res = svc::login(pool.get_ref(), cfg.get_ref(), payload.into_inner()).await?;
+    let res = svc::login(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
     Ok(resp::ok(res))
 }
 
+#[post("/auth/2fa/verify")]
+pub async fn verify_mfa_admin(
+    pool: web::Data<DbPool>,
+    cfg: web::Data<AppConfig>,
+    client: ClientInfo,
+    payload: web::Json<MfaVerifyRequest>,
+) -> Result<HttpResponse, AppError> {
+    let res = svc::verify_mfa(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
+#[post("/auth/refresh")]
+pub async fn refresh_admin(
+    pool: web::Data<DbPool>,
+    cfg: web::Data<AppConfig>,
+    payload: web::Json<RefreshRequest>,
+) -> Result<HttpResponse, AppError> {
+    let res = svc::refresh(pool.get_ref(), cfg.get_ref(), payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
+#[post("/auth/logout")]
+pub async fn logout_admin(
+    pool: web::Data<DbPool>,
+    session: AdminSession,
+) -> Result<HttpResponse, AppError> {
+    svc::logout(pool.get_ref(), session.admin_id, session.session_id).await?;
+    Ok(resp::message("Logged out"))
+}
+
 #[get("/me")]
 pub async fn me_admin(
     pool: web::Data<DbPool>,
@@ -38,5 +72,10 @@ pub async fn me_admin(
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(register_admin).service(login_admin).service(me_admin);
+    cfg.service(register_admin)
+        .service(login_admin)
+        .service(verify_mfa_admin)
+        .service(refresh_admin)
+        .service(logout_admin)
+        .service(me_admin);
 }
@@ -0,0 +1,22 @@
+use actix_web::{get, web, HttpResponse};
+
+use crate::auth::AdminUser;
+use crate::db::DbPool;
+use crate::dto::auth_event::AdminEventQuery;
+use crate::errors::AppError;
+use crate::services::audit_service as svc;
+use crate::response as resp;
+
+#[get("/auth-events")]
+pub async fn search_auth_events(
+    _admin: AdminUser,
+    pool: web::Data<DbPool>,
+    query: web::Query<AdminEventQuery>,
+) -> Result<HttpResponse, AppError> {
+    let rows = svc::search(pool.get_ref(), query.into_inner()).await?;
+    Ok(resp::ok(rows))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(search_auth_events);
+}
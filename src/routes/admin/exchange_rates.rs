@@ -0,0 +1,57 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use chrono::NaiveDate;
+
+use crate::auth::AdminUser;
+use crate::db::DbPool;
+use crate::dto::exchange_rate::{RateQuery, UploadRates};
+use crate::errors::AppError;
+use crate::services::exchange_rates::{self as svc, RateProvider};
+use crate::response as resp;
+
+#[get("/exchange-rates")]
+pub async fn list_rates(
+    _admin: AdminUser,
+    pool: web::Data<DbPool>,
+    query: web::Query<RateQuery>,
+) -> Result<HttpResponse, AppError> {
+    let rows = svc::list(pool.get_ref(), query.into_inner()).await?;
+    Ok(resp::ok(rows))
+}
+
+#[post("/exchange-rates")]
+pub async fn upload_rates(
+    _admin: AdminUser,
+    pool: web::Data<DbPool>,
+    payload: web::Json<UploadRates>,
+) -> Result<HttpResponse, AppError> {
+    let written = svc::upload(pool.get_ref(), payload.into_inner().rates).await?;
+    Ok(resp::message(format!("{} rate(s) saved", written)))
+}
+
+#[post("/exchange-rates/sync")]
+pub async fn sync_rates(
+    _admin: AdminUser,
+    pool: web::Data<DbPool>,
+    provider: web::Data<RateProvider>,
+) -> Result<HttpResponse, AppError> {
+    let written = svc::sync(pool.get_ref(), provider.get_ref()).await?;
+    Ok(resp::message(format!("{} rate(s) imported", written)))
+}
+
+#[delete("/exchange-rates/{base}/{quote}/{date}")]
+pub async fn delete_rate(
+    _admin: AdminUser,
+    pool: web::Data<DbPool>,
+    path: web::Path<(String, String, NaiveDate)>,
+) -> Result<HttpResponse, AppError> {
+    let (base, quote, date) = path.into_inner();
+    svc::delete(pool.get_ref(), &base, &quote, date).await?;
+    Ok(resp::message("Exchange rate deleted"))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_rates)
+        .service(upload_rates)
+        .service(sync_rates)
+        .service(delete_rate);
+}
@@ -3,11 +3,17 @@ use actix_web::web;
 pub mod auth;
 pub mod users;
 pub mod settings;
+pub mod two_factor;
+pub mod auth_events;
+pub mod exchange_rates;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/admin")
         .configure(auth::config)
+        .configure(two_factor::config)
         .configure(users::config)
-        .configure(settings::config));
+        .configure(settings::config)
+        .configure(auth_events::config)
+        .configure(exchange_rates::config));
 }
 
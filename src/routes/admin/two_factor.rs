@@ -0,0 +1,40 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+
+use crate::auth::{AdminUser, Role};
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::two_factor::TotpCodePayload;
+use crate::errors::AppError;
+use crate::services::two_factor_service as svc;
+use crate::response as resp;
+
+#[get("/me/2fa")]
+pub async fn status_admin(pool: web::Data<DbPool>, admin: AdminUser) -> Result<HttpResponse, AppError> {
+    let res = svc::status(pool.get_ref(), Role::Admin, admin.0).await?;
+    Ok(resp::ok(res))
+}
+
+#[post("/me/2fa/totp/setup")]
+pub async fn setup_totp_admin(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, admin: AdminUser) -> Result<HttpResponse, AppError> {
+    let res = svc::setup_totp(pool.get_ref(), cfg.get_ref(), Role::Admin, admin.0).await?;
+    Ok(resp::ok(res))
+}
+
+#[post("/me/2fa/totp/confirm")]
+pub async fn confirm_totp_admin(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, admin: AdminUser, payload: web::Json<TotpCodePayload>) -> Result<HttpResponse, AppError> {
+    let res = svc::confirm_totp(pool.get_ref(), cfg.get_ref(), Role::Admin, admin.0, &payload.code).await?;
+    Ok(resp::ok(res))
+}
+
+#[delete("/me/2fa/totp")]
+pub async fn disable_totp_admin(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, admin: AdminUser, payload: web::Json<TotpCodePayload>) -> Result<HttpResponse, AppError> {
+    svc::disable_totp(pool.get_ref(), cfg.get_ref(), Role::Admin, admin.0, &payload.code).await?;
+    Ok(resp::message("Two-factor authentication disabled"))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(status_admin)
+        .service(setup_totp_admin)
+        .service(confirm_totp_admin)
+        .service(disable_totp_admin);
+}
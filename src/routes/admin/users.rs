@@ -2,6 +2,7 @@ use actix_web::{delete, get, post, put, web, HttpResponse};
 use uuid::Uuid;
 
 use crate::auth::AdminUser;
+use crate::config::AppConfig;
 use crate::db::DbPool;
 use crate::errors::AppError;
 use crate::services::admin_user_service as svc;
@@ -31,9 +32,10 @@ pub async fn get_user(
 pub async fn create_user(
     _admin: AdminUser,
     pool: web::Data<DbPool>,
+    cfg: web::Data<AppConfig>,
     payload: web::Json<svc::CreateUserReq>,
 ) -> Result<HttpResponse, AppError> {
-    let rec = svc::create(pool.get_ref(), payload.into_inner()).await?;
+    let rec = svc::create(pool.get_ref(), cfg.get_ref(), payload.into_inner()).await?;
     Ok(resp::created(rec))
 }
 
@@ -42,10 +44,11 @@ pub async fn update_user(
     _admin: AdminUser,
     pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    cfg: web::Data<AppConfig>,
     payload: web::Json<svc::UpdateUserReq>,
 ) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
-    let rec = svc::update(pool.get_ref(), id, payload.into_inner()).await?;
+    let rec = svc::update(pool.get_ref(), cfg.get_ref(), id, payload.into_inner()).await?;
     Ok(resp::ok(rec))
 }
 
@@ -0,0 +1,35 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use uuid::Uuid;
+
+use crate::auth::UserSession;
+use crate::db::DbPool;
+use crate::dto::api_token::CreateApiToken;
+use crate::errors::AppError;
+use crate::services::api_token_service as svc;
+use crate::response as resp;
+
+// Managing tokens needs a real login (UserSession), so a token cannot mint more tokens.
+
+#[get("/me/tokens")]
+pub async fn list_tokens(pool: web::Data<DbPool>, session: UserSession) -> Result<HttpResponse, AppError> {
+    let rows = svc::list(pool.get_ref(), session.user_id).await?;
+    Ok(resp::ok(rows))
+}
+
+#[post("/me/tokens")]
+pub async fn create_token(pool: web::Data<DbPool>, session: UserSession, payload: web::Json<CreateApiToken>) -> Result<HttpResponse, AppError> {
+    let res = svc::create(pool.get_ref(), session.user_id, payload.into_inner()).await?;
+    Ok(resp::created(res))
+}
+
+#[delete("/me/tokens/{id}")]
+pub async fn revoke_token(pool: web::Data<DbPool>, session: UserSession, path: web::Path<Uuid>) -> Result<HttpResponse, AppError> {
+    svc::revoke(pool.get_ref(), session.user_id, path.into_inner()).await?;
+    Ok(resp::message("Token revoked"))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_tokens)
+        .service(create_token)
+        .service(revoke_token);
+}
@@ -1,20 +1,23 @@
 use actix_web::{get, post, web, HttpResponse};
+use crate::client::ClientInfo;
 use crate::config::AppConfig;
 use crate::db::DbPool;
 use crate::dto::auth::*;
 use crate::errors::AppError;
 use crate::services::auth_service as svc;
+use crate::services::google::GoogleVerifier;
+use crate::services::oidc::{self, OidcRegistry};
 use crate::response as resp;
 
 #[post("/auth/register")]
-pub async fn register(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, payload: web::Json<RegisterRequest>) -> Result<HttpResponse, AppError> {
-    let res = svc::register(pool.get_ref(), cfg.get_ref(), payload.into_inner()).await?;
+pub async fn register(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<RegisterRequest>) -> Result<HttpResponse, AppError> {
+    let res = svc::register(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
     Ok(resp::ok(res))
 }
 
 #[post("/auth/login")]
-pub async fn login(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, payload: web::Json<LoginRequest>) -> Result<HttpResponse, AppError> {
-    let res = svc::login(pool.get_ref(), cfg.get_ref(), payload.into_inner()).await?;
+pub async fn login(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<LoginRequest>) -> Result<HttpResponse, AppError> {
+    let res = svc::login(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
     Ok(resp::ok(res))
 }
 
@@ -25,55 +28,106 @@ pub async fn me(pool: web::Data<DbPool>, user: crate::auth::AuthUser) -> Result<
 }
 
 #[actix_web::delete("/me")]
-pub async fn delete_me(pool: web::Data<DbPool>, user: crate::auth::AuthUser) -> Result<HttpResponse, AppError> {
-    svc::delete_me(pool.get_ref(), user.0).await?;
+pub async fn delete_me(pool: web::Data<DbPool>, client: ClientInfo, user: crate::auth::AuthUser) -> Result<HttpResponse, AppError> {
+    svc::delete_me(pool.get_ref(), &client, user.0).await?;
     Ok(resp::message("Account deleted"))
 }
 
 #[post("/auth/request-otp")]
-pub async fn request_otp(pool: web::Data<DbPool>, payload: web::Json<RequestOtpPayload>) -> Result<HttpResponse, AppError> {
-    svc::request_otp(pool.get_ref(), payload.into_inner()).await?;
+pub async fn request_otp(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<RequestOtpPayload>) -> Result<HttpResponse, AppError> {
+    svc::request_otp(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
     Ok(resp::message("OTP sent"))
 }
 
 #[post("/auth/verify-otp")]
-pub async fn verify_otp(pool: web::Data<DbPool>, payload: web::Json<VerifyOtpPayload>) -> Result<HttpResponse, AppError> {
-    svc::verify_otp(pool.get_ref(), payload.into_inner()).await?;
+pub async fn verify_otp(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<VerifyOtpPayload>) -> Result<HttpResponse, AppError> {
+    svc::verify_otp(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
     Ok(resp::message("Verified"))
 }
 
+#[post("/auth/passwordless/request")]
+pub async fn request_login_code(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<RequestOtpPayload>) -> Result<HttpResponse, AppError> {
+    svc::request_login_code(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
+    Ok(resp::message("If the account exists, a sign-in code has been sent"))
+}
+
+#[post("/auth/passwordless/verify")]
+pub async fn login_with_code(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<VerifyOtpPayload>) -> Result<HttpResponse, AppError> {
+    let res = svc::login_with_code(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
+#[post("/auth/passwordless/link")]
+pub async fn login_with_link(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<MagicLinkRequest>) -> Result<HttpResponse, AppError> {
+    let res = svc::login_with_link(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
 #[post("/auth/forgot-password")]
-pub async fn forgot_password(pool: web::Data<DbPool>, payload: web::Json<RequestOtpPayload>) -> Result<HttpResponse, AppError> {
-    svc::forgot_password(pool.get_ref(), payload.into_inner()).await?;
+pub async fn forgot_password(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<RequestOtpPayload>) -> Result<HttpResponse, AppError> {
+    svc::forgot_password(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
     Ok(resp::message("Reset OTP sent"))
 }
 
 #[post("/auth/reset-password")]
-pub async fn reset_password(pool: web::Data<DbPool>, payload: web::Json<ResetPasswordPayload>) -> Result<HttpResponse, AppError> {
-    svc::reset_password(pool.get_ref(), payload.into_inner()).await?;
+pub async fn reset_password(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<ResetPasswordPayload>) -> Result<HttpResponse, AppError> {
+    svc::reset_password(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
     Ok(resp::message("Password reset"))
 }
 
+#[post("/auth/2fa/verify")]
+pub async fn verify_mfa(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<MfaVerifyRequest>) -> Result<HttpResponse, AppError> {
+    let res = svc::verify_mfa(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
+#[post("/auth/refresh")]
+pub async fn refresh(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, payload: web::Json<RefreshRequest>) -> Result<HttpResponse, AppError> {
+    let res = svc::refresh(pool.get_ref(), cfg.get_ref(), payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
 #[post("/auth/logout")]
-pub async fn logout(_user: crate::auth::AuthUser) -> Result<HttpResponse, AppError> {
+pub async fn logout(pool: web::Data<DbPool>, client: ClientInfo, session: crate::auth::UserSession) -> Result<HttpResponse, AppError> {
+    svc::logout(pool.get_ref(), &client, session.user_id, session.session_id).await?;
     Ok(resp::message("Logged out"))
 }
 
 #[post("/auth/google")]
-pub async fn google_login(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, payload: web::Json<GoogleLoginRequest>) -> Result<HttpResponse, AppError> {
-    let res = svc::google_login(pool.get_ref(), cfg.get_ref(), payload.into_inner()).await?;
+pub async fn google_login(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, verifier: web::Data<GoogleVerifier>, client: ClientInfo, payload: web::Json<GoogleLoginRequest>) -> Result<HttpResponse, AppError> {
+    let res = svc::google_login(pool.get_ref(), cfg.get_ref(), verifier.get_ref(), &client, payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
+#[get("/auth/oidc/providers")]
+pub async fn oidc_providers(pool: web::Data<DbPool>) -> Result<HttpResponse, AppError> {
+    let res = oidc::list_providers(pool.get_ref()).await?;
+    Ok(resp::ok(res))
+}
+
+#[post("/auth/oidc/{provider}")]
+pub async fn oidc_login(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, registry: web::Data<OidcRegistry>, client: ClientInfo, path: web::Path<String>, payload: web::Json<OidcLoginRequest>) -> Result<HttpResponse, AppError> {
+    let provider = path.into_inner();
+    let res = svc::oidc_login(pool.get_ref(), cfg.get_ref(), registry.get_ref(), &client, &provider, payload.into_inner()).await?;
     Ok(resp::ok(res))
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(register)
         .service(login)
+        .service(verify_mfa)
         .service(me)
         .service(delete_me)
         .service(request_otp)
         .service(verify_otp)
+        .service(request_login_code)
+        .service(login_with_code)
+        .service(login_with_link)
         .service(google_login)
+        .service(oidc_providers)
+        .service(oidc_login)
         .service(forgot_password)
         .service(reset_password)
+        .service(refresh)
         .service(logout);
 }
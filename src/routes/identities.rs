@@ -0,0 +1,47 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::auth::LinkIdentityRequest;
+use crate::errors::AppError;
+use crate::services::google::GoogleVerifier;
+use crate::services::identity_service as svc;
+use crate::services::oidc::OidcRegistry;
+use crate::response as resp;
+
+#[get("/me/identities")]
+pub async fn list_identities(pool: web::Data<DbPool>, user: AuthUser) -> Result<HttpResponse, AppError> {
+    let rows = svc::list(pool.get_ref(), user.0).await?;
+    Ok(resp::ok(rows))
+}
+
+#[post("/me/identities")]
+pub async fn link_identity(
+    pool: web::Data<DbPool>,
+    cfg: web::Data<AppConfig>,
+    google: web::Data<GoogleVerifier>,
+    registry: web::Data<OidcRegistry>,
+    client: ClientInfo,
+    user: AuthUser,
+    payload: web::Json<LinkIdentityRequest>,
+) -> Result<HttpResponse, AppError> {
+    let payload = payload.into_inner();
+    let identity = svc::verify(pool.get_ref(), cfg.get_ref(), google.get_ref(), registry.get_ref(), &payload.provider, &payload.id_token).await?;
+    let linked = svc::link(pool.get_ref(), &client, user.0, &payload.provider, &identity).await?;
+    Ok(resp::created(linked))
+}
+
+#[delete("/me/identities/{id}")]
+pub async fn unlink_identity(pool: web::Data<DbPool>, client: ClientInfo, user: AuthUser, path: web::Path<Uuid>) -> Result<HttpResponse, AppError> {
+    svc::unlink(pool.get_ref(), &client, user.0, path.into_inner()).await?;
+    Ok(resp::message("Identity unlinked"))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_identities)
+        .service(link_identity)
+        .service(unlink_identity);
+}
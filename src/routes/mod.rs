@@ -1,18 +1,40 @@
 use actix_web::web;
 
 mod health;
+mod well_known;
 pub mod auth;
+pub mod account;
+pub mod sessions;
+pub mod identities;
+pub mod passkeys;
+pub mod api_tokens;
+pub mod two_factor;
+pub mod security_events;
 pub mod categories;
 pub mod transactions;
+pub mod money_accounts;
+pub mod transfers;
+pub mod recurring;
 pub mod summary;
 pub mod admin;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.configure(well_known::config);
     cfg.service(web::scope("/api")
         .configure(health::config)
         .configure(auth::config)
+        .configure(account::config)
+        .configure(sessions::config)
+        .configure(identities::config)
+        .configure(passkeys::config)
+        .configure(api_tokens::config)
+        .configure(two_factor::config)
+        .configure(security_events::config)
         .configure(categories::config)
         .configure(transactions::config)
+        .configure(money_accounts::config)
+        .configure(transfers::config)
+        .configure(recurring::config)
         .configure(summary::config)
         .configure(admin::config));
 }
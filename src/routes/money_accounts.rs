@@ -0,0 +1,80 @@
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::dto::money_account::{AccountListQuery, BalanceQuery, CreateMoneyAccount, UpdateMoneyAccount};
+use crate::services::money_account_service as svc;
+use crate::response as resp;
+
+#[get("/accounts")]
+pub async fn list_accounts(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    query: web::Query<AccountListQuery>,
+) -> Result<HttpResponse, AppError> {
+    let rows = svc::list(pool.get_ref(), user.0, query.into_inner()).await?;
+    Ok(resp::ok(rows))
+}
+
+#[post("/accounts")]
+pub async fn create_account(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    payload: web::Json<CreateMoneyAccount>,
+) -> Result<HttpResponse, AppError> {
+    let row = svc::create(pool.get_ref(), user.0, payload.into_inner()).await?;
+    Ok(resp::created(row))
+}
+
+#[get("/accounts/{id}")]
+pub async fn get_account(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let row = svc::get(pool.get_ref(), user.0, path.into_inner()).await?;
+    Ok(resp::ok(row))
+}
+
+#[get("/accounts/{id}/balance")]
+pub async fn account_balance(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+    query: web::Query<BalanceQuery>,
+) -> Result<HttpResponse, AppError> {
+    let res = svc::balance(pool.get_ref(), user.0, path.into_inner(), query.as_of).await?;
+    Ok(resp::ok(res))
+}
+
+#[put("/accounts/{id}")]
+pub async fn update_account(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+    payload: web::Json<UpdateMoneyAccount>,
+) -> Result<HttpResponse, AppError> {
+    let row = svc::update(pool.get_ref(), user.0, path.into_inner(), payload.into_inner()).await?;
+    Ok(resp::ok(row))
+}
+
+#[delete("/accounts/{id}")]
+pub async fn delete_account(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    svc::delete(pool.get_ref(), user.0, path.into_inner()).await?;
+    Ok(resp::message("Account deleted"))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_accounts)
+        .service(create_account)
+        .service(get_account)
+        .service(account_balance)
+        .service(update_account)
+        .service(delete_account);
+}
@@ -0,0 +1,62 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use uuid::Uuid;
+
+use crate::auth::UserSession;
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::passkey::{FinishLogin, FinishRegistration};
+use crate::errors::AppError;
+use crate::services::{auth_service, passkey_service as svc};
+use crate::response as resp;
+
+// Adding or removing a passkey needs a real login (UserSession), not a personal access token.
+#[get("/me/passkeys")]
+pub async fn list_passkeys(pool: web::Data<DbPool>, session: UserSession) -> Result<HttpResponse, AppError> {
+    let rows = svc::list(pool.get_ref(), session.user_id).await?;
+    Ok(resp::ok(rows))
+}
+
+#[post("/me/passkeys/register/start")]
+pub async fn start_registration(pool: web::Data<DbPool>, session: UserSession) -> Result<HttpResponse, AppError> {
+    let options = svc::start_registration(pool.get_ref(), session.user_id).await?;
+    Ok(resp::ok(options))
+}
+
+#[post("/me/passkeys/register/finish")]
+pub async fn finish_registration(
+    pool: web::Data<DbPool>,
+    client: ClientInfo,
+    session: UserSession,
+    payload: web::Json<FinishRegistration>,
+) -> Result<HttpResponse, AppError> {
+    let passkey = svc::finish_registration(pool.get_ref(), &client, session.user_id, payload.into_inner()).await?;
+    Ok(resp::created(passkey))
+}
+
+#[delete("/me/passkeys/{id}")]
+pub async fn delete_passkey(pool: web::Data<DbPool>, client: ClientInfo, session: UserSession, path: web::Path<Uuid>) -> Result<HttpResponse, AppError> {
+    svc::delete(pool.get_ref(), &client, session.user_id, path.into_inner()).await?;
+    Ok(resp::message("Passkey removed"))
+}
+
+#[post("/auth/passkeys/start")]
+pub async fn start_login(pool: web::Data<DbPool>) -> Result<HttpResponse, AppError> {
+    let options = svc::start_login(pool.get_ref()).await?;
+    Ok(resp::ok(options))
+}
+
+#[post("/auth/passkeys/finish")]
+pub async fn finish_login(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, client: ClientInfo, payload: web::Json<FinishLogin>) -> Result<HttpResponse, AppError> {
+    let res = auth_service::passkey_login(pool.get_ref(), cfg.get_ref(), &client, payload.into_inner()).await?;
+    Ok(resp::ok(res))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_passkeys)
+        .service(start_registration)
+        .service(finish_registration)
+        .service(delete_passkey)
+        .service(start_login)
+        .service(finish_login);
+}
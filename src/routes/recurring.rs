@@ -0,0 +1,104 @@
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::dto::recurring::{CreateRecurring, OccurrenceQuery, SkipOccurrence, UpdateRecurring};
+use crate::services::recurring_service as svc;
+use crate::response as resp;
+
+#[get("/recurring")]
+pub async fn list_recurring(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+) -> Result<HttpResponse, AppError> {
+    let rows = svc::list(pool.get_ref(), user.0).await?;
+    Ok(resp::ok(rows))
+}
+
+#[post("/recurring")]
+pub async fn create_recurring(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    payload: web::Json<CreateRecurring>,
+) -> Result<HttpResponse, AppError> {
+    let rec = svc::create(pool.get_ref(), user.0, payload.into_inner()).await?;
+    Ok(resp::created(rec))
+}
+
+#[get("/recurring/{id}")]
+pub async fn get_recurring(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let rec = svc::get(pool.get_ref(), user.0, path.into_inner()).await?;
+    Ok(resp::ok(rec))
+}
+
+#[put("/recurring/{id}")]
+pub async fn update_recurring(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+    payload: web::Json<UpdateRecurring>,
+) -> Result<HttpResponse, AppError> {
+    let rec = svc::update(pool.get_ref(), user.0, path.into_inner(), payload.into_inner()).await?;
+    Ok(resp::ok(rec))
+}
+
+#[delete("/recurring/{id}")]
+pub async fn delete_recurring(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    svc::delete(pool.get_ref(), user.0, path.into_inner()).await?;
+    Ok(resp::message("Recurring transaction deleted"))
+}
+
+#[get("/recurring/{id}/occurrences")]
+pub async fn preview_occurrences(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+    query: web::Query<OccurrenceQuery>,
+) -> Result<HttpResponse, AppError> {
+    let rows = svc::preview(pool.get_ref(), user.0, path.into_inner(), query.into_inner()).await?;
+    Ok(resp::ok(rows))
+}
+
+#[post("/recurring/{id}/skips")]
+pub async fn skip_occurrence(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+    payload: web::Json<SkipOccurrence>,
+) -> Result<HttpResponse, AppError> {
+    svc::skip(pool.get_ref(), user.0, path.into_inner(), payload.occurrence_date).await?;
+    Ok(resp::message("Occurrence skipped"))
+}
+
+#[delete("/recurring/{id}/skips/{date}")]
+pub async fn unskip_occurrence(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<(Uuid, NaiveDate)>,
+) -> Result<HttpResponse, AppError> {
+    let (id, date) = path.into_inner();
+    svc::unskip(pool.get_ref(), user.0, id, date).await?;
+    Ok(resp::message("Occurrence restored"))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_recurring)
+        .service(create_recurring)
+        .service(get_recurring)
+        .service(update_recurring)
+        .service(delete_recurring)
+        .service(preview_occurrences)
+        .service(skip_occurrence)
+        .service(unskip_occurrence);
+}
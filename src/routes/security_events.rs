@@ -0,0 +1,22 @@
+use actix_web::{get, web, HttpResponse};
+
+use crate::auth::UserSession;
+use crate::db::DbPool;
+use crate::dto::auth_event::EventPageQuery;
+use crate::errors::AppError;
+use crate::services::audit_service as svc;
+use crate::response as resp;
+
+#[get("/me/security-events")]
+pub async fn list_security_events(
+    pool: web::Data<DbPool>,
+    session: UserSession,
+    query: web::Query<EventPageQuery>,
+) -> Result<HttpResponse, AppError> {
+    let rows = svc::list_for_user(pool.get_ref(), session.user_id, query.into_inner()).await?;
+    Ok(resp::ok(rows))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_security_events);
+}
@@ -0,0 +1,69 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use uuid::Uuid;
+
+use crate::auth::{Role, UserSession};
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::auth::RevokeSessionRequest;
+use crate::errors::AppError;
+use crate::services::device_service;
+use crate::services::session_service as svc;
+use crate::response as resp;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RevokeAllQuery {
+    #[serde(default)]
+    pub keep_current: bool,
+}
+
+#[get("/me/sessions")]
+pub async fn list_sessions(
+    pool: web::Data<DbPool>,
+    session: UserSession,
+) -> Result<HttpResponse, AppError> {
+    let rows = svc::list_for_user(pool.get_ref(), session.user_id, session.session_id).await?;
+    Ok(resp::ok(rows))
+}
+
+#[delete("/me/sessions/{id}")]
+pub async fn revoke_session(
+    pool: web::Data<DbPool>,
+    session: UserSession,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+    svc::revoke(pool.get_ref(), Role::User, session.user_id, id).await?;
+    Ok(resp::message("Session revoked"))
+}
+
+// "Sign out everywhere"
+#[delete("/me/sessions")]
+pub async fn revoke_all_sessions(
+    pool: web::Data<DbPool>,
+    session: UserSession,
+    query: web::Query<RevokeAllQuery>,
+) -> Result<HttpResponse, AppError> {
+    let keep = if query.keep_current { Some(session.session_id) } else { None };
+    let count = svc::revoke_all_for_user(pool.get_ref(), session.user_id, keep).await?;
+    Ok(resp::message(format!("{} session(s) revoked", count)))
+}
+
+// Target of the link in new-login alert emails; needs no login
+#[post("/auth/sessions/revoke")]
+pub async fn revoke_from_alert(
+    pool: web::Data<DbPool>,
+    cfg: web::Data<AppConfig>,
+    client: ClientInfo,
+    payload: web::Json<RevokeSessionRequest>,
+) -> Result<HttpResponse, AppError> {
+    device_service::revoke_from_alert(pool.get_ref(), cfg.get_ref(), &client, &payload.token).await?;
+    Ok(resp::message("Session signed out. If this was not you, change your password now."))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_sessions)
+        .service(revoke_session)
+        .service(revoke_all_sessions)
+        .service(revoke_from_alert);
+}
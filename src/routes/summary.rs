@@ -13,6 +13,8 @@ use crate::response as resp;
 pub struct MonthlySummary {
     pub year: i32,
     pub month: u32,
+    /// The user's preferred currency, which every amount below is converted to.
+    pub currency: String,
     pub total_income: Decimal,
     pub total_expense: Decimal,
     pub balance: Decimal,
@@ -25,7 +27,7 @@ pub async fn monthly_summary(
     user: AuthUser,
     query: web::Query<SummaryQuery>,
 ) -> Result<HttpResponse, AppError> {
-    let res = svc::monthly_summary(pool.get_ref(), user.0, query.year, query.month).await?;
+    let res = svc::monthly_summary(pool.get_ref(), user.0, query.year, query.month, query.account_id).await?;
     Ok(resp::ok(res))
 }
 
@@ -33,6 +35,7 @@ pub async fn monthly_summary(
 pub struct SummaryQuery {
     pub year: i32,
     pub month: u32,
+    pub account_id: Option<uuid::Uuid>,
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
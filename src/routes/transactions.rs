@@ -1,10 +1,13 @@
-use actix_web::{delete, get, post, put, web, HttpResponse};
+use actix_web::http::header::{self, ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse};
 use uuid::Uuid;
 
 use crate::auth::AuthUser;
 use crate::db::DbPool;
 use crate::errors::AppError;
+use crate::dto::attachment::UploadQuery;
 use crate::dto::transaction::{CreateTransaction, TxnQuery, UpdateTransaction};
+use crate::services::attachment_service;
 use crate::services::transaction_service as svc;
 use crate::response as resp;
 
@@ -52,9 +55,74 @@ pub async fn delete_transaction(
     Ok(resp::message("Transaction deleted"))
 }
 
+#[get("/transactions/{id}/attachments")]
+pub async fn list_attachments(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let rows = attachment_service::list(pool.get_ref(), user.0, path.into_inner()).await?;
+    Ok(resp::ok(rows))
+}
+
+/// The request body is the file itself, typed by its `Content-Type` header.
+#[post("/transactions/{id}/attachments")]
+pub async fn upload_attachment(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+    query: web::Query<UploadQuery>,
+    req: HttpRequest,
+    body: web::Payload,
+) -> Result<HttpResponse, AppError> {
+    let limit = attachment_service::max_bytes(pool.get_ref()).await?;
+    let content = body
+        .to_bytes_limited(limit)
+        .await
+        .map_err(|_| AppError::BadRequest(format!("Attachment must be at most {} bytes", limit)))?
+        .map_err(|_| AppError::BadRequest("Could not read the uploaded file".into()))?;
+    let content_type = req.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    let rec = attachment_service::upload(pool.get_ref(), user.0, path.into_inner(), query.filename.as_deref(), content_type, &content).await?;
+    Ok(resp::created(rec))
+}
+
+#[get("/transactions/{id}/attachments/{attachment_id}")]
+pub async fn download_attachment(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    let (id, attachment_id) = path.into_inner();
+    let file = attachment_service::download(pool.get_ref(), user.0, id, attachment_id).await?;
+    // Always a download, so an uploaded HTML file cannot run in the API's origin
+    Ok(HttpResponse::Ok()
+        .content_type(file.content_type)
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(file.filename)],
+        })
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .body(file.content))
+}
+
+#[delete("/transactions/{id}/attachments/{attachment_id}")]
+pub async fn delete_attachment(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    let (id, attachment_id) = path.into_inner();
+    attachment_service::delete(pool.get_ref(), user.0, id, attachment_id).await?;
+    Ok(resp::message("Attachment deleted"))
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(list_transactions)
         .service(create_transaction)
         .service(update_transaction)
-        .service(delete_transaction);
+        .service(delete_transaction)
+        .service(list_attachments)
+        .service(upload_attachment)
+        .service(download_attachment)
+        .service(delete_attachment);
 }
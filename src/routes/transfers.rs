@@ -0,0 +1,68 @@
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::dto::transfer::{CreateTransfer, TransferQuery, UpdateTransfer};
+use crate::services::transfer_service as svc;
+use crate::response as resp;
+
+#[get("/transfers")]
+pub async fn list_transfers(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    query: web::Query<TransferQuery>,
+) -> Result<HttpResponse, AppError> {
+    let rows = svc::list(pool.get_ref(), user.0, query.into_inner()).await?;
+    Ok(resp::ok(rows))
+}
+
+#[post("/transfers")]
+pub async fn create_transfer(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    payload: web::Json<CreateTransfer>,
+) -> Result<HttpResponse, AppError> {
+    let rec = svc::create(pool.get_ref(), user.0, payload.into_inner()).await?;
+    Ok(resp::created(rec))
+}
+
+#[get("/transfers/{id}")]
+pub async fn get_transfer(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let rec = svc::get(pool.get_ref(), user.0, path.into_inner()).await?;
+    Ok(resp::ok(rec))
+}
+
+#[put("/transfers/{id}")]
+pub async fn update_transfer(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+    payload: web::Json<UpdateTransfer>,
+) -> Result<HttpResponse, AppError> {
+    let updated = svc::update(pool.get_ref(), user.0, path.into_inner(), payload.into_inner()).await?;
+    Ok(resp::ok(updated))
+}
+
+#[delete("/transfers/{id}")]
+pub async fn delete_transfer(
+    pool: web::Data<DbPool>,
+    user: AuthUser,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    svc::delete(pool.get_ref(), user.0, path.into_inner()).await?;
+    Ok(resp::message("Transfer deleted"))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_transfers)
+        .service(create_transfer)
+        .service(get_transfer)
+        .service(update_transfer)
+        .service(delete_transfer);
+}
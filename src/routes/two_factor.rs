@@ -0,0 +1,40 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+
+use crate::auth::{AuthUser, Role};
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::two_factor::TotpCodePayload;
+use crate::errors::AppError;
+use crate::services::two_factor_service as svc;
+use crate::response as resp;
+
+#[get("/me/2fa")]
+pub async fn status(pool: web::Data<DbPool>, user: AuthUser) -> Result<HttpResponse, AppError> {
+    let res = svc::status(pool.get_ref(), Role::User, user.0).await?;
+    Ok(resp::ok(res))
+}
+
+#[post("/me/2fa/totp/setup")]
+pub async fn setup_totp(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, user: AuthUser) -> Result<HttpResponse, AppError> {
+    let res = svc::setup_totp(pool.get_ref(), cfg.get_ref(), Role::User, user.0).await?;
+    Ok(resp::ok(res))
+}
+
+#[post("/me/2fa/totp/confirm")]
+pub async fn confirm_totp(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, user: AuthUser, payload: web::Json<TotpCodePayload>) -> Result<HttpResponse, AppError> {
+    let res = svc::confirm_totp(pool.get_ref(), cfg.get_ref(), Role::User, user.0, &payload.code).await?;
+    Ok(resp::ok(res))
+}
+
+#[delete("/me/2fa/totp")]
+pub async fn disable_totp(pool: web::Data<DbPool>, cfg: web::Data<AppConfig>, user: AuthUser, payload: web::Json<TotpCodePayload>) -> Result<HttpResponse, AppError> {
+    svc::disable_totp(pool.get_ref(), cfg.get_ref(), Role::User, user.0, &payload.code).await?;
+    Ok(resp::message("Two-factor authentication disabled"))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(status)
+        .service(setup_totp)
+        .service(confirm_totp)
+        .service(disable_totp);
+}
@@ -0,0 +1,16 @@
+use actix_web::{get, http::header, web, HttpResponse, Responder};
+
+use crate::config::AppConfig;
+
+/// Public keys for our access tokens, in the standard JWKS shape (not the `success`
+/// envelope) so other services can point a JWT library straight at it.
+#[get("/.well-known/jwks.json")]
+async fn jwks(cfg: web::Data<AppConfig>) -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+        .json(cfg.jwt_keys.jwks())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(jwks);
+}
@@ -0,0 +1,163 @@
+use uuid::Uuid;
+
+use crate::auth::{hash_password, verify_password};
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::account::{ChangeEmailRequest, ChangePasswordRequest, ConfirmEmailChange, UpdateProfile};
+use crate::errors::AppError;
+use crate::mailer;
+use crate::models::user::PublicUser;
+use crate::repositories::{otp_repo, user_repo};
+use crate::services::{currency, otp_service, password_policy, session_service};
+use crate::services::audit_service::{self, Event, EventKind};
+use crate::services::throttle_service::{self, Action};
+
+// Deliberately loose; the confirmation code is the real check.
+fn is_plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !email.contains(char::is_whitespace),
+        None => false,
+    }
+}
+
+const MAX_NAME_LEN: usize = 100;
+
+// Shape check for BCP 47 tags such as "en", "id-ID" or "zh-Hant-TW"
+fn is_locale_tag(tag: &str) -> bool {
+    let mut parts = tag.split('-');
+    let lang_ok = parts.next().is_some_and(|l| (2..=3).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()));
+    lang_ok && tag.len() <= 35 && parts.all(|p| (1..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+pub async fn update_profile(pool: &DbPool, user_id: Uuid, payload: UpdateProfile) -> Result<PublicUser, AppError> {
+    let current = user_repo::get_by_id(pool, user_id).await?;
+    let name = match payload.name {
+        Some(n) => {
+            let n = n.trim().to_string();
+            if n.is_empty() || n.chars().count() > MAX_NAME_LEN {
+                return Err(AppError::BadRequest(format!("Name must be 1-{} characters", MAX_NAME_LEN)));
+            }
+            n
+        }
+        None => current.name,
+    };
+    let currency = match payload.preferred_currency {
+        Some(c) => currency::parse_code("preferred_currency", &c)?,
+        None => current.preferred_currency,
+    };
+    let locale = match payload.locale {
+        Some(l) => {
+            let l = l.trim().to_string();
+            if !is_locale_tag(&l) {
+                return Err(AppError::BadRequest("locale must be a language tag such as \"id-ID\"".into()));
+            }
+            l
+        }
+        None => current.locale,
+    };
+    let timezone = match payload.timezone {
+        Some(tz) => {
+            let tz = tz.trim().to_string();
+            if !user_repo::is_known_timezone(pool, &tz).await? {
+                return Err(AppError::BadRequest("timezone must be an IANA zone such as \"Asia/Jakarta\"".into()));
+            }
+            tz
+        }
+        None => current.timezone,
+    };
+    Ok(user_repo::update_profile(pool, user_id, &name, &currency, &locale, &timezone).await?.into())
+}
+
+/// Changes (or, for accounts created through Google/OIDC, sets) the password and signs
+/// out every other session. Returns how many sessions were revoked.
+pub async fn change_password(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, user_id: Uuid, session_id: Uuid, payload: ChangePasswordRequest) -> Result<u64, AppError> {
+    let user = user_repo::get_by_id(pool, user_id).await?;
+    let event = Event::new(EventKind::PasswordChanged, client).user(user_id).email(&user.email);
+    if !user.password_hash.is_empty() {
+        let account = user_id.to_string();
+        throttle_service::check(pool, Action::PasswordChange, &account, client).await?;
+        let password = payload.current_password.as_deref().unwrap_or_default();
+        if !verify_password(password, &user.password_hash)? {
+            throttle_service::record_failure(pool, Action::PasswordChange, &account, client).await?;
+            let err = AppError::BadRequest("Current password is incorrect".into());
+            audit_service::failure(pool, event, &err).await;
+            return Err(err);
+        }
+        throttle_service::record_success(pool, Action::PasswordChange, &account).await?;
+    }
+    password_policy::validate(pool, cfg, "new_password", &payload.new_password, &user.email, &user.name).await?;
+    let new_hash = hash_password(&payload.new_password, cfg)?;
+    sqlx::query("UPDATE users SET password_hash=$1 WHERE id=$2")
+        .bind(new_hash)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Db(e.to_string()))?;
+    let detail = if user.password_hash.is_empty() { "password set" } else { "password changed" };
+    audit_service::success(pool, event.detail(detail)).await;
+    session_service::revoke_all_for_user(pool, user_id, Some(session_id)).await
+}
+
+/// Sends a confirmation code to the new address. Nothing changes until it is confirmed.
+/// Accounts with a password must re-enter it; passwordless (Google/OIDC-only) accounts
+/// rely on the code alone.
+pub async fn request_email_change(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, user_id: Uuid, payload: ChangeEmailRequest) -> Result<(), AppError> {
+    let user = user_repo::get_by_id(pool, user_id).await?;
+    let new_email = payload.new_email.trim().to_lowercase();
+    if !is_plausible_email(&new_email) {
+        return Err(AppError::BadRequest("Invalid email address".into()));
+    }
+    if new_email == user.email {
+        return Err(AppError::BadRequest("That is already your email address".into()));
+    }
+    if !user.password_hash.is_empty() {
+        let account = user_id.to_string();
+        throttle_service::check(pool, Action::EmailChange, &account, client).await?;
+        let password = payload.current_password.as_deref().unwrap_or_default();
+        if !verify_password(password, &user.password_hash)? {
+            throttle_service::record_failure(pool, Action::EmailChange, &account, client).await?;
+            return Err(AppError::BadRequest("Current password is incorrect".into()));
+        }
+        throttle_service::record_success(pool, Action::EmailChange, &account).await?;
+    }
+    if user_repo::get_by_email(pool, &new_email).await?.is_some() {
+        return Err(AppError::Conflict("Email already registered".into()));
+    }
+    let issued = otp_service::issue_email_change(pool, cfg, user_id, &new_email).await;
+    let event = Event::new(EventKind::EmailChangeRequested, client).user(user_id).email(&user.email).detail(new_email.as_str());
+    audit_service::outcome(pool, event, issued).await
+}
+
+/// Swaps in the new address and notifies the old one. Linked Google/OIDC identities are
+/// matched by provider subject, not email, so they keep working after the change.
+pub async fn confirm_email_change(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, user_id: Uuid, payload: ConfirmEmailChange) -> Result<PublicUser, AppError> {
+    let otp_id = otp_service::consume(pool, cfg, client, Action::EmailChange, user_id, &payload.code, otp_service::EMAIL_CHANGE).await?;
+    let new_email = otp_repo::target_email(pool, otp_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Invalid or expired code".into()))?;
+    let old_email = user_repo::get_by_id(pool, user_id).await?.email;
+
+    let mut tx = pool.begin().await.map_err(|e| AppError::Db(e.to_string()))?;
+    if !otp_repo::mark_used(&mut tx, otp_id).await? {
+        return Err(AppError::BadRequest("Invalid or expired code".into()));
+    }
+    // The address may have been taken since the code was sent
+    sqlx::query("UPDATE users SET email=$1, is_verified=true WHERE id=$2")
+        .bind(&new_email)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| match AppError::from(e) {
+            AppError::Db(s) if s.contains("unique") => AppError::Conflict("Email already registered".into()),
+            other => other,
+        })?;
+    tx.commit().await.map_err(|e| AppError::Db(e.to_string()))?;
+    let event = Event::new(EventKind::EmailChanged, client).user(user_id).email(&new_email);
+    audit_service::success(pool, event.detail(format!("{} -> {}", old_email, new_email))).await;
+
+    if let Err(e) = mailer::send_email_changed_notice(pool, &old_email, &new_email).await {
+        log::warn!("Failed to notify {} of email change: {}", old_email, e);
+    }
+    Ok(user_repo::get_by_id(pool, user_id).await?.into())
+}
@@ -1,10 +1,14 @@
 use uuid::Uuid;
-use crate::auth::{hash_password, verify_password, create_jwt};
+use crate::auth::{hash_password, password_needs_rehash, verify_password, Role};
+use crate::client::ClientInfo;
 use crate::config::AppConfig;
 use crate::db::DbPool;
+use crate::dto::auth::{LoginOutcome, MfaVerifyRequest, RefreshRequest, TokenPair};
 use crate::errors::AppError;
 use crate::models::admin::PublicAdmin;
 use crate::repositories::admin_repo as repo;
+use crate::services::throttle_service::{self, Action};
+use crate::services::{password_policy, session_service, two_factor_service};
 
 #[derive(Debug, serde::Deserialize)]
 pub struct AdminRegisterRequest { pub name: String, pub email: String, pub password: String }
@@ -13,26 +17,62 @@ pub struct AdminRegisterRequest { pub name: String, pub email: String, pub passw
 pub struct AdminLoginRequest { pub email: String, pub password: String }
 
 #[derive(Debug, serde::Serialize)]
-pub struct AdminAuthResponse { pub token: String, pub admin: PublicAdmin }
+pub struct AdminAuthResponse { #[serde(flatten)] pub tokens: TokenPair, pub admin: PublicAdmin }
 
-pub async fn register(pool: &DbPool, cfg: &AppConfig, payload: AdminRegisterRequest, bootstrap_ok: bool) -> Result<AdminAuthResponse, AppError> {
+pub async fn register(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: AdminRegisterRequest, bootstrap_ok: bool) -> Result<AdminAuthResponse, AppError> {
     let exists = repo::count_admins(pool).await? > 0;
     if exists && !bootstrap_ok { return Err(AppError::Forbidden); }
     let email = payload.email.trim().to_lowercase();
-    let hash = hash_password(&payload.password)?;
+    password_policy::validate(pool, cfg, "password", &payload.password, &email, &payload.name).await?;
+    let hash = hash_password(&payload.password, cfg)?;
     let id = Uuid::new_v4();
     let rec = repo::insert(pool, id, &payload.name, &email, &hash).await
         .map_err(|e| match e { AppError::Db(s) if s.contains("unique") => AppError::Conflict("Admin email already exists".into()), other => other })?;
-    let token = create_jwt(rec.id, cfg)?;
-    Ok(AdminAuthResponse { token, admin: rec.into() })
+    let tokens = session_service::start(pool, cfg, Role::Admin, rec.id, client).await?;
+    Ok(AdminAuthResponse { tokens, admin: rec.into() })
 }
 
-pub async fn login(pool: &DbPool, cfg: &AppConfig, payload: AdminLoginRequest) -> Result<AdminAuthResponse, AppError> {
+pub async fn login(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: AdminLoginRequest) -> Result<LoginOutcome<AdminAuthResponse>, AppError> {
     let email = payload.email.trim().to_lowercase();
-    let admin = repo::get_by_email(pool, &email).await?.ok_or(AppError::Unauthorized)?;
-    if !verify_password(&payload.password, &admin.password_hash)? { return Err(AppError::Unauthorized); }
-    let token = create_jwt(admin.id, cfg)?;
-    Ok(AdminAuthResponse { token, admin: admin.into() })
+    throttle_service::check(pool, Action::AdminLogin, &email, client).await?;
+    let admin = match repo::get_by_email(pool, &email).await? {
+        Some(a) if verify_password(&payload.password, &a.password_hash)? => a,
+        _ => {
+            throttle_service::record_failure(pool, Action::AdminLogin, &email, client).await?;
+            return Err(AppError::Unauthorized);
+        }
+    };
+    throttle_service::record_success(pool, Action::AdminLogin, &email).await?;
+    if password_needs_rehash(&admin.password_hash, cfg) {
+        match hash_password(&payload.password, cfg) {
+            Ok(new_hash) => {
+                if let Err(e) = repo::replace_password_hash(pool, admin.id, &admin.password_hash, &new_hash).await {
+                    log::warn!("Failed to upgrade password hash for admin {}: {}", admin.id, e);
+                }
+            }
+            Err(e) => log::warn!("Failed to rehash password for admin {}: {}", admin.id, e),
+        }
+    }
+    if two_factor_service::is_enabled(pool, Role::Admin, admin.id).await? {
+        return Ok(LoginOutcome::MfaRequired(two_factor_service::challenge(cfg, Role::Admin, admin.id)?));
+    }
+    let tokens = session_service::start(pool, cfg, Role::Admin, admin.id, client).await?;
+    Ok(LoginOutcome::Authenticated(AdminAuthResponse { tokens, admin: admin.into() }))
+}
+
+pub async fn verify_mfa(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: MfaVerifyRequest) -> Result<AdminAuthResponse, AppError> {
+    let admin_id = two_factor_service::complete_challenge(pool, cfg, client, Role::Admin, &payload).await?;
+    let admin = repo::get_by_id(pool, admin_id).await?;
+    let tokens = session_service::start(pool, cfg, Role::Admin, admin.id, client).await?;
+    Ok(AdminAuthResponse { tokens, admin: admin.into() })
+}
+
+pub async fn refresh(pool: &DbPool, cfg: &AppConfig, payload: RefreshRequest) -> Result<TokenPair, AppError> {
+    session_service::refresh(pool, cfg, Role::Admin, &payload.refresh_token).await
+}
+
+pub async fn logout(pool: &DbPool, admin_id: Uuid, session_id: Uuid) -> Result<(), AppError> {
+    session_service::revoke(pool, Role::Admin, admin_id, session_id).await
 }
 
 pub async fn me(pool: &DbPool, admin_id: Uuid) -> Result<PublicAdmin, AppError> {
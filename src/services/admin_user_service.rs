@@ -1,9 +1,12 @@
 use uuid::Uuid;
+use crate::config::AppConfig;
 use crate::db::DbPool;
 use crate::errors::AppError;
 use crate::models::user::{PublicUser, User};
 use crate::repositories::user_repo as repo;
 use crate::auth::hash_password;
+use crate::mailer;
+use crate::services::password_policy;
 
 #[derive(Debug, serde::Deserialize)]
 pub struct CreateUserReq { pub name: String, pub email: String, pub password: String }
@@ -13,7 +16,7 @@ pub struct UpdateUserReq { pub name: Option<String>, pub email: Option<String>,
 
 pub async fn list(pool: &DbPool) -> Result<Vec<PublicUser>, AppError> {
     let rows = sqlx::query_as::<_, User>(
-        "SELECT id, name, email, password_hash, auth_provider, google_sub, is_verified, created_at FROM users ORDER BY created_at DESC",
+        "SELECT id, name, email, password_hash, auth_provider, is_verified, preferred_currency, locale, timezone, created_at FROM users ORDER BY created_at DESC",
     )
     .fetch_all(pool)
     .await?;
@@ -24,30 +27,50 @@ pub async fn get(pool: &DbPool, id: Uuid) -> Result<PublicUser, AppError> {
     Ok(repo::get_by_id(pool, id).await?.into())
 }
 
-pub async fn create(pool: &DbPool, payload: CreateUserReq) -> Result<PublicUser, AppError> {
+pub async fn create(pool: &DbPool, cfg: &AppConfig, payload: CreateUserReq) -> Result<PublicUser, AppError> {
     let id = Uuid::new_v4();
     let email = payload.email.trim().to_lowercase();
-    let hash = hash_password(&payload.password)?;
+    password_policy::validate(pool, cfg, "password", &payload.password, &email, &payload.name).await?;
+    let hash = hash_password(&payload.password, cfg)?;
     let rec = repo::insert_local(pool, id, &payload.name, &email, &hash).await
         .map_err(|e| match e { AppError::Db(s) if s.contains("unique") => AppError::Conflict("Email already registered".into()), other => other })?;
     Ok(rec.into())
 }
 
-pub async fn update(pool: &DbPool, id: Uuid, payload: UpdateUserReq) -> Result<PublicUser, AppError> {
+/// An email changed by an admin is unconfirmed, so the account goes back to unverified
+/// (the user verifies the new address via OTP) and the old address is told about it.
+pub async fn update(pool: &DbPool, cfg: &AppConfig, id: Uuid, payload: UpdateUserReq) -> Result<PublicUser, AppError> {
     let current = repo::get_by_id(pool, id).await?;
     let name = payload.name.unwrap_or(current.name);
-    let email = payload.email.map(|e| e.trim().to_lowercase()).unwrap_or(current.email);
-    let password_hash = if let Some(pw) = payload.password { hash_password(&pw)? } else { current.password_hash.clone() };
+    let email = payload.email.map(|e| e.trim().to_lowercase()).unwrap_or_else(|| current.email.clone());
+    let password_hash = match payload.password {
+        Some(pw) => {
+            password_policy::validate(pool, cfg, "password", &pw, &email, &name).await?;
+            hash_password(&pw, cfg)?
+        }
+        None => current.password_hash.clone(),
+    };
     let rec = sqlx::query_as::<_, User>(
-        "UPDATE users SET name=$1, email=$2, password_hash=$3 WHERE id=$4
-         RETURNING id, name, email, password_hash, auth_provider, google_sub, is_verified, created_at",
+        "UPDATE users SET name=$1, email=$2, password_hash=$3,
+             is_verified = CASE WHEN email <> $2 THEN false ELSE is_verified END
+         WHERE id=$4
+         RETURNING id, name, email, password_hash, auth_provider, is_verified, preferred_currency, locale, timezone, created_at",
     )
     .bind(name)
-    .bind(email)
+    .bind(&email)
     .bind(password_hash)
     .bind(id)
     .fetch_one(pool)
-    .await?;
+    .await
+    .map_err(|e| match AppError::from(e) {
+        AppError::Db(s) if s.contains("unique") => AppError::Conflict("Email already registered".into()),
+        other => other,
+    })?;
+    if email != current.email {
+        if let Err(e) = mailer::send_email_changed_notice(pool, &current.email, &email).await {
+            log::warn!("Failed to notify {} of email change: {}", current.email, e);
+        }
+    }
     Ok(rec.into())
 }
 
@@ -0,0 +1,158 @@
+use actix_web::http::Method;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::auth::{generate_token_secret, hash_token};
+use crate::db::DbPool;
+use crate::dto::api_token::{CreateApiToken, CreatedApiToken};
+use crate::errors::AppError;
+use crate::models::api_token::PublicApiToken;
+use crate::repositories::api_token_repo;
+
+/// Every personal access token starts with this, so the extractors can tell them from JWTs.
+pub const TOKEN_PREFIX: &str = "fbt_";
+
+/// Resources a token can be scoped to, named after their URL segment under `/api`.
+const RESOURCES: [&str; 6] = ["transactions", "categories", "accounts", "transfers", "recurring", "summary"];
+const MAX_NAME_LEN: usize = 100;
+const LAST_USED_TOUCH_INTERVAL_MINUTES: i64 = 5;
+
+// Scopes are "<resource>:read" or "<resource>:write"; write implies read.
+fn normalize_scopes(scopes: &[String]) -> Result<Vec<String>, AppError> {
+    let mut out: Vec<String> = Vec::new();
+    for raw in scopes {
+        let scope = raw.trim().to_lowercase();
+        let valid = scope
+            .split_once(':')
+            .is_some_and(|(res, access)| RESOURCES.contains(&res) && (access == "read" || access == "write"));
+        if !valid {
+            return Err(AppError::BadRequest(format!(
+                "Unknown scope '{}'; use <resource>:read or <resource>:write with resource one of {}",
+                raw,
+                RESOURCES.join(", ")
+            )));
+        }
+        if !out.contains(&scope) {
+            out.push(scope);
+        }
+    }
+    if out.is_empty() {
+        return Err(AppError::BadRequest("At least one scope is required".into()));
+    }
+    out.sort();
+    Ok(out)
+}
+
+pub async fn create(pool: &DbPool, user_id: Uuid, payload: CreateApiToken) -> Result<CreatedApiToken, AppError> {
+    let name = payload.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::BadRequest(format!("Token name must be 1-{} characters", MAX_NAME_LEN)));
+    }
+    let scopes = normalize_scopes(&payload.scopes)?;
+    if payload.expires_at.is_some_and(|t| t <= Utc::now()) {
+        return Err(AppError::BadRequest("expires_at must be in the future".into()));
+    }
+    let token = format!("{}{}", TOKEN_PREFIX, generate_token_secret());
+    let prefix: String = token.chars().take(TOKEN_PREFIX.len() + 6).collect();
+    let info = api_token_repo::create(pool, user_id, name, &hash_token(&token), &prefix, &scopes, payload.expires_at).await?;
+    Ok(CreatedApiToken { info, token })
+}
+
+pub async fn list(pool: &DbPool, user_id: Uuid) -> Result<Vec<PublicApiToken>, AppError> {
+    api_token_repo::list_active_for_user(pool, user_id).await
+}
+
+pub async fn revoke(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    if api_token_repo::revoke(pool, id, user_id).await? == 0 {
+        return Err(AppError::NotFound("Token not found".into()));
+    }
+    Ok(())
+}
+
+// The scope a request needs, derived from its path and method. Anything outside the
+// token resources (profile, sessions, 2FA, ...) is never reachable with a token.
+fn required_scope(method: &Method, path: &str) -> Option<String> {
+    let resource = path.strip_prefix("/api/")?.split('/').next()?;
+    if !RESOURCES.contains(&resource) {
+        return None;
+    }
+    let access = if matches!(*method, Method::GET | Method::HEAD) { "read" } else { "write" };
+    Some(format!("{}:{}", resource, access))
+}
+
+/// Resolves a personal access token to its owner, checking it grants access to the
+/// requested resource.
+pub async fn authenticate(pool: &DbPool, token: &str, method: &Method, path: &str) -> Result<Uuid, AppError> {
+    let row = api_token_repo::find_active_by_hash(pool, &hash_token(token))
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let required = required_scope(method, path).ok_or(AppError::Forbidden)?;
+    let write_scope = required.replace(":read", ":write");
+    if !row.scopes.iter().any(|s| *s == required || *s == write_scope) {
+        return Err(AppError::Forbidden);
+    }
+    if row.last_used_at.is_none_or(|t| Utc::now() - t > Duration::minutes(LAST_USED_TOUCH_INTERVAL_MINUTES)) {
+        if let Err(e) = api_token_repo::touch(pool, row.id).await {
+            log::warn!("Failed to update last_used_at for API token {}: {}", row.id, e);
+        }
+    }
+    Ok(row.user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn scopes(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn scopes_are_validated_and_deduplicated() {
+        assert_eq!(
+            normalize_scopes(&scopes(&[" Transactions:Write", "categories:read", "transactions:write"])).unwrap(),
+            scopes(&["categories:read", "transactions:write"])
+        );
+        assert!(normalize_scopes(&scopes(&["profile:read"])).is_err());
+        assert!(normalize_scopes(&scopes(&["transactions:delete"])).is_err());
+        assert!(normalize_scopes(&[]).is_err());
+    }
+
+    #[test]
+    fn requests_map_to_resource_scopes() {
+        assert_eq!(required_scope(&Method::GET, "/api/transactions").as_deref(), Some("transactions:read"));
+        assert_eq!(required_scope(&Method::HEAD, "/api/summary/monthly").as_deref(), Some("summary:read"));
+        assert_eq!(required_scope(&Method::POST, "/api/transactions").as_deref(), Some("transactions:write"));
+        assert_eq!(required_scope(&Method::DELETE, "/api/categories/42").as_deref(), Some("categories:write"));
+        assert_eq!(required_scope(&Method::GET, "/api/profile"), None);
+        assert_eq!(required_scope(&Method::GET, "/api/auth/sessions"), None);
+        assert_eq!(required_scope(&Method::GET, "/api/transactions-export"), None);
+        assert_eq!(required_scope(&Method::GET, "/transactions"), None);
+    }
+
+    #[tokio::test]
+    async fn tokens_only_reach_their_scopes() {
+        let Some(pool) = db::test_pool().await else { return };
+        let user_id = db::test_user(&pool).await;
+        let issue = |granted: &[&str]| {
+            let payload = CreateApiToken { name: "script".into(), scopes: scopes(granted), expires_at: None };
+            create(&pool, user_id, payload)
+        };
+
+        let reader = issue(&["transactions:read"]).await.unwrap();
+        assert!(reader.token.starts_with(TOKEN_PREFIX));
+        assert_eq!(authenticate(&pool, &reader.token, &Method::GET, "/api/transactions").await.unwrap(), user_id);
+        assert!(matches!(authenticate(&pool, &reader.token, &Method::POST, "/api/transactions").await, Err(AppError::Forbidden)));
+        assert!(matches!(authenticate(&pool, &reader.token, &Method::GET, "/api/categories").await, Err(AppError::Forbidden)));
+        assert!(matches!(authenticate(&pool, &reader.token, &Method::GET, "/api/profile").await, Err(AppError::Forbidden)));
+
+        let writer = issue(&["categories:write"]).await.unwrap();
+        assert!(authenticate(&pool, &writer.token, &Method::GET, "/api/categories").await.is_ok());
+        assert!(authenticate(&pool, &writer.token, &Method::PUT, "/api/categories/1").await.is_ok());
+
+        revoke(&pool, user_id, writer.info.id).await.unwrap();
+        assert!(matches!(authenticate(&pool, &writer.token, &Method::GET, "/api/categories").await, Err(AppError::Unauthorized)));
+        assert!(matches!(authenticate(&pool, "fbt_unknown", &Method::GET, "/api/categories").await, Err(AppError::Unauthorized)));
+    }
+}
@@ -0,0 +1,88 @@
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::attachment::{Attachment, AttachmentFile};
+use crate::repositories::{attachment_repo as repo, settings_repo, transaction_repo};
+
+const DEFAULT_MAX_BYTES: usize = 5 * 1024 * 1024;
+const MAX_FILENAME_LENGTH: usize = 255;
+
+/// Largest upload accepted, from the `attachment_max_bytes` setting.
+pub async fn max_bytes(pool: &DbPool) -> Result<usize, AppError> {
+    Ok(settings_repo::get_or(pool, "attachment_max_bytes", DEFAULT_MAX_BYTES).await?.max(1))
+}
+
+async fn ensure_transaction(pool: &DbPool, user_id: Uuid, transaction_id: Uuid) -> Result<(), AppError> {
+    match transaction_repo::get_by_id_user(pool, transaction_id, user_id).await? {
+        Some(_) => Ok(()),
+        None => Err(AppError::NotFound("Transaction not found".into())),
+    }
+}
+
+// Keeps the last path segment and drops control characters, so the name is safe to echo
+// back in a Content-Disposition header.
+fn clean_filename(raw: Option<&str>) -> String {
+    let name = raw.unwrap_or_default().rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = name.chars().filter(|c| !c.is_control()).take(MAX_FILENAME_LENGTH).collect();
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." { "attachment".to_string() } else { name.to_string() }
+}
+
+pub async fn list(pool: &DbPool, user_id: Uuid, transaction_id: Uuid) -> Result<Vec<Attachment>, AppError> {
+    ensure_transaction(pool, user_id, transaction_id).await?;
+    repo::list_for_transaction(pool, transaction_id).await
+}
+
+pub async fn upload(
+    pool: &DbPool,
+    user_id: Uuid,
+    transaction_id: Uuid,
+    filename: Option<&str>,
+    content_type: Option<&str>,
+    content: &[u8],
+) -> Result<Attachment, AppError> {
+    ensure_transaction(pool, user_id, transaction_id).await?;
+    if content.is_empty() {
+        return Err(AppError::BadRequest("Attachment is empty".into()));
+    }
+    let content_type = content_type.map(str::trim).filter(|c| !c.is_empty()).unwrap_or("application/octet-stream");
+    repo::insert(pool, Uuid::new_v4(), transaction_id, &clean_filename(filename), content_type, content).await
+}
+
+pub async fn download(pool: &DbPool, user_id: Uuid, transaction_id: Uuid, id: Uuid) -> Result<AttachmentFile, AppError> {
+    ensure_transaction(pool, user_id, transaction_id).await?;
+    repo::get_file(pool, id, transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Attachment not found".into()))
+}
+
+pub async fn delete(pool: &DbPool, user_id: Uuid, transaction_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    ensure_transaction(pool, user_id, transaction_id).await?;
+    if repo::delete(pool, id, transaction_id).await? == 0 {
+        return Err(AppError::NotFound("Attachment not found".into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filenames_keep_only_the_last_path_segment() {
+        assert_eq!(clean_filename(Some("receipt.pdf")), "receipt.pdf");
+        assert_eq!(clean_filename(Some("../../etc/passwd")), "passwd");
+        assert_eq!(clean_filename(Some("C:\\Users\\me\\scan.png")), "scan.png");
+        assert_eq!(clean_filename(Some("bad\r\nname.txt")), "badname.txt");
+    }
+
+    #[test]
+    fn missing_or_empty_filenames_fall_back_to_a_default() {
+        assert_eq!(clean_filename(None), "attachment");
+        assert_eq!(clean_filename(Some("  ")), "attachment");
+        assert_eq!(clean_filename(Some("dir/")), "attachment");
+        assert_eq!(clean_filename(Some("..")), "attachment");
+        assert_eq!(clean_filename(Some(&"x".repeat(300))).len(), MAX_FILENAME_LENGTH);
+    }
+}
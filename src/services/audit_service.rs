@@ -0,0 +1,149 @@
+use uuid::Uuid;
+
+use crate::client::ClientInfo;
+use crate::db::DbPool;
+use crate::dto::auth_event::{AdminEventQuery, EventPageQuery};
+use crate::errors::{error_code, AppError};
+use crate::models::auth_event::{AuthEvent, NewAuthEvent};
+use crate::repositories::auth_event_repo::{self, EventFilter};
+
+const DEFAULT_PAGE: i64 = 50;
+const MAX_PAGE: i64 = 200;
+
+/// Security-relevant things that happen to a user account, as stored in `auth_events`.
+#[derive(Debug, Clone, Copy)]
+pub enum EventKind {
+    Register,
+    Login,
+    Mfa,
+    Logout,
+    SessionRevoked,
+    OtpRequested,
+    EmailVerified,
+    PasswordResetRequested,
+    PasswordReset,
+    PasswordChanged,
+    EmailChangeRequested,
+    EmailChanged,
+    IdentityLinked,
+    IdentityUnlinked,
+    PasskeyAdded,
+    PasskeyRemoved,
+    AccountDeleted,
+}
+
+impl EventKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Register => "register",
+            EventKind::Login => "login",
+            EventKind::Mfa => "mfa",
+            EventKind::Logout => "logout",
+            EventKind::SessionRevoked => "session_revoked",
+            EventKind::OtpRequested => "otp_requested",
+            EventKind::EmailVerified => "email_verified",
+            EventKind::PasswordResetRequested => "password_reset_requested",
+            EventKind::PasswordReset => "password_reset",
+            EventKind::PasswordChanged => "password_changed",
+            EventKind::EmailChangeRequested => "email_change_requested",
+            EventKind::EmailChanged => "email_changed",
+            EventKind::IdentityLinked => "identity_linked",
+            EventKind::IdentityUnlinked => "identity_unlinked",
+            EventKind::PasskeyAdded => "passkey_added",
+            EventKind::PasskeyRemoved => "passkey_removed",
+            EventKind::AccountDeleted => "account_deleted",
+        }
+    }
+}
+
+/// An event about to be recorded, built up with whatever is known about the account.
+#[derive(Debug, Clone)]
+pub struct Event<'a> {
+    kind: EventKind,
+    client: &'a ClientInfo,
+    user_id: Option<Uuid>,
+    email: Option<&'a str>,
+    detail: Option<String>,
+}
+
+impl<'a> Event<'a> {
+    pub fn new(kind: EventKind, client: &'a ClientInfo) -> Self {
+        Self { kind, client, user_id: None, email: None, detail: None }
+    }
+
+    pub fn user(mut self, user_id: Uuid) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    pub fn email(mut self, email: &'a str) -> Self {
+        self.email = Some(email);
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+// The audit trail must never be the reason a sign-in fails, so write errors are only logged.
+async fn write(pool: &DbPool, event: &Event<'_>, failure: Option<&AppError>) {
+    let row = NewAuthEvent {
+        user_id: event.user_id,
+        email: event.email,
+        event_type: event.kind.as_str(),
+        success: failure.is_none(),
+        failure_reason: failure.map(error_code),
+        detail: event.detail.as_deref(),
+        ip: event.client.ip.as_deref(),
+        user_agent: event.client.user_agent.as_deref(),
+    };
+    if let Err(e) = auth_event_repo::insert(pool, &row).await {
+        log::error!("Failed to record {} event: {}", row.event_type, e);
+    }
+}
+
+pub async fn success(pool: &DbPool, event: Event<'_>) {
+    write(pool, &event, None).await;
+}
+
+pub async fn failure(pool: &DbPool, event: Event<'_>, error: &AppError) {
+    write(pool, &event, Some(error)).await;
+}
+
+/// Records a failure if `result` is an error, and passes it through unchanged.
+pub async fn on_error<T>(pool: &DbPool, event: Event<'_>, result: Result<T, AppError>) -> Result<T, AppError> {
+    if let Err(e) = &result {
+        write(pool, &event, Some(e)).await;
+    }
+    result
+}
+
+/// Records `result` as a success or a failure, and passes it through unchanged.
+pub async fn outcome<T>(pool: &DbPool, event: Event<'_>, result: Result<T, AppError>) -> Result<T, AppError> {
+    write(pool, &event, result.as_ref().err()).await;
+    result
+}
+
+fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE)
+}
+
+pub async fn list_for_user(pool: &DbPool, user_id: Uuid, q: EventPageQuery) -> Result<Vec<AuthEvent>, AppError> {
+    auth_event_repo::list_for_user(pool, user_id, q.before, page_size(q.limit)).await
+}
+
+pub async fn search(pool: &DbPool, q: AdminEventQuery) -> Result<Vec<AuthEvent>, AppError> {
+    let filter = EventFilter {
+        user_id: q.user_id,
+        email: q.email.map(|e| e.trim().to_lowercase()),
+        event_type: q.event_type,
+        success: q.success,
+        ip: q.ip,
+        from: q.from,
+        to: q.to,
+    };
+    auth_event_repo::search(pool, &filter, page_size(q.limit), q.offset.unwrap_or(0).max(0)).await
+}
+
@@ -1,131 +1,180 @@
-use chrono::{Duration, Utc};
 use uuid::Uuid;
 
-use crate::auth::{create_jwt, hash_password, verify_password};
+use crate::auth::{hash_password, password_needs_rehash, verify_password, Role};
+use crate::client::ClientInfo;
 use crate::config::AppConfig;
 use crate::db::DbPool;
-use crate::dto::auth::{AuthResponse, GoogleLoginRequest, LoginRequest, RegisterRequest, RequestOtpPayload, ResetPasswordPayload, VerifyOtpPayload};
+use crate::dto::auth::{AuthResponse, GoogleLoginRequest, LoginOutcome, LoginRequest, MagicLinkRequest, MfaVerifyRequest, OidcLoginRequest, RefreshRequest, RegisterRequest, RequestOtpPayload, ResetPasswordPayload, TokenPair, VerifyOtpPayload};
+use crate::dto::passkey::FinishLogin;
 use crate::errors::AppError;
-use crate::mailer;
-use crate::models::user::PublicUser;
-use crate::repositories::{otp_repo, user_repo, settings_repo};
-use crate::services::google;
-use rand::Rng;
+use crate::models::user::{PublicUser, User};
+use crate::repositories::{otp_repo, user_repo};
+use crate::services::audit_service::{self, Event, EventKind};
+use crate::services::throttle_service::{self, Action};
+use crate::services::google::{self, GoogleVerifier};
+use crate::services::oidc::{self, OidcRegistry};
+use crate::services::{device_service, identity_service, otp_service, passkey_service, password_policy, session_service, two_factor_service};
 
-fn generate_otp_code() -> String {
-    let mut rng = rand::thread_rng();
-    format!("{:06}", rng.gen_range(0..1_000_000))
-}
-
-pub async fn register(pool: &DbPool, cfg: &AppConfig, payload: RegisterRequest) -> Result<AuthResponse<PublicUser>, AppError> {
+pub async fn register(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: RegisterRequest) -> Result<AuthResponse<PublicUser>, AppError> {
     let email = payload.email.trim().to_lowercase();
-    let hash = hash_password(&payload.password)?;
+    password_policy::validate(pool, cfg, "password", &payload.password, &email, &payload.name).await?;
+    let hash = hash_password(&payload.password, cfg)?;
     let id = Uuid::new_v4();
-    let rec = user_repo::insert_local(pool, id, &payload.name, &email, &hash).await
+    let inserted = user_repo::insert_local(pool, id, &payload.name, &email, &hash).await
         .map_err(|e| match e {
             AppError::Db(s) if s.contains("unique") => AppError::Conflict("Email already registered".into()),
             other => other,
-        })?;
+        });
+    let rec = audit_service::outcome(pool, Event::new(EventKind::Register, client).user(id).email(&email), inserted).await?;
     // Auto-send OTP after register 
-    let code = generate_otp_code();
-    let expires_at = Utc::now() + Duration::minutes(10);
-    if let Err(e) = otp_repo::create(pool, rec.id, &code, "verify", expires_at).await {
-        log::warn!("Failed to store OTP for {}: {}", email, e);
-    } else if let Err(e) = mailer::send_otp(pool, &email, &code).await {
-        log::warn!("SMTP failed for {}: {} (code {})", email, e, code);
-    } else {
-        log::info!("OTP sent to {} on register", email);
+    if let Err(e) = otp_service::issue(pool, cfg, rec.id, &email, "verify", true).await {
+        log::warn!("Failed to issue OTP for {}: {}", email, e);
     }
-    let token = create_jwt(rec.id, cfg)?;
-    Ok(AuthResponse { token, user: rec.into() })
+    let tokens = session_service::start(pool, cfg, Role::User, rec.id, client).await?;
+    device_service::record_login(pool, cfg, client, &rec, tokens.session_id).await;
+    Ok(AuthResponse { tokens, user: rec.into() })
 }
 
-pub async fn login(pool: &DbPool, cfg: &AppConfig, payload: LoginRequest) -> Result<AuthResponse<PublicUser>, AppError> {
+// Called once the first factor (`method`) checked out: either opens a session or, when the
+// account has 2FA enabled, hands out a challenge for `verify_mfa`. A first factor that is
+// already multi-factor (`mfa_satisfied`, e.g. a user-verifying passkey) skips the challenge.
+async fn finish_login(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, user: User, method: &str, mfa_satisfied: bool) -> Result<LoginOutcome<AuthResponse<PublicUser>>, AppError> {
+    let event = Event::new(EventKind::Login, client).user(user.id).email(&user.email);
+    if !user.is_verified {
+        audit_service::failure(pool, event.detail(method), &AppError::Forbidden).await;
+        return Err(AppError::Forbidden);
+    }
+    if !mfa_satisfied && two_factor_service::is_enabled(pool, Role::User, user.id).await? {
+        audit_service::success(pool, event.detail(format!("{}, 2FA pending", method))).await;
+        return Ok(LoginOutcome::MfaRequired(two_factor_service::challenge(cfg, Role::User, user.id)?));
+    }
+    audit_service::success(pool, event.detail(method)).await;
+    let tokens = session_service::start(pool, cfg, Role::User, user.id, client).await?;
+    device_service::record_login(pool, cfg, client, &user, tokens.session_id).await;
+    Ok(LoginOutcome::Authenticated(AuthResponse { tokens, user: user.into() }))
+}
+
+pub async fn login(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: LoginRequest) -> Result<LoginOutcome<AuthResponse<PublicUser>>, AppError> {
     let email = payload.email.trim().to_lowercase();
-    let user = user_repo::get_by_email(pool, &email).await?.ok_or(AppError::Unauthorized)?;
-    if !verify_password(&payload.password, &user.password_hash)? {
-        return Err(AppError::Unauthorized);
+    let event = Event::new(EventKind::Login, client).email(&email).detail("password");
+    audit_service::on_error(pool, event.clone(), throttle_service::check(pool, Action::Login, &email, client).await).await?;
+    let user = match user_repo::get_by_email(pool, &email).await? {
+        Some(u) if verify_password(&payload.password, &u.password_hash)? => u,
+        found => {
+            throttle_service::record_failure(pool, Action::Login, &email, client).await?;
+            let event = match found {
+                Some(u) => event.user(u.id),
+                None => event,
+            };
+            audit_service::failure(pool, event, &AppError::Unauthorized).await;
+            return Err(AppError::Unauthorized);
+        }
+    };
+    throttle_service::record_success(pool, Action::Login, &email).await?;
+    // Upgrade hashes made with older Argon2 settings while the plaintext is at hand
+    if password_needs_rehash(&user.password_hash, cfg) {
+        match hash_password(&payload.password, cfg) {
+            Ok(new_hash) => {
+                if let Err(e) = user_repo::replace_password_hash(pool, user.id, &user.password_hash, &new_hash).await {
+                    log::warn!("Failed to upgrade password hash for user {}: {}", user.id, e);
+                }
+            }
+            Err(e) => log::warn!("Failed to rehash password for user {}: {}", user.id, e),
+        }
     }
-    if !user.is_verified { return Err(AppError::Forbidden); }
-    let token = create_jwt(user.id, cfg)?;
-    Ok(AuthResponse { token, user: user.into() })
+    finish_login(pool, cfg, client, user, "password", false).await
+}
+
+/// Signs in with a passkey. Failed assertions are throttled per credential id and client
+/// like wrong passwords, so a stolen challenge cannot be retried without limit.
+pub async fn passkey_login(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: FinishLogin) -> Result<LoginOutcome<AuthResponse<PublicUser>>, AppError> {
+    let event = Event::new(EventKind::Login, client).detail("passkey");
+    let credential = format!("passkey:{}", passkey_service::normalize_credential_id(&payload.credential.id));
+    audit_service::on_error(pool, event.clone(), throttle_service::check(pool, Action::PasskeyLogin, &credential, client).await).await?;
+    let authenticated = passkey_service::authenticate(pool, &payload).await;
+    match &authenticated {
+        Ok(_) => throttle_service::record_success(pool, Action::PasskeyLogin, &credential).await?,
+        Err(AppError::Unauthorized | AppError::BadRequest(_)) => throttle_service::record_failure(pool, Action::PasskeyLogin, &credential, client).await?,
+        Err(_) => {}
+    }
+    let (user, user_verified) = audit_service::on_error(pool, event, authenticated).await?;
+    finish_login(pool, cfg, client, user, "passkey", user_verified).await
+}
+
+pub async fn verify_mfa(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: MfaVerifyRequest) -> Result<AuthResponse<PublicUser>, AppError> {
+    let completed = two_factor_service::complete_challenge(pool, cfg, client, Role::User, &payload).await;
+    let user_id = audit_service::on_error(pool, Event::new(EventKind::Mfa, client), completed).await?;
+    let user = user_repo::get_by_id(pool, user_id).await?;
+    audit_service::success(pool, Event::new(EventKind::Mfa, client).user(user.id).email(&user.email)).await;
+    let tokens = session_service::start(pool, cfg, Role::User, user.id, client).await?;
+    device_service::record_login(pool, cfg, client, &user, tokens.session_id).await;
+    Ok(AuthResponse { tokens, user: user.into() })
+}
+
+pub async fn refresh(pool: &DbPool, cfg: &AppConfig, payload: RefreshRequest) -> Result<TokenPair, AppError> {
+    session_service::refresh(pool, cfg, Role::User, &payload.refresh_token).await
+}
+
+pub async fn logout(pool: &DbPool, client: &ClientInfo, user_id: Uuid, session_id: Uuid) -> Result<(), AppError> {
+    session_service::revoke(pool, Role::User, user_id, session_id).await?;
+    audit_service::success(pool, Event::new(EventKind::Logout, client).user(user_id)).await;
+    Ok(())
 }
 
 pub async fn me(pool: &DbPool, user_id: Uuid) -> Result<PublicUser, AppError> {
     Ok(user_repo::get_by_id(pool, user_id).await?.into())
 }
 
-pub async fn delete_me(pool: &DbPool, user_id: Uuid) -> Result<(), AppError> {
+pub async fn delete_me(pool: &DbPool, client: &ClientInfo, user_id: Uuid) -> Result<(), AppError> {
+    let user = user_repo::get_by_id(pool, user_id).await?;
     let affected = user_repo::delete_by_id(pool, user_id).await?;
     if affected == 0 { return Err(AppError::NotFound("User not found".into())); }
+    audit_service::success(pool, Event::new(EventKind::AccountDeleted, client).user(user_id).email(&user.email)).await;
     Ok(())
 }
 
-pub async fn request_otp(pool: &DbPool, payload: RequestOtpPayload) -> Result<(), AppError> {
+pub async fn request_otp(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: RequestOtpPayload) -> Result<(), AppError> {
     let email = payload.email.trim().to_lowercase();
     let user = user_repo::get_by_email(pool, &email).await?.ok_or_else(|| AppError::NotFound("User not found".into()))?;
-    if let Some(created_at) = otp_repo::last_created_at(pool, user.id, "verify").await? {
-        if (Utc::now() - created_at) < Duration::seconds(120) {
-            let wait = 120 - (Utc::now() - created_at).num_seconds().max(0);
-            return Err(AppError::TooManyRequests(format!("Please wait {}s before requesting another code", wait)));
-        }
-    }
-    let code = generate_otp_code();
-    let expires_at = Utc::now() + Duration::minutes(10);
-    otp_repo::create(pool, user.id, &code, "verify", expires_at).await?;
-    match mailer::send_otp(pool, &email, &code).await {
-        Ok(_) => log::info!("OTP sent to {}", email),
-        Err(e) => log::warn!("SMTP failed ({}). OTP for {} is {} (dev)", e, email, code),
-    }
-    Ok(())
+    let issued = otp_service::issue(pool, cfg, user.id, &email, "verify", false).await;
+    audit_service::outcome(pool, Event::new(EventKind::OtpRequested, client).user(user.id).email(&email).detail("verify"), issued).await
 }
 
-pub async fn verify_otp(pool: &DbPool, payload: VerifyOtpPayload) -> Result<(), AppError> {
+pub async fn verify_otp(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: VerifyOtpPayload) -> Result<(), AppError> {
     let email = payload.email.trim().to_lowercase();
-    let code = payload.code.trim().to_string();
     let user = user_repo::get_by_email(pool, &email).await?.ok_or_else(|| AppError::NotFound("User not found".into()))?;
-    let otp_id = otp_repo::find_valid(pool, user.id, &code, "verify").await?.ok_or_else(|| AppError::BadRequest("Invalid or expired code".into()))?;
+    let event = Event::new(EventKind::EmailVerified, client).user(user.id).email(&email);
+    let consumed = otp_service::consume(pool, cfg, client, Action::VerifyOtp, user.id, &payload.code, "verify").await;
+    let otp_id = audit_service::on_error(pool, event.clone(), consumed).await?;
     let mut tx = pool.begin().await.map_err(|e| AppError::Db(e.to_string()))?;
-    sqlx::query("UPDATE user_otp_codes SET used_at = now() WHERE id=$1")
-        .bind(otp_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| AppError::Db(e.to_string()))?;
+    if !otp_repo::mark_used(&mut tx, otp_id).await? {
+        return Err(AppError::BadRequest("Invalid or expired code".into()));
+    }
     sqlx::query("UPDATE users SET is_verified=true WHERE id=$1").bind(user.id).execute(&mut *tx).await.map_err(|e| AppError::Db(e.to_string()))?;
     tx.commit().await.map_err(|e| AppError::Db(e.to_string()))?;
+    audit_service::success(pool, event).await;
     Ok(())
 }
 
-pub async fn forgot_password(pool: &DbPool, payload: RequestOtpPayload) -> Result<(), AppError> {
+pub async fn forgot_password(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: RequestOtpPayload) -> Result<(), AppError> {
     let email = payload.email.trim().to_lowercase();
     let user = user_repo::get_by_email(pool, &email).await?.ok_or_else(|| AppError::NotFound("User not found".into()))?;
-    if let Some(created_at) = otp_repo::last_created_at(pool, user.id, "reset").await? {
-        if (Utc::now() - created_at) < Duration::seconds(120) {
-            let wait = 120 - (Utc::now() - created_at).num_seconds().max(0);
-            return Err(AppError::TooManyRequests(format!("Please wait {}s before requesting another code", wait)));
-        }
-    }
-    let code = generate_otp_code();
-    let expires_at = Utc::now() + Duration::minutes(10);
-    otp_repo::create(pool, user.id, &code, "reset", expires_at).await?;
-    match mailer::send_otp(pool, &email, &code).await {
-        Ok(_) => log::info!("Reset OTP sent to {}", email),
-        Err(e) => log::warn!("SMTP failed ({}). Reset OTP for {} is {} (dev)", e, email, code),
-    }
-    Ok(())
+    let issued = otp_service::issue(pool, cfg, user.id, &email, "reset", false).await;
+    audit_service::outcome(pool, Event::new(EventKind::PasswordResetRequested, client).user(user.id).email(&email), issued).await
 }
 
-pub async fn reset_password(pool: &DbPool, payload: ResetPasswordPayload) -> Result<(), AppError> {
+pub async fn reset_password(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: ResetPasswordPayload) -> Result<(), AppError> {
     let email = payload.email.trim().to_lowercase();
     let user = user_repo::get_by_email(pool, &email).await?.ok_or_else(|| AppError::NotFound("User not found".into()))?;
-    let otp_id = otp_repo::find_valid(pool, user.id, payload.code.trim(), "reset").await?.ok_or_else(|| AppError::BadRequest("Invalid or expired code".into()))?;
-    let new_hash = crate::auth::hash_password(&payload.new_password)?;
+    password_policy::validate(pool, cfg, "new_password", &payload.new_password, &user.email, &user.name).await?;
+    let event = Event::new(EventKind::PasswordReset, client).user(user.id).email(&email);
+    let consumed = otp_service::consume(pool, cfg, client, Action::ResetPassword, user.id, &payload.code, "reset").await;
+    let otp_id = audit_service::on_error(pool, event.clone(), consumed).await?;
+    let new_hash = crate::auth::hash_password(&payload.new_password, cfg)?;
     let mut tx = pool.begin().await.map_err(|e| AppError::Db(e.to_string()))?;
-    sqlx::query("UPDATE user_otp_codes SET used_at = now() WHERE id=$1")
-        .bind(otp_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| AppError::Db(e.to_string()))?;
+    if !otp_repo::mark_used(&mut tx, otp_id).await? {
+        return Err(AppError::BadRequest("Invalid or expired code".into()));
+    }
     sqlx::query("UPDATE users SET password_hash=$1, is_verified=true WHERE id=$2")
         .bind(new_hash)
         .bind(user.id)
@@ -133,58 +182,75 @@ pub async fn reset_password(pool: &DbPool, payload: ResetPasswordPayload) -> Res
         .await
         .map_err(|e| AppError::Db(e.to_string()))?;
     tx.commit().await.map_err(|e| AppError::Db(e.to_string()))?;
+    audit_service::success(pool, event).await;
+    // Whoever forced the reset may still hold a refresh token
+    let revoked = session_service::revoke_all_for_user(pool, user.id, None).await?;
+    if revoked > 0 {
+        let event = Event::new(EventKind::SessionRevoked, client).user(user.id).detail(format!("{} session(s) after password reset", revoked));
+        audit_service::success(pool, event).await;
+    }
     Ok(())
 }
 
-pub async fn google_login(pool: &DbPool, cfg: &AppConfig, req: GoogleLoginRequest) -> Result<AuthResponse<PublicUser>, AppError> {
-    // Load allowed client_ids: env GOOGLE_CLIENT_ID (can be comma-separated),
-    // plus app_settings google_client_id and/or google_client_ids
-    let mut allowed: Vec<String> = Vec::new();
-    if let Some(s) = cfg.google_client_id.clone() { allowed.push(s); }
-    if let Some(s) = settings_repo::get_value(pool, "google_client_id").await? { allowed.push(s); }
-    if let Some(s) = settings_repo::get_value(pool, "google_client_ids").await? { allowed.push(s); }
-    let allowed: Vec<String> = allowed
-        .into_iter()
-        .flat_map(|v| v.split(',').map(|x| x.trim().to_string()).collect::<Vec<_>>())
-        .filter(|s| !s.is_empty())
-        .collect();
-    if allowed.is_empty() {
-        return Err(AppError::BadRequest("GOOGLE_CLIENT_ID not configured".into()));
+/// Sends a passwordless sign-in code. Unknown addresses get the same response, and so do
+/// requests inside the resend cooldown or whose email fails, so the endpoint cannot be used
+/// to probe for accounts.
+pub async fn request_login_code(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: RequestOtpPayload) -> Result<(), AppError> {
+    let email = payload.email.trim().to_lowercase();
+    if let Some(user) = user_repo::get_by_email(pool, &email).await? {
+        let issued = otp_service::issue_login(pool, cfg, user.id, &email).await;
+        let event = Event::new(EventKind::OtpRequested, client).user(user.id).email(&email).detail(otp_service::LOGIN);
+        if let Err(e) = audit_service::outcome(pool, event, issued).await {
+            log::warn!("passwordless code for user {} not sent: {}", user.id, e);
+        }
     }
+    Ok(())
+}
 
-    let g = google::verify_id_token(&req.id_token).await?;
-    if !allowed.iter().any(|id| id == &g.aud) {
-        return Err(AppError::BadRequest(format!("aud mismatch: expected one of [{}], got {}", allowed.join(","), g.aud)));
+// Receiving the code proves control of the mailbox, which also verifies the account.
+async fn finish_passwordless_login(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, user_id: Uuid, method: &str) -> Result<LoginOutcome<AuthResponse<PublicUser>>, AppError> {
+    sqlx::query("UPDATE users SET is_verified=true WHERE id=$1 AND NOT is_verified")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Db(e.to_string()))?;
+    let user = user_repo::get_by_id(pool, user_id).await?;
+    finish_login(pool, cfg, client, user, method, false).await
+}
+
+pub async fn login_with_code(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: VerifyOtpPayload) -> Result<LoginOutcome<AuthResponse<PublicUser>>, AppError> {
+    let email = payload.email.trim().to_lowercase();
+    let user = user_repo::get_by_email(pool, &email).await?.ok_or_else(|| AppError::BadRequest("Invalid or expired code".into()))?;
+    let consumed = otp_service::consume(pool, cfg, client, Action::PasswordlessLogin, user.id, &payload.code, otp_service::LOGIN).await;
+    let event = Event::new(EventKind::Login, client).user(user.id).email(&email).detail("email code");
+    let otp_id = audit_service::on_error(pool, event, consumed).await?;
+    if !otp_repo::mark_used(&mut *pool.acquire().await?, otp_id).await? {
+        return Err(AppError::BadRequest("Invalid or expired code".into()));
     }
-    let email = g.email.to_lowercase();
-    let existing = user_repo::get_by_email(pool, &email).await?;
-    let user = if let Some(u) = existing {
-        let mut u2 = if u.google_sub.is_none() { user_repo::link_google_sub(pool, u.id, &g.sub).await? } else { u };
-        if !u2.is_verified && g.email_verified {
-            sqlx::query("UPDATE users SET is_verified=true WHERE id=$1")
-                .bind(u2.id)
-                .execute(pool)
-                .await
-                .map_err(|e| AppError::Db(e.to_string()))?;
-            // reload minimal fields
-            u2 = user_repo::get_by_id(pool, u2.id).await?;
-        }
-        u2
-    } else {
-        let id = Uuid::new_v4();
-        // create unverified then optionally verify if Google asserts email_verified
-        let mut created = user_repo::insert_google(pool, id, &g.name, &email, &g.sub).await?;
-        if g.email_verified {
-            sqlx::query("UPDATE users SET is_verified=true WHERE id=$1")
-                .bind(created.id)
-                .execute(pool)
-                .await
-                .map_err(|e| AppError::Db(e.to_string()))?;
-            created = user_repo::get_by_id(pool, created.id).await?;
-        }
-        created
-    };
-    if !user.is_verified { return Err(AppError::Forbidden); }
-    let token = create_jwt(user.id, cfg)?;
-    Ok(AuthResponse { token, user: user.into() })
+    finish_passwordless_login(pool, cfg, client, user.id, "email code").await
+}
+
+pub async fn login_with_link(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, payload: MagicLinkRequest) -> Result<LoginOutcome<AuthResponse<PublicUser>>, AppError> {
+    let consumed = otp_service::consume_login_link(pool, cfg, payload.token.trim()).await;
+    let user_id = audit_service::on_error(pool, Event::new(EventKind::Login, client).detail("email link"), consumed).await?;
+    finish_passwordless_login(pool, cfg, client, user_id, "email link").await
+}
+
+pub async fn google_login(pool: &DbPool, cfg: &AppConfig, verifier: &GoogleVerifier, client: &ClientInfo, req: GoogleLoginRequest) -> Result<LoginOutcome<AuthResponse<PublicUser>>, AppError> {
+    let allowed = google::allowed_client_ids(pool, cfg).await?;
+    let event = Event::new(EventKind::Login, client).detail(identity_service::GOOGLE);
+    let identity = audit_service::on_error(pool, event.clone(), verifier.verify_id_token(&req.id_token, &allowed).await).await?;
+    let resolved = identity_service::resolve_user(pool, client, identity_service::GOOGLE, &identity).await;
+    let user = audit_service::on_error(pool, event, resolved).await?;
+    finish_login(pool, cfg, client, user, identity_service::GOOGLE, false).await
+}
+
+pub async fn oidc_login(pool: &DbPool, cfg: &AppConfig, registry: &OidcRegistry, client: &ClientInfo, provider: &str, req: OidcLoginRequest) -> Result<LoginOutcome<AuthResponse<PublicUser>>, AppError> {
+    let provider = oidc::find_provider(pool, provider).await?;
+    let method = format!("oidc:{}", provider.name);
+    let event = Event::new(EventKind::Login, client).detail(method.clone());
+    let identity = audit_service::on_error(pool, event.clone(), registry.verify(&provider, &req.id_token).await).await?;
+    let resolved = identity_service::resolve_user(pool, client, &provider.name, &identity).await;
+    let user = audit_service::on_error(pool, event, resolved).await?;
+    finish_login(pool, cfg, client, user, &method, false).await
 }
@@ -33,8 +33,16 @@ pub async fn update(pool: &DbPool, user_id: Uuid, id: Uuid, payload: UpdateCateg
     Ok(updated)
 }
 
+/// Transactions in the category go with it, but not split transactions: a category any of
+/// their lines uses cannot be deleted until those lines are moved elsewhere.
 pub async fn delete(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
-    let affected = repo::delete(pool, id, user_id).await?;
+    if repo::used_by_splits(pool, id, user_id).await? {
+        return Err(AppError::Conflict("Category is used by split transactions; move their lines to another category first".into()));
+    }
+    let affected = repo::delete(pool, id, user_id).await.map_err(|e| match e {
+        AppError::Db(s) if s.contains("foreign key") => AppError::Conflict("Category is used for transfer fees".into()),
+        other => other,
+    })?;
     if affected == 0 { return Err(AppError::NotFound("Category not found".into())); }
     Ok(())
 }
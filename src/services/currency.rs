@@ -0,0 +1,101 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::errors::AppError;
+
+// Currencies whose amounts are not written with two decimals. IDR is listed by ISO 4217 with
+// two, but sen have not circulated for decades and nobody records them.
+const NO_MINOR_UNITS: [&str; 18] = [
+    "BIF", "CLP", "DJF", "GNF", "IDR", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI", "VND", "VUV", "XAF", "XOF", "XPF",
+];
+const THREE_MINOR_UNITS: [&str; 7] = ["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"];
+
+pub fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase())
+}
+
+/// Trims and uppercases a currency code, rejecting anything that is not shaped like ISO 4217.
+pub fn parse_code(field: &str, raw: &str) -> Result<String, AppError> {
+    let code = raw.trim().to_uppercase();
+    if !is_currency_code(&code) {
+        return Err(AppError::BadRequest(format!("{} must be a 3-letter ISO 4217 code", field)));
+    }
+    Ok(code)
+}
+
+/// Number of decimals amounts in `currency` are kept with.
+pub fn minor_units(currency: &str) -> u32 {
+    if NO_MINOR_UNITS.contains(&currency) {
+        0
+    } else if THREE_MINOR_UNITS.contains(&currency) {
+        3
+    } else {
+        2
+    }
+}
+
+/// Checks that `amount` has no more decimals than `currency` allows and brings it to exactly
+/// that many, so 50000 IDR is stored as `50000` and 1.5 KWD as `1.500`.
+pub fn normalize_amount(field: &str, amount: Decimal, currency: &str) -> Result<Decimal, AppError> {
+    let places = minor_units(currency);
+    if amount.normalize().scale() > places {
+        return Err(AppError::BadRequest(format!("{} in {} can have at most {} decimal places", field, currency, places)));
+    }
+    let mut amount = amount;
+    amount.rescale(places);
+    Ok(amount)
+}
+
+/// Rounds a computed amount (e.g. a conversion) to `currency`'s decimals, halves away from zero.
+pub fn round(amount: Decimal, currency: &str) -> Decimal {
+    let places = minor_units(currency);
+    let mut rounded = amount.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero);
+    rounded.rescale(places);
+    rounded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(raw: &str) -> Decimal {
+        Decimal::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn idr_amounts_are_whole() {
+        assert_eq!(normalize_amount("amount", dec("50000"), "IDR").unwrap().to_string(), "50000");
+        assert_eq!(normalize_amount("amount", dec("50000.00"), "IDR").unwrap().to_string(), "50000");
+        assert!(normalize_amount("amount", dec("50000.5"), "IDR").is_err());
+    }
+
+    #[test]
+    fn kwd_amounts_have_three_decimals() {
+        assert_eq!(normalize_amount("amount", dec("1.5"), "KWD").unwrap().to_string(), "1.500");
+        assert_eq!(normalize_amount("amount", dec("1.125"), "KWD").unwrap().to_string(), "1.125");
+        assert!(normalize_amount("amount", dec("1.1255"), "KWD").is_err());
+    }
+
+    #[test]
+    fn other_currencies_have_two_decimals() {
+        assert_eq!(normalize_amount("amount", dec("12"), "USD").unwrap().to_string(), "12.00");
+        assert!(normalize_amount("amount", dec("12.001"), "usd").is_err());
+    }
+
+    #[test]
+    fn rounding_goes_half_away_from_zero_to_the_currency_decimals() {
+        assert_eq!(round(dec("10.005"), "USD").to_string(), "10.01");
+        assert_eq!(round(dec("-10.005"), "USD").to_string(), "-10.01");
+        assert_eq!(round(dec("10.004"), "USD").to_string(), "10.00");
+        assert_eq!(round(dec("15499.5"), "IDR").to_string(), "15500");
+        assert_eq!(round(dec("0.0005"), "KWD").to_string(), "0.001");
+        assert_eq!(round(dec("7"), "KWD").to_string(), "7.000");
+    }
+
+    #[test]
+    fn codes_are_trimmed_and_uppercased() {
+        assert_eq!(parse_code("currency", " idr ").unwrap(), "IDR");
+        assert!(parse_code("currency", "RP").is_err());
+        assert!(parse_code("currency", "US1").is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::net::IpAddr;
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::auth::{create_purpose_jwt, hash_token, verify_purpose_jwt};
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::mailer;
+use crate::models::user::User;
+use crate::repositories::{known_device_repo, session_repo, settings_repo};
+use crate::services::audit_service::{self, Event, EventKind};
+
+const REVOKE_AUDIENCE: &str = "finance-user-revoke-session";
+
+// Without client cooperation the user agent is all that tells devices apart.
+fn fingerprint(client: &ClientInfo) -> String {
+    let agent = client.user_agent.as_deref().unwrap_or_default().trim().to_lowercase();
+    hash_token(&agent)
+}
+
+// Addresses are compared by network (IPv4 /24, IPv6 /48), so a home router handing out a
+// new address does not count as somewhere new.
+fn ip_range(ip: Option<&str>) -> String {
+    match ip.and_then(|ip| ip.parse::<IpAddr>().ok()) {
+        Some(IpAddr::V4(v4)) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{}.{}.{}.0/24", a, b, c)
+        }
+        Some(IpAddr::V6(v6)) => {
+            let s = v6.segments();
+            format!("{:x}:{:x}:{:x}::/48", s[0], s[1], s[2])
+        }
+        None => "unknown".to_string(),
+    }
+}
+
+/// Remembers where a successful login came from and, when the device or network is new
+/// for an account that has signed in before, emails the owner a way to end that session.
+/// Never fails the login; problems are only logged.
+pub async fn record_login(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, user: &User, session_id: Uuid) {
+    if let Err(e) = check_and_remember(pool, cfg, client, user, session_id).await {
+        log::warn!("Failed to record login device for user {}: {}", user.id, e);
+    }
+}
+
+async fn check_and_remember(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, user: &User, session_id: Uuid) -> Result<(), AppError> {
+    let fingerprint = fingerprint(client);
+    let range = ip_range(client.ip.as_deref());
+    let seen = known_device_repo::familiarity(pool, user.id, &fingerprint, &range).await?;
+    known_device_repo::remember(pool, user.id, &fingerprint, &range, client.user_agent.as_deref()).await?;
+    // The very first login has nothing to compare against
+    if !seen.any || (seen.device && seen.network) {
+        return Ok(());
+    }
+    if !settings_repo::get_or(pool, "login_alerts_enabled", true).await? {
+        return Ok(());
+    }
+
+    let token = create_purpose_jwt(session_id, REVOKE_AUDIENCE, Duration::days(cfg.refresh_token_exp_days as i64), cfg)?;
+    let link = match settings_repo::get_value(pool, "session_revoke_url").await? {
+        Some(base) if !base.trim().is_empty() => {
+            let sep = if base.contains('?') { '&' } else { '?' };
+            Some(format!("{}{}token={}", base.trim(), sep, token))
+        }
+        _ => None,
+    };
+    // Sent in the background so a slow or failing mail server does not hold up the login
+    let (pool, user_id, email) = (pool.clone(), user.id, user.email.clone());
+    let (user_agent, ip, when) = (client.user_agent.clone(), client.ip.clone(), Utc::now());
+    tokio::spawn(async move {
+        let alert = mailer::LoginAlert {
+            when,
+            user_agent: user_agent.as_deref(),
+            ip: ip.as_deref(),
+            revoke_link: link.as_deref(),
+            revoke_code: &token,
+        };
+        if let Err(e) = mailer::send_new_login_alert(&pool, &email, &alert).await {
+            log::warn!("Failed to send new-login alert to user {}: {}", user_id, e);
+        }
+    });
+    Ok(())
+}
+
+/// Ends the session named in a new-login alert. Works without signing in, since whoever
+/// holds the alert may have lost access to the account.
+pub async fn revoke_from_alert(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, token: &str) -> Result<(), AppError> {
+    let claims = verify_purpose_jwt(token.trim(), REVOKE_AUDIENCE, cfg)
+        .map_err(|_| AppError::BadRequest("Invalid or expired link".into()))?;
+    let session_id = claims.sub;
+    if let Some(session) = session_repo::find_active(pool, session_id).await? {
+        session_repo::revoke(pool, session_id).await?;
+        if let Some(user_id) = session.user_id {
+            let event = Event::new(EventKind::SessionRevoked, client).user(user_id).detail(format!("session {} from login alert", session_id));
+            audit_service::success(pool, event).await;
+        }
+    }
+    Ok(())
+}
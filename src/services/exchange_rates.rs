@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+use chrono::{Duration, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::exchange_rate::RateQuery;
+use crate::errors::AppError;
+use crate::models::exchange_rate::{ExchangeRate, NewExchangeRate};
+use crate::repositories::exchange_rate_repo as repo;
+use crate::services::currency;
+use crate::services::jwks::unavailable;
+
+const DEFAULT_LIST_LIMIT: i64 = 500;
+const MAX_LIST_LIMIT: i64 = 5000;
+
+pub type RatesFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<NewExchangeRate>, AppError>> + Send + 'a>>;
+
+/// Where published exchange rates come from. The file source stands in for a rate API in
+/// local setups and tests.
+pub trait RateSource: Send + Sync {
+    /// Recorded as the `source` of the rates it provides.
+    fn name(&self) -> &str;
+    fn fetch(&self) -> RatesFuture<'_>;
+}
+
+// Feed format shared by both sources, the one common rate APIs (ECB-style) publish:
+// {"base":"USD","date":"2025-09-01","rates":{"IDR":16350.5,"EUR":0.92}}, or an array of them.
+#[derive(Deserialize)]
+struct Published {
+    base: String,
+    date: NaiveDate,
+    rates: HashMap<String, Decimal>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Feed {
+    One(Published),
+    Many(Vec<Published>),
+}
+
+fn parse_feed(raw: &str, origin: &str) -> Result<Vec<NewExchangeRate>, AppError> {
+    let feed: Feed = serde_json::from_str(raw).map_err(|e| unavailable(format!("invalid exchange rate feed from {}: {}", origin, e)))?;
+    let days = match feed {
+        Feed::One(day) => vec![day],
+        Feed::Many(days) => days,
+    };
+    Ok(days
+        .into_iter()
+        .flat_map(|day| {
+            day.rates.into_iter().map(move |(quote, rate)| NewExchangeRate {
+                base_currency: day.base.clone(),
+                quote_currency: quote,
+                rate_date: day.date,
+                rate,
+            })
+        })
+        .collect())
+}
+
+pub struct HttpRateSource {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl HttpRateSource {
+    pub fn new(url: String) -> Self {
+        Self { url, client: reqwest::Client::new() }
+    }
+}
+
+impl RateSource for HttpRateSource {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    fn fetch(&self) -> RatesFuture<'_> {
+        Box::pin(async move {
+            let resp = self
+                .client
+                .get(&self.url)
+                .send()
+                .await
+                .map_err(|e| unavailable(format!("exchange rate request failed: {}", e)))?;
+            if !resp.status().is_success() {
+                return Err(unavailable(format!("exchange rate request returned {}", resp.status())));
+            }
+            let body = resp.text().await.map_err(|e| unavailable(format!("exchange rate response unreadable: {}", e)))?;
+            parse_feed(&body, &self.url)
+        })
+    }
+}
+
+pub struct FileRateSource {
+    path: String,
+}
+
+impl FileRateSource {
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+}
+
+impl RateSource for FileRateSource {
+    fn name(&self) -> &str {
+        &self.path
+    }
+
+    fn fetch(&self) -> RatesFuture<'_> {
+        Box::pin(async move {
+            let raw = tokio::fs::read_to_string(&self.path)
+                .await
+                .map_err(|e| unavailable(format!("cannot read exchange rate file {}: {}", self.path, e)))?;
+            parse_feed(&raw, &self.path)
+        })
+    }
+}
+
+/// The configured rate source, if any (EXCHANGE_RATES_FILE wins over EXCHANGE_RATES_URL).
+/// Shared by all workers through `web::Data`.
+pub struct RateProvider {
+    source: Option<Box<dyn RateSource>>,
+}
+
+impl RateProvider {
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        let source: Option<Box<dyn RateSource>> = match (&cfg.exchange_rates_file, &cfg.exchange_rates_url) {
+            (Some(path), _) => Some(Box::new(FileRateSource::new(path.clone()))),
+            (None, Some(url)) => Some(Box::new(HttpRateSource::new(url.clone()))),
+            (None, None) => None,
+        };
+        Self { source }
+    }
+}
+
+/// Stored rates of a date range, for converting many amounts without a query each.
+pub struct RateTable {
+    rates: Vec<ExchangeRate>,
+}
+
+impl RateTable {
+    pub fn new(rates: Vec<ExchangeRate>) -> Self {
+        Self { rates }
+    }
+
+    // Newest rate between the two currencies published in `oldest..=day`, direct or inverted;
+    // a direct rate wins over an inverted one of the same day.
+    fn pair(&self, from: &str, to: &str, day: NaiveDate, oldest: NaiveDate) -> Option<(NaiveDate, Decimal)> {
+        self.rates
+            .iter()
+            .filter(|r| r.rate_date <= day && r.rate_date >= oldest)
+            .filter_map(|r| {
+                if r.base_currency == from && r.quote_currency == to {
+                    Some((r.rate_date, true, r.rate))
+                } else if r.base_currency == to && r.quote_currency == from {
+                    Some((r.rate_date, false, Decimal::ONE / r.rate))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|(date, direct, _)| (*date, *direct))
+            .map(|(date, _, rate)| (date, rate))
+    }
+
+    /// How much 1 `from` is worth in `to` on `day`, using rates at most `max_age_days` old.
+    /// Without a rate between the two, converts through a currency both have rates against
+    /// (typically the feed's base), preferring the one whose older rate is newest.
+    pub fn rate(&self, from: &str, to: &str, day: NaiveDate, max_age_days: i64) -> Option<Decimal> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+        let oldest = day - Duration::days(max_age_days);
+        if let Some((_, rate)) = self.pair(from, to, day, oldest) {
+            return Some(rate);
+        }
+        let mut via: Vec<&str> = self
+            .rates
+            .iter()
+            .flat_map(|r| [r.base_currency.as_str(), r.quote_currency.as_str()])
+            .filter(|c| *c != from && *c != to)
+            .collect();
+        via.sort_unstable();
+        via.dedup();
+        via.into_iter()
+            .filter_map(|shared| {
+                let (first_date, first) = self.pair(from, shared, day, oldest)?;
+                let (second_date, second) = self.pair(shared, to, day, oldest)?;
+                Some((first_date.min(second_date), shared, first.checked_mul(second)?))
+            })
+            // Newest wins; ties go to the alphabetically first currency, so the result is stable
+            .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(a.1)))
+            .map(|(_, _, rate)| rate)
+    }
+}
+
+// Normalizes codes and drops nothing silently: one bad entry rejects the batch.
+fn validate(rates: Vec<NewExchangeRate>) -> Result<Vec<NewExchangeRate>, AppError> {
+    rates
+        .into_iter()
+        .map(|r| {
+            let base = currency::parse_code("base_currency", &r.base_currency)?;
+            let quote = currency::parse_code("quote_currency", &r.quote_currency)?;
+            if base == quote {
+                return Err(AppError::BadRequest(format!("{} cannot have a rate against itself", base)));
+            }
+            if r.rate <= Decimal::ZERO {
+                return Err(AppError::BadRequest(format!("Rate {}/{} on {} must be positive", base, quote, r.rate_date)));
+            }
+            Ok(NewExchangeRate { base_currency: base, quote_currency: quote, rate_date: r.rate_date, rate: r.rate })
+        })
+        .collect()
+}
+
+/// Stores rates uploaded by an admin, replacing existing ones for the same pair and day.
+pub async fn upload(pool: &DbPool, rates: Vec<NewExchangeRate>) -> Result<u64, AppError> {
+    if rates.is_empty() {
+        return Err(AppError::BadRequest("No rates given".into()));
+    }
+    repo::upsert_many(pool, &validate(rates)?, "upload").await
+}
+
+/// Pulls the latest rates from the configured source.
+pub async fn sync(pool: &DbPool, provider: &RateProvider) -> Result<u64, AppError> {
+    let source = provider
+        .source
+        .as_ref()
+        .ok_or_else(|| AppError::BadRequest("No exchange rate source configured".into()))?;
+    let rates = validate(source.fetch().await?)?;
+    let written = repo::upsert_many(pool, &rates, source.name()).await?;
+    log::info!("Imported {} exchange rate(s) from {}", written, source.name());
+    Ok(written)
+}
+
+pub async fn list(pool: &DbPool, q: RateQuery) -> Result<Vec<ExchangeRate>, AppError> {
+    let currency = q.currency.as_deref().map(|c| currency::parse_code("currency", c)).transpose()?;
+    let limit = q.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    repo::list(pool, currency.as_deref(), q.from, q.to, limit).await
+}
+
+pub async fn delete(pool: &DbPool, base: &str, quote: &str, rate_date: NaiveDate) -> Result<(), AppError> {
+    let base = currency::parse_code("base_currency", base)?;
+    let quote = currency::parse_code("quote_currency", quote)?;
+    if repo::delete(pool, &base, &quote, rate_date).await? == 0 {
+        return Err(AppError::NotFound("Exchange rate not found".into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn day(raw: &str) -> NaiveDate {
+        NaiveDate::from_str(raw).unwrap()
+    }
+
+    fn dec(raw: &str) -> Decimal {
+        Decimal::from_str(raw).unwrap()
+    }
+
+    fn stored(base: &str, quote: &str, date: &str, rate: &str) -> ExchangeRate {
+        ExchangeRate {
+            base_currency: base.into(),
+            quote_currency: quote.into(),
+            rate_date: day(date),
+            rate: dec(rate),
+            source: "test".into(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn uses_the_newest_direct_or_inverted_rate_within_the_max_age() {
+        let table = RateTable::new(vec![
+            stored("USD", "IDR", "2025-09-01", "16000"),
+            stored("USD", "IDR", "2025-09-03", "16400"),
+            stored("IDR", "USD", "2025-09-03", "0.00005"),
+        ]);
+        assert_eq!(table.rate("USD", "IDR", day("2025-09-02"), 7), Some(dec("16000")));
+        // Same day in both directions: the direct rate wins
+        assert_eq!(table.rate("USD", "IDR", day("2025-09-05"), 7), Some(dec("16400")));
+        assert_eq!(table.rate("IDR", "USD", day("2025-09-02"), 7), Some(Decimal::ONE / dec("16000")));
+        assert_eq!(table.rate("USD", "IDR", day("2025-08-31"), 7), None);
+        assert_eq!(table.rate("USD", "IDR", day("2025-09-12"), 7), None);
+        assert_eq!(table.rate("IDR", "IDR", day("2025-09-12"), 0), Some(Decimal::ONE));
+    }
+
+    #[test]
+    fn triangulates_through_a_shared_base() {
+        // A USD-based feed has no EUR/IDR pair
+        let table = RateTable::new(vec![stored("USD", "IDR", "2025-09-01", "16000"), stored("USD", "EUR", "2025-09-01", "0.8")]);
+        assert_eq!(table.rate("EUR", "IDR", day("2025-09-02"), 7), Some(dec("20000")));
+        assert_eq!(table.rate("IDR", "EUR", day("2025-09-02"), 7), Some(dec("0.00005")));
+        assert_eq!(table.rate("EUR", "IDR", day("2025-09-20"), 7), None);
+        assert_eq!(table.rate("EUR", "KWD", day("2025-09-02"), 7), None);
+    }
+
+    #[test]
+    fn a_direct_rate_wins_over_triangulation_and_the_freshest_base_is_used() {
+        let table = RateTable::new(vec![
+            stored("USD", "IDR", "2025-09-01", "16000"),
+            stored("USD", "EUR", "2025-09-01", "0.8"),
+            stored("GBP", "IDR", "2025-09-04", "21000"),
+            stored("GBP", "EUR", "2025-09-04", "1.25"),
+        ]);
+        assert_eq!(table.rate("EUR", "IDR", day("2025-09-05"), 7), Some(dec("16800")));
+        assert_eq!(table.rate("EUR", "IDR", day("2025-09-02"), 7), Some(dec("20000")));
+        let direct = RateTable::new(vec![stored("EUR", "IDR", "2025-08-30", "19000"), stored("USD", "IDR", "2025-09-01", "16000"), stored("USD", "EUR", "2025-09-01", "0.8")]);
+        assert_eq!(direct.rate("EUR", "IDR", day("2025-09-02"), 7), Some(dec("19000")));
+    }
+
+    #[tokio::test]
+    async fn file_source_reads_a_feed_fixture() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/exchange_rates.json");
+        let source = FileRateSource::new(path.to_string());
+        let mut rates = validate(source.fetch().await.unwrap()).unwrap();
+        rates.sort_by(|a, b| (a.rate_date, &a.quote_currency).cmp(&(b.rate_date, &b.quote_currency)));
+        let flat: Vec<(&str, &str, NaiveDate, Decimal)> =
+            rates.iter().map(|r| (r.base_currency.as_str(), r.quote_currency.as_str(), r.rate_date, r.rate)).collect();
+        assert_eq!(
+            flat,
+            vec![
+                ("USD", "EUR", day("2025-09-01"), dec("0.92")),
+                ("USD", "IDR", day("2025-09-01"), dec("16350.5")),
+                ("USD", "IDR", day("2025-09-02"), dec("16410")),
+                ("USD", "KWD", day("2025-09-02"), dec("0.305")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn file_source_reports_a_missing_file() {
+        let source = FileRateSource::new("/nonexistent/exchange_rates.json".into());
+        assert!(source.fetch().await.is_err());
+    }
+}
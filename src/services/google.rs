@@ -1,50 +1,142 @@
+use std::time::Duration;
+
+use crate::config::AppConfig;
+use crate::db::DbPool;
 use crate::errors::AppError;
-use serde::Deserialize;
-
-#[derive(Debug)]
-pub struct GoogleUser {
-    pub sub: String,
-    pub email: String,
-    pub name: String,
-    pub email_verified: bool,
-    pub aud: String,
+use crate::repositories::settings_repo;
+use crate::services::jwks::{FileKeySource, HttpKeySource, JwksCache, KeySource};
+use crate::services::oidc::{self, VerifiedIdentity};
+
+const GOOGLE_ISSUERS: [&str; 2] = ["accounts.google.com", "https://accounts.google.com"];
+
+/// Verifies Google ID tokens locally against a cached copy of Google's JWKS.
+/// Shared by all workers through `web::Data`.
+pub struct GoogleVerifier {
+    keys: JwksCache,
 }
 
-pub async fn verify_id_token(id_token: &str) -> Result<GoogleUser, AppError> {
-    #[derive(Deserialize)]
-    struct TokenInfo {
-        aud: String,
-        sub: String,
-        email: Option<String>,
-        #[serde(default)]
-        email_verified: Option<String>,
-        name: Option<String>,
-    }
-
-    let resp = reqwest::Client::new()
-        .get("https://oauth2.googleapis.com/tokeninfo")
-        .query(&[("id_token", id_token.to_string())])
-        .send()
-        .await
-        .map_err(|e| AppError::BadRequest(format!("tokeninfo request failed: {}", e)))?;
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let body = resp.text().await.unwrap_or_default();
-        return Err(AppError::BadRequest(format!("tokeninfo {}: {}", status, body)));
-    }
-    let info: TokenInfo = resp
-        .json()
-        .await
-        .map_err(|e| AppError::BadRequest(format!("invalid tokeninfo response: {}", e)))?;
-    let email_verified = match info.email_verified.as_deref() {
-        Some("true") | Some("True") | Some("1") => true,
-        _ => true, 
-    };
-    Ok(GoogleUser {
-        sub: info.sub,
-        email: info.email.ok_or_else(|| AppError::BadRequest("Email not present in token".into()))?,
-        name: info.name.unwrap_or_else(|| "User".to_string()),
-        email_verified,
-        aud: info.aud,
-    })
+impl GoogleVerifier {
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        let source: Box<dyn KeySource> = match &cfg.google_jwks_file {
+            Some(path) => Box::new(FileKeySource::new(path.clone())),
+            None => Box::new(HttpKeySource::new(cfg.google_jwks_url.clone())),
+        };
+        Self { keys: JwksCache::new(source, Duration::from_secs(cfg.google_jwks_refresh_secs)) }
+    }
+
+    /// Checks the RS256 signature, issuer, expiry and that `aud` is one of `allowed_audiences`.
+    pub async fn verify_id_token(&self, id_token: &str, allowed_audiences: &[String]) -> Result<VerifiedIdentity, AppError> {
+        let issuers: Vec<String> = GOOGLE_ISSUERS.iter().map(|s| s.to_string()).collect();
+        oidc::verify_id_token(&self.keys, id_token, &issuers, allowed_audiences).await
+    }
+}
+
+/// Allowed client ids: env GOOGLE_CLIENT_ID (can be comma-separated), plus app_settings
+/// google_client_id and/or google_client_ids.
+pub async fn allowed_client_ids(pool: &DbPool, cfg: &AppConfig) -> Result<Vec<String>, AppError> {
+    let mut allowed: Vec<String> = Vec::new();
+    if let Some(s) = cfg.google_client_id.clone() { allowed.push(s); }
+    if let Some(s) = settings_repo::get_value(pool, "google_client_id").await? { allowed.push(s); }
+    if let Some(s) = settings_repo::get_value(pool, "google_client_ids").await? { allowed.push(s); }
+    let allowed: Vec<String> = allowed
+        .into_iter()
+        .flat_map(|v| v.split(',').map(|x| x.trim().to_string()).collect::<Vec<_>>())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if allowed.is_empty() {
+        return Err(AppError::BadRequest("GOOGLE_CLIENT_ID not configured".into()));
+    }
+    Ok(allowed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::{json, Value};
+
+    const CLIENT_ID: &str = "test-client.apps.googleusercontent.com";
+    const SIGNING_KEY: &str = include_str!("../../tests/fixtures/id_token_signing_key.pem");
+
+    fn verifier() -> GoogleVerifier {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/id_token_jwks.json");
+        GoogleVerifier { keys: JwksCache::new(Box::new(FileKeySource::new(path.into())), Duration::from_secs(3600)) }
+    }
+
+    fn claims() -> Value {
+        json!({
+            "iss": "https://accounts.google.com",
+            "aud": CLIENT_ID,
+            "sub": "1234567890",
+            "email": "Someone@Example.com",
+            "email_verified": true,
+            "name": "Someone",
+            "exp": Utc::now().timestamp() + 600,
+        })
+    }
+
+    fn sign(claims: &Value, kid: &str) -> String {
+        let mut header = Header::new(jsonwebtoken::Algorithm::RS256);
+        header.kid = Some(kid.into());
+        encode(&header, claims, &EncodingKey::from_rsa_pem(SIGNING_KEY.as_bytes()).unwrap()).unwrap()
+    }
+
+    async fn verify(claims: &Value, kid: &str) -> Result<VerifiedIdentity, AppError> {
+        verifier().verify_id_token(&sign(claims, kid), &[CLIENT_ID.to_string()]).await
+    }
+
+    fn rejected(result: Result<VerifiedIdentity, AppError>) -> String {
+        match result {
+            Err(AppError::BadRequest(msg)) => msg,
+            other => panic!("expected a rejected token, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn accepts_a_valid_token() {
+        let identity = verify(&claims(), "test-key-1").await.unwrap();
+        assert_eq!(identity.subject, "1234567890");
+        assert_eq!(identity.email.as_deref(), Some("someone@example.com"));
+        assert!(identity.email_verified);
+        assert_eq!(identity.name.as_deref(), Some("Someone"));
+    }
+
+    #[tokio::test]
+    async fn rejects_another_audience() {
+        let mut claims = claims();
+        claims["aud"] = json!("someone-else.apps.googleusercontent.com");
+        assert!(rejected(verify(&claims, "test-key-1").await).contains("Invalid ID token"));
+    }
+
+    #[tokio::test]
+    async fn rejects_another_issuer() {
+        let mut claims = claims();
+        claims["iss"] = json!("https://evil.example.com");
+        assert!(rejected(verify(&claims, "test-key-1").await).contains("Invalid ID token"));
+    }
+
+    #[tokio::test]
+    async fn rejects_an_expired_token() {
+        let mut claims = claims();
+        // Past the default leeway of 60 seconds
+        claims["exp"] = json!(Utc::now().timestamp() - 300);
+        assert!(rejected(verify(&claims, "test-key-1").await).contains("Invalid ID token"));
+    }
+
+    #[tokio::test]
+    async fn reports_unverified_email() {
+        let mut claims = claims();
+        claims["email_verified"] = json!(false);
+        assert!(!verify(&claims, "test-key-1").await.unwrap().email_verified);
+        claims["email_verified"] = json!("false");
+        assert!(!verify(&claims, "test-key-1").await.unwrap().email_verified);
+        claims["email_verified"] = json!("true");
+        assert!(verify(&claims, "test-key-1").await.unwrap().email_verified);
+    }
+
+    #[tokio::test]
+    async fn rejects_an_unknown_key() {
+        assert_eq!(rejected(verify(&claims(), "rotated-away").await), "ID token signed by an unknown key");
+    }
 }
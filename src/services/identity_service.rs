@@ -0,0 +1,120 @@
+use uuid::Uuid;
+
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::identity::UserIdentity;
+use crate::models::user::User;
+use crate::repositories::{identity_repo, passkey_repo, user_repo};
+use crate::services::audit_service::{self, Event, EventKind};
+use crate::services::google::{self, GoogleVerifier};
+use crate::services::oidc::{self, OidcRegistry, VerifiedIdentity};
+
+pub const GOOGLE: &str = "google";
+
+fn already_linked(provider: &str) -> impl FnOnce(AppError) -> AppError + '_ {
+    move |e| match e {
+        AppError::Db(s) if s.contains("unique") => {
+            AppError::Conflict(format!("This {} identity or account is already linked elsewhere", provider))
+        }
+        other => other,
+    }
+}
+
+/// Verifies an ID token from the built-in Google flow or a configured OIDC provider.
+pub async fn verify(
+    pool: &DbPool,
+    cfg: &AppConfig,
+    google_verifier: &GoogleVerifier,
+    registry: &OidcRegistry,
+    provider: &str,
+    id_token: &str,
+) -> Result<VerifiedIdentity, AppError> {
+    if provider == GOOGLE {
+        let allowed = google::allowed_client_ids(pool, cfg).await?;
+        google_verifier.verify_id_token(id_token, &allowed).await
+    } else {
+        let provider = oidc::find_provider(pool, provider).await?;
+        registry.verify(&provider, id_token).await
+    }
+}
+
+/// Finds the account behind an external identity. Unknown identities are linked to the
+/// account with the same (provider-verified) email, or get a new passwordless account.
+pub async fn resolve_user(pool: &DbPool, client: &ClientInfo, provider: &str, identity: &VerifiedIdentity) -> Result<User, AppError> {
+    if let Some(user_id) = identity_repo::find_user_id(pool, provider, &identity.subject).await? {
+        return user_repo::get_by_id(pool, user_id).await;
+    }
+    let email = identity
+        .email
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest(format!("{} did not share an email address", provider)))?;
+    // Linking by email is only safe when the provider vouches for the address
+    if !identity.email_verified {
+        return Err(AppError::BadRequest(format!("The {} account's email address is not verified", provider)));
+    }
+    let user = match user_repo::get_by_email(pool, email).await? {
+        Some(u) => {
+            identity_repo::create(pool, u.id, provider, &identity.subject, Some(email))
+                .await
+                .map_err(already_linked(provider))?;
+            let event = Event::new(EventKind::IdentityLinked, client).user(u.id).email(email);
+            audit_service::success(pool, event.detail(format!("{} (matched by email)", provider))).await;
+            if !u.is_verified {
+                sqlx::query("UPDATE users SET is_verified=true WHERE id=$1")
+                    .bind(u.id)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| AppError::Db(e.to_string()))?;
+            }
+            user_repo::get_by_id(pool, u.id).await?
+        }
+        None => {
+            let name = identity.name.clone().unwrap_or_else(|| "User".to_string());
+            let created = user_repo::insert_external(pool, Uuid::new_v4(), &name, email, provider).await?;
+            identity_repo::create(pool, created.id, provider, &identity.subject, Some(email))
+                .await
+                .map_err(already_linked(provider))?;
+            audit_service::success(pool, Event::new(EventKind::Register, client).user(created.id).email(email).detail(provider)).await;
+            audit_service::success(pool, Event::new(EventKind::IdentityLinked, client).user(created.id).email(email).detail(provider)).await;
+            created
+        }
+    };
+    Ok(user)
+}
+
+pub async fn list(pool: &DbPool, user_id: Uuid) -> Result<Vec<UserIdentity>, AppError> {
+    identity_repo::list_for_user(pool, user_id).await
+}
+
+/// Links an identity the signed-in user has just proven control of.
+pub async fn link(pool: &DbPool, client: &ClientInfo, user_id: Uuid, provider: &str, identity: &VerifiedIdentity) -> Result<UserIdentity, AppError> {
+    let linked = match identity_repo::find_user_id(pool, provider, &identity.subject).await? {
+        Some(owner) if owner == user_id => Err(AppError::Conflict(format!("This {} identity is already linked", provider))),
+        Some(_) => Err(AppError::Conflict(format!("This {} identity is linked to another account", provider))),
+        None => identity_repo::create(pool, user_id, provider, &identity.subject, identity.email.as_deref())
+            .await
+            .map_err(|e| match e {
+                AppError::Db(s) if s.contains("unique") => {
+                    AppError::Conflict(format!("A different {} identity is already linked; unlink it first", provider))
+                }
+                other => other,
+            }),
+    };
+    audit_service::outcome(pool, Event::new(EventKind::IdentityLinked, client).user(user_id).detail(provider), linked).await
+}
+
+/// Unlinks an identity, refusing to remove the account's last way to sign in.
+pub async fn unlink(pool: &DbPool, client: &ClientInfo, user_id: Uuid, identity_id: Uuid) -> Result<(), AppError> {
+    let user = user_repo::get_by_id(pool, user_id).await?;
+    let others = identity_repo::count_for_user(pool, user_id).await? + passkey_repo::count_for_user(pool, user_id).await?;
+    if user.password_hash.is_empty() && others <= 1 {
+        return Err(AppError::BadRequest("Set a password before removing your last sign-in method".into()));
+    }
+    if identity_repo::delete(pool, identity_id, user_id).await? == 0 {
+        return Err(AppError::NotFound("Identity not found".into()));
+    }
+    audit_service::success(pool, Event::new(EventKind::IdentityUnlinked, client).user(user_id).detail(identity_id.to_string())).await;
+    Ok(())
+}
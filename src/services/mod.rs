@@ -1,7 +1,29 @@
 pub mod auth_service;
+pub mod account_service;
+pub mod session_service;
+pub mod device_service;
+pub mod api_token_service;
+pub mod totp;
+pub mod two_factor_service;
+pub mod throttle_service;
+pub mod audit_service;
+pub mod otp_service;
+pub mod password_policy;
+pub mod jwks;
+pub mod oidc;
 pub mod google;
+pub mod identity_service;
+pub mod webauthn;
+pub mod passkey_service;
 pub mod category_service;
 pub mod transaction_service;
+pub mod attachment_service;
+pub mod money_account_service;
+pub mod transfer_service;
+pub mod recurrence;
+pub mod recurring_service;
+pub mod currency;
+pub mod exchange_rates;
 pub mod summary_service;
 pub mod admin_auth_service;
 pub mod admin_user_service;
@@ -0,0 +1,167 @@
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::money_account::{AccountBalance, MoneyAccount};
+use crate::dto::money_account::{AccountListQuery, CreateMoneyAccount, UpdateMoneyAccount};
+use crate::repositories::{money_account_repo as repo, user_repo};
+use crate::services::currency;
+
+pub const KINDS: [&str; 7] = ["cash", "bank", "e_wallet", "credit_card", "savings", "investment", "other"];
+const MAX_NAME_LEN: usize = 100;
+
+fn validate_kind(kind: &str) -> Result<(), AppError> {
+    if KINDS.contains(&kind) { Ok(()) } else { Err(AppError::BadRequest(format!("kind must be one of: {}", KINDS.join(", ")))) }
+}
+
+fn validate_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::BadRequest(format!("Name must be 1-{} characters", MAX_NAME_LEN)));
+    }
+    Ok(name.to_string())
+}
+
+fn name_taken(e: AppError) -> AppError {
+    match e {
+        AppError::Db(s) if s.contains("unique") => AppError::Conflict("An account with this name already exists".into()),
+        other => other,
+    }
+}
+
+pub async fn list(pool: &DbPool, user_id: Uuid, q: AccountListQuery) -> Result<Vec<MoneyAccount>, AppError> {
+    repo::list_by_user(pool, user_id, q.as_of, q.include_archived).await
+}
+
+pub async fn get(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<MoneyAccount, AppError> {
+    repo::get_by_id_user(pool, id, user_id, None).await?.ok_or_else(|| AppError::NotFound("Account not found".into()))
+}
+
+pub async fn create(pool: &DbPool, user_id: Uuid, payload: CreateMoneyAccount) -> Result<MoneyAccount, AppError> {
+    let name = validate_name(&payload.name)?;
+    validate_kind(&payload.kind)?;
+    let currency = match payload.currency {
+        Some(c) => currency::parse_code("currency", &c)?,
+        None => user_repo::get_by_id(pool, user_id).await?.preferred_currency,
+    };
+    let id = Uuid::new_v4();
+    let opening_balance = currency::normalize_amount("opening_balance", payload.opening_balance.unwrap_or(Decimal::ZERO), &currency)?;
+    repo::insert(pool, id, user_id, &name, &payload.kind, &currency, opening_balance).await.map_err(name_taken)?;
+    get(pool, user_id, id).await
+}
+
+pub async fn update(pool: &DbPool, user_id: Uuid, id: Uuid, payload: UpdateMoneyAccount) -> Result<MoneyAccount, AppError> {
+    let current = get(pool, user_id, id).await?;
+    let name = match payload.name {
+        Some(n) => validate_name(&n)?,
+        None => current.name,
+    };
+    if let Some(kind) = &payload.kind { validate_kind(kind)?; }
+    let kind = payload.kind.unwrap_or(current.kind);
+    let opening_balance = match payload.opening_balance {
+        Some(amount) => currency::normalize_amount("opening_balance", amount, &current.currency)?,
+        None => current.opening_balance,
+    };
+    let archived = payload.archived.unwrap_or(current.archived);
+    repo::update(pool, id, user_id, &name, &kind, opening_balance, archived).await.map_err(name_taken)?;
+    get(pool, user_id, id).await
+}
+
+pub async fn delete(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let affected = repo::delete(pool, id, user_id).await.map_err(|e| match e {
+        AppError::Db(s) if s.contains("foreign key") => {
+            AppError::Conflict("Account still has transactions, transfers or recurring transactions; archive it instead".into())
+        }
+        other => other,
+    })?;
+    if affected == 0 { return Err(AppError::NotFound("Account not found".into())); }
+    Ok(())
+}
+
+pub async fn balance(pool: &DbPool, user_id: Uuid, id: Uuid, as_of: Option<chrono::NaiveDate>) -> Result<AccountBalance, AppError> {
+    let account = repo::get_by_id_user(pool, id, user_id, as_of).await?.ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    Ok(AccountBalance { account_id: account.id, currency: account.currency, as_of, balance: account.balance })
+}
+
+/// Checks that transactions may be booked to the account, and returns its currency.
+pub async fn ensure_usable(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<String, AppError> {
+    match repo::find_state(pool, id, user_id).await? {
+        Some((false, currency)) => Ok(currency),
+        Some((true, _)) => Err(AppError::BadRequest("Account is archived".into())),
+        None => Err(AppError::BadRequest("Invalid account for user".into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::services::{category_service, transaction_service, transfer_service};
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    fn on(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    async fn account(pool: &DbPool, user_id: Uuid, name: &str, opening: &str) -> Uuid {
+        let payload = serde_json::from_value(json!({ "name": name, "kind": "bank", "currency": "USD", "opening_balance": opening })).unwrap();
+        create(pool, user_id, payload).await.unwrap().id
+    }
+
+    async fn category(pool: &DbPool, user_id: Uuid, kind: &str) -> Uuid {
+        let payload = serde_json::from_value(json!({ "name": kind, "kind": kind })).unwrap();
+        category_service::create(pool, user_id, payload).await.unwrap().id
+    }
+
+    async fn book(pool: &DbPool, user_id: Uuid, account_id: Uuid, category_id: Uuid, amount: &str, day: u32) {
+        let payload = serde_json::from_value(json!({
+            "category_id": category_id, "account_id": account_id, "amount": amount, "occurred_on": on(day),
+        }))
+        .unwrap();
+        transaction_service::create(pool, user_id, payload).await.unwrap();
+    }
+
+    async fn balance_on(pool: &DbPool, user_id: Uuid, id: Uuid, as_of: Option<NaiveDate>) -> Decimal {
+        balance(pool, user_id, id, as_of).await.unwrap().balance
+    }
+
+    #[tokio::test]
+    async fn balance_adds_income_and_subtracts_expenses_up_to_the_date() {
+        let Some(pool) = db::test_pool().await else { return };
+        let user_id = db::test_user(&pool).await;
+        let wallet = account(&pool, user_id, "Wallet", "100.00").await;
+        let (salary, food) = (category(&pool, user_id, "income").await, category(&pool, user_id, "expense").await);
+
+        assert_eq!(balance_on(&pool, user_id, wallet, None).await, Decimal::new(10000, 2));
+        book(&pool, user_id, wallet, salary, "250.00", 1).await;
+        book(&pool, user_id, wallet, food, "40.50", 10).await;
+        book(&pool, user_id, wallet, food, "9.50", 20).await;
+
+        assert_eq!(balance_on(&pool, user_id, wallet, None).await, Decimal::new(30000, 2));
+        // Inclusive of the day itself; before the first transaction only the opening balance counts
+        assert_eq!(balance_on(&pool, user_id, wallet, Some(on(10))).await, Decimal::new(30950, 2));
+        assert_eq!(balance_on(&pool, user_id, wallet, Some(NaiveDate::from_ymd_opt(2026, 2, 28).unwrap())).await, Decimal::new(10000, 2));
+        assert!(matches!(balance(&pool, Uuid::new_v4(), wallet, None).await, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn transfers_move_money_between_accounts_and_charge_the_fee() {
+        let Some(pool) = db::test_pool().await else { return };
+        let user_id = db::test_user(&pool).await;
+        let bank = account(&pool, user_id, "Bank", "500.00").await;
+        let savings = account(&pool, user_id, "Savings", "0").await;
+        let fees = category(&pool, user_id, "expense").await;
+
+        let payload = serde_json::from_value(json!({
+            "from_account_id": bank, "to_account_id": savings, "amount": "200.00", "fee": "2.50",
+            "fee_category_id": fees, "occurred_on": on(15),
+        }))
+        .unwrap();
+        transfer_service::create(&pool, user_id, payload).await.unwrap();
+
+        assert_eq!(balance_on(&pool, user_id, bank, None).await, Decimal::new(29750, 2));
+        assert_eq!(balance_on(&pool, user_id, savings, None).await, Decimal::new(20000, 2));
+        assert_eq!(balance_on(&pool, user_id, savings, Some(on(14))).await, Decimal::ZERO);
+    }
+}
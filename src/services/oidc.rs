@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::repositories::settings_repo;
+use crate::services::jwks::{unavailable, FileKeySource, HttpKeySource, JwksCache, KeySource};
+
+// Asymmetric algorithms only; an HMAC "key" from a JWKS would be public knowledge
+const ALLOWED_ALGORITHMS: [Algorithm; 6] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+/// What an identity provider tells us about the person behind a verified ID token.
+#[derive(Debug)]
+pub struct VerifiedIdentity {
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+/// One entry of the `oidc_providers` setting, e.g.
+/// `{"name":"keycloak","issuer":"https://sso.example.com/realms/staff","client_ids":["finance"]}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderConfig {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub issuer: String,
+    pub client_ids: Vec<String>,
+    /// Treat the `email` claim as verified even without `email_verified`; only for providers
+    /// that never hand out addresses they have not checked (e.g. a company directory).
+    #[serde(default)]
+    pub trust_email: bool,
+    /// Read signing keys from a local JWKS file and skip discovery. Honoured in development
+    /// only, as the setting is editable by any admin and would let them read server files.
+    #[serde(default)]
+    pub jwks_file: Option<String>,
+    /// Seconds between refreshes of the provider's signing keys.
+    #[serde(default)]
+    pub jwks_refresh_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProviderInfo {
+    pub name: String,
+    pub display_name: String,
+}
+
+const DEFAULT_JWKS_REFRESH_SECS: u64 = 3600;
+
+/// Provider names reserved for built-in flows.
+const RESERVED_PROVIDERS: [&str; 1] = ["google"];
+
+/// Reads the configured providers from `app_settings`.
+pub async fn load_providers(pool: &DbPool) -> Result<Vec<ProviderConfig>, AppError> {
+    let Some(raw) = settings_repo::get_value(pool, "oidc_providers").await? else { return Ok(Vec::new()) };
+    if raw.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let providers: Vec<ProviderConfig> = serde_json::from_str(&raw)
+        .map_err(|e| unavailable(format!("invalid oidc_providers setting: {}", e)))?;
+    Ok(providers
+        .into_iter()
+        .filter(|p| !RESERVED_PROVIDERS.contains(&p.name.as_str()))
+        .collect())
+}
+
+pub async fn find_provider(pool: &DbPool, name: &str) -> Result<ProviderConfig, AppError> {
+    load_providers(pool)
+        .await?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown sign-in provider '{}'", name)))
+}
+
+pub async fn list_providers(pool: &DbPool) -> Result<Vec<ProviderInfo>, AppError> {
+    Ok(load_providers(pool)
+        .await?
+        .into_iter()
+        .map(|p| ProviderInfo { display_name: p.display_name.unwrap_or_else(|| p.name.clone()), name: p.name })
+        .collect())
+}
+
+/// Checks an ID token's signature against `keys`, and its `iss`, `aud` and `exp` claims.
+pub async fn verify_id_token(keys: &JwksCache, id_token: &str, issuers: &[String], audiences: &[String]) -> Result<VerifiedIdentity, AppError> {
+    #[derive(Deserialize)]
+    struct IdClaims {
+        sub: String,
+        email: Option<String>,
+        #[serde(default)]
+        email_verified: Option<serde_json::Value>,
+        name: Option<String>,
+    }
+
+    if audiences.is_empty() {
+        return Err(AppError::BadRequest("Sign-in provider has no client id configured".into()));
+    }
+    let header = decode_header(id_token).map_err(|_| AppError::BadRequest("Malformed ID token".into()))?;
+    if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+        return Err(AppError::BadRequest("Unexpected ID token algorithm".into()));
+    }
+    let kid = header.kid.ok_or_else(|| AppError::BadRequest("ID token has no key id".into()))?;
+    let key = keys.key_for(&kid).await?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(issuers);
+    validation.set_audience(audiences);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    let claims = decode::<IdClaims>(id_token, &key, &validation)
+        .map_err(|e| AppError::BadRequest(format!("Invalid ID token: {}", e)))?
+        .claims;
+
+    // Normally a boolean, but some providers send the string form
+    let email_verified = match claims.email_verified {
+        Some(serde_json::Value::Bool(b)) => b,
+        Some(serde_json::Value::String(s)) => s == "true",
+        _ => false,
+    };
+    Ok(VerifiedIdentity {
+        subject: claims.sub,
+        email: claims.email.map(|e| e.trim().to_lowercase()),
+        email_verified,
+        name: claims.name,
+    })
+}
+
+struct Discovered {
+    issuer: String,
+    keys: JwksCache,
+}
+
+/// Discovery documents and key sets of the configured OIDC providers, cached per
+/// provider and issuer. Shared by all workers through `web::Data`.
+pub struct OidcRegistry {
+    http: reqwest::Client,
+    allow_jwks_files: bool,
+    discovered: RwLock<HashMap<String, Arc<Discovered>>>,
+}
+
+impl OidcRegistry {
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            allow_jwks_files: cfg.is_development(),
+            discovered: RwLock::new(HashMap::new()),
+        }
+    }
+
+    async fn discover(&self, provider: &ProviderConfig) -> Result<Arc<Discovered>, AppError> {
+        #[derive(Deserialize)]
+        struct Metadata {
+            issuer: String,
+            jwks_uri: String,
+        }
+
+        // Keyed by issuer and refresh interval too, so editing the setting takes effect without a restart
+        let refresh_secs = provider.jwks_refresh_secs.unwrap_or(DEFAULT_JWKS_REFRESH_SECS);
+        let cache_key = format!("{}|{}|{}", provider.name, provider.issuer, refresh_secs);
+        if let Some(d) = self.discovered.read().unwrap_or_else(|e| e.into_inner()).get(&cache_key) {
+            return Ok(d.clone());
+        }
+
+        let (issuer, source): (String, Box<dyn KeySource>) = match &provider.jwks_file {
+            Some(path) if self.allow_jwks_files => (provider.issuer.clone(), Box::new(FileKeySource::new(path.clone()))),
+            Some(_) => return Err(unavailable(format!("jwks_file of OIDC provider {} is only honoured in development", provider.name))),
+            None => {
+                let url = format!("{}/.well-known/openid-configuration", provider.issuer.trim_end_matches('/'));
+                let resp = self
+                    .http
+                    .get(&url)
+                    .send()
+                    .await
+                    .map_err(|e| unavailable(format!("OIDC discovery for {} failed: {}", provider.name, e)))?;
+                if !resp.status().is_success() {
+                    return Err(unavailable(format!("OIDC discovery for {} returned {}", provider.name, resp.status())));
+                }
+                let meta: Metadata = resp
+                    .json()
+                    .await
+                    .map_err(|e| unavailable(format!("invalid OIDC discovery document for {}: {}", provider.name, e)))?;
+                if meta.issuer.trim_end_matches('/') != provider.issuer.trim_end_matches('/') {
+                    return Err(unavailable(format!(
+                        "OIDC discovery for {} reported issuer {}, expected {}",
+                        provider.name, meta.issuer, provider.issuer
+                    )));
+                }
+                (meta.issuer, Box::new(HttpKeySource::new(meta.jwks_uri)))
+            }
+        };
+        let discovered = Arc::new(Discovered { issuer, keys: JwksCache::new(source, Duration::from_secs(refresh_secs)) });
+        self.discovered
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(cache_key, discovered.clone());
+        Ok(discovered)
+    }
+
+    pub async fn verify(&self, provider: &ProviderConfig, id_token: &str) -> Result<VerifiedIdentity, AppError> {
+        let discovered = self.discover(provider).await?;
+        let mut identity = verify_id_token(&discovered.keys, id_token, std::slice::from_ref(&discovered.issuer), &provider.client_ids).await?;
+        identity.email_verified |= provider.trust_email && identity.email.is_some();
+        Ok(identity)
+    }
+}
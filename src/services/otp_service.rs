@@ -0,0 +1,162 @@
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use uuid::Uuid;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use crate::auth::{create_purpose_jwt, verify_purpose_jwt};
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::mailer;
+use crate::repositories::{otp_repo, settings_repo};
+use crate::services::throttle_service::{self, Action};
+
+/// OTP behaviour, tunable by admins through `app_settings`.
+#[derive(Debug, Clone)]
+pub struct OtpPolicy {
+    pub expiry_minutes: i64,
+    pub resend_cooldown_seconds: i64,
+    pub length: usize,
+    /// Wrong guesses after which all outstanding codes of that purpose are invalidated
+    pub max_attempts: i32,
+}
+
+impl OtpPolicy {
+    pub async fn load(pool: &DbPool) -> Result<Self, AppError> {
+        Ok(Self {
+            expiry_minutes: settings_repo::get_or(pool, "otp_expiry_minutes", 10i64).await?.clamp(1, 24 * 60),
+            resend_cooldown_seconds: settings_repo::get_or(pool, "otp_resend_cooldown_seconds", 120i64).await?.max(0),
+            length: settings_repo::get_or(pool, "otp_length", 6usize).await?.clamp(4, 10),
+            max_attempts: settings_repo::get_or(pool, "otp_max_attempts", 5i32).await?.max(1),
+        })
+    }
+}
+
+fn generate_code(length: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..length).map(|_| char::from(b'0' + rng.gen_range(0..10u8))).collect()
+}
+
+// Keyed so a leaked table cannot be brute-forced offline, and bound to the user and
+// purpose so a code can never be replayed elsewhere.
+fn hash_code(cfg: &AppConfig, user_id: Uuid, purpose: &str, code: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(cfg.otp_hash_key.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}:{}:{}", user_id, purpose, code).as_bytes());
+    URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+}
+
+/// Purpose of passwordless sign-in codes.
+pub const LOGIN: &str = "login";
+// Audience of the signed sign-in links; their subject is the id of the code row
+const LOGIN_LINK_AUDIENCE: &str = "finance-user-login-link";
+
+// Stores a fresh code of `purpose`, replacing any outstanding one. Enforces the resend
+// cooldown unless `skip_cooldown` is set.
+async fn store_new_code(
+    pool: &DbPool,
+    cfg: &AppConfig,
+    user_id: Uuid,
+    purpose: &str,
+    target_email: Option<&str>,
+    skip_cooldown: bool,
+) -> Result<(OtpPolicy, String, Uuid), AppError> {
+    let policy = OtpPolicy::load(pool).await?;
+    if !skip_cooldown {
+        if let Some(created_at) = otp_repo::last_created_at(pool, user_id, purpose).await? {
+            let elapsed = (Utc::now() - created_at).num_seconds().max(0);
+            if elapsed < policy.resend_cooldown_seconds {
+                let wait = policy.resend_cooldown_seconds - elapsed;
+                return Err(AppError::TooManyRequests(format!("Please wait {}s before requesting another code", wait), wait as u64));
+            }
+        }
+    }
+    let code = generate_code(policy.length);
+    let expires_at = Utc::now() + Duration::minutes(policy.expiry_minutes);
+    let id = otp_repo::create(pool, user_id, &hash_code(cfg, user_id, purpose, &code), purpose, target_email, expires_at).await?;
+    Ok((policy, code, id))
+}
+
+fn log_delivery(cfg: &AppConfig, sent: Result<(), AppError>, purpose: &str, email: &str, code: &str) {
+    match sent {
+        Ok(_) => log::info!("{} OTP sent to {}", purpose, email),
+        // Only a development setup may fall back to reading codes from the log
+        Err(e) if cfg.is_development() => log::warn!("SMTP failed ({}). {} OTP for {} is {} (dev)", e, purpose, email, code),
+        Err(e) => log::warn!("SMTP failed ({}); {} OTP for {} was not delivered", e, purpose, email),
+    }
+}
+
+/// Generates, stores and emails a new code, replacing any outstanding code of the same
+/// purpose. Enforces the resend cooldown unless `skip_cooldown` is set.
+pub async fn issue(pool: &DbPool, cfg: &AppConfig, user_id: Uuid, email: &str, purpose: &str, skip_cooldown: bool) -> Result<(), AppError> {
+    let (policy, code, _) = store_new_code(pool, cfg, user_id, purpose, None, skip_cooldown).await?;
+    let sent = mailer::send_otp(pool, email, &code, policy.expiry_minutes).await;
+    log_delivery(cfg, sent, purpose, email, &code);
+    Ok(())
+}
+
+/// Emails a passwordless sign-in code, plus a signed single-use link when the
+/// `magic_link_url` setting is configured. Code and link are the same credential:
+/// using either one uses up both.
+pub async fn issue_login(pool: &DbPool, cfg: &AppConfig, user_id: Uuid, email: &str) -> Result<(), AppError> {
+    let (policy, code, otp_id) = store_new_code(pool, cfg, user_id, LOGIN, None, false).await?;
+    let link = match settings_repo::get_value(pool, "magic_link_url").await? {
+        Some(base) if !base.trim().is_empty() => {
+            let token = create_purpose_jwt(otp_id, LOGIN_LINK_AUDIENCE, Duration::minutes(policy.expiry_minutes), cfg)?;
+            let sep = if base.contains('?') { '&' } else { '?' };
+            Some(format!("{}{}token={}", base.trim(), sep, token))
+        }
+        _ => None,
+    };
+    let sent = mailer::send_login_code(pool, email, &code, link.as_deref(), policy.expiry_minutes).await;
+    log_delivery(cfg, sent, LOGIN, email, &code);
+    Ok(())
+}
+
+/// Purpose of codes confirming a new email address.
+pub const EMAIL_CHANGE: &str = "email_change";
+
+/// Sends a confirmation code to `new_email`, remembering it as the code's target.
+pub async fn issue_email_change(pool: &DbPool, cfg: &AppConfig, user_id: Uuid, new_email: &str) -> Result<(), AppError> {
+    let (policy, code, _) = store_new_code(pool, cfg, user_id, EMAIL_CHANGE, Some(new_email), false).await?;
+    let sent = mailer::send_otp(pool, new_email, &code, policy.expiry_minutes).await;
+    log_delivery(cfg, sent, EMAIL_CHANGE, new_email, &code);
+    Ok(())
+}
+
+/// Redeems a sign-in link, returning the user it was issued to.
+pub async fn consume_login_link(pool: &DbPool, cfg: &AppConfig, token: &str) -> Result<Uuid, AppError> {
+    let invalid = || AppError::BadRequest("Invalid or expired sign-in link".into());
+    let claims = verify_purpose_jwt(token, LOGIN_LINK_AUDIENCE, cfg).map_err(|_| invalid())?;
+    otp_repo::consume_by_id(pool, claims.sub, LOGIN).await?.ok_or_else(invalid)
+}
+
+/// Looks up a matching unused code. A wrong guess counts against every outstanding code of
+/// that purpose and against the account/IP throttle for `action`.
+pub async fn consume(
+    pool: &DbPool,
+    cfg: &AppConfig,
+    client: &ClientInfo,
+    action: Action,
+    user_id: Uuid,
+    code: &str,
+    purpose: &str,
+) -> Result<Uuid, AppError> {
+    let account = user_id.to_string();
+    throttle_service::check(pool, action, &account, client).await?;
+    match otp_repo::find_valid(pool, user_id, &hash_code(cfg, user_id, purpose, code.trim()), purpose).await? {
+        Some(id) => {
+            throttle_service::record_success(pool, action, &account).await?;
+            Ok(id)
+        }
+        None => {
+            let policy = OtpPolicy::load(pool).await?;
+            otp_repo::register_failed_attempt(pool, user_id, purpose, policy.max_attempts).await?;
+            throttle_service::record_failure(pool, action, &account, client).await?;
+            Err(AppError::BadRequest("Invalid or expired code".into()))
+        }
+    }
+}
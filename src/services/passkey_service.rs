@@ -0,0 +1,183 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::client::ClientInfo;
+use crate::db::DbPool;
+use crate::dto::passkey::{
+    AuthenticatorSelection, CeremonyStart, CreationOptions, CredentialDescriptor, CredentialParameter, FinishLogin,
+    FinishRegistration, RelyingPartyEntity, RequestOptions, UserEntity,
+};
+use crate::errors::AppError;
+use crate::models::passkey::{NewPasskey, PublicPasskey};
+use crate::models::user::User;
+use crate::repositories::{identity_repo, passkey_repo, settings_repo, user_repo};
+use crate::services::audit_service::{self, Event, EventKind};
+use crate::services::webauthn::{self, RelyingParty, SUPPORTED_ALGORITHMS};
+
+const CHALLENGE_TTL_SECS: i64 = 300;
+const REGISTER: &str = "register";
+const LOGIN: &str = "login";
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Reads the relying party from `app_settings`: `webauthn_rp_id` (required),
+/// `webauthn_rp_name` and `webauthn_origins` (comma-separated, default `https://<rp id>`).
+pub async fn relying_party(pool: &DbPool) -> Result<RelyingParty, AppError> {
+    let id = settings_repo::get_value(pool, "webauthn_rp_id")
+        .await?
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| AppError::BadRequest("Passkeys are not configured".into()))?;
+    let name = settings_repo::get_or(pool, "webauthn_rp_name", "Finance Tracker".to_string()).await?;
+    let origins: Vec<String> = settings_repo::get_value(pool, "webauthn_origins")
+        .await?
+        .map(|v| v.split(',').map(|o| o.trim().trim_end_matches('/').to_string()).filter(|o| !o.is_empty()).collect())
+        .unwrap_or_default();
+    let origins = if origins.is_empty() { vec![format!("https://{}", id)] } else { origins };
+    Ok(RelyingParty { id, name, origins })
+}
+
+async fn issue_challenge(pool: &DbPool, user_id: Option<Uuid>, purpose: &str) -> Result<(Uuid, String), AppError> {
+    let challenge = webauthn::generate_challenge();
+    let expires_at = Utc::now() + Duration::seconds(CHALLENGE_TTL_SECS);
+    let id = passkey_repo::create_challenge(pool, user_id, purpose, &challenge, expires_at).await?;
+    Ok((id, challenge))
+}
+
+fn expired() -> AppError {
+    AppError::BadRequest("Passkey challenge expired or already used; start again".into())
+}
+
+// Credential ids arrive in base64url with or without padding
+pub(crate) fn normalize_credential_id(id: &str) -> String {
+    id.trim().trim_end_matches('=').to_string()
+}
+
+/// Options for `navigator.credentials.create()`. Passkeys are discoverable credentials, so
+/// signing in later needs neither an email address nor a password.
+pub async fn start_registration(pool: &DbPool, user_id: Uuid) -> Result<CeremonyStart<CreationOptions>, AppError> {
+    let rp = relying_party(pool).await?;
+    let user = user_repo::get_by_id(pool, user_id).await?;
+    let existing = passkey_repo::list_for_user(pool, user_id).await?;
+    let (challenge_id, challenge) = issue_challenge(pool, Some(user_id), REGISTER).await?;
+    Ok(CeremonyStart {
+        challenge_id,
+        public_key: CreationOptions {
+            challenge,
+            rp: RelyingPartyEntity { id: rp.id, name: rp.name },
+            user: UserEntity { id: webauthn::encode_b64(user.id.as_bytes()), name: user.email, display_name: user.name },
+            pub_key_cred_params: SUPPORTED_ALGORITHMS.iter().map(|&alg| CredentialParameter { kind: "public-key", alg }).collect(),
+            timeout: CHALLENGE_TTL_SECS as u64 * 1000,
+            exclude_credentials: existing
+                .into_iter()
+                .map(|p| CredentialDescriptor { kind: "public-key", id: p.credential_id })
+                .collect(),
+            authenticator_selection: AuthenticatorSelection {
+                resident_key: "required",
+                require_resident_key: true,
+                user_verification: "preferred",
+            },
+            attestation: "none",
+        },
+    })
+}
+
+pub async fn finish_registration(pool: &DbPool, client: &ClientInfo, user_id: Uuid, payload: FinishRegistration) -> Result<PublicPasskey, AppError> {
+    let event = Event::new(EventKind::PasskeyAdded, client).user(user_id);
+    let result = async {
+        let rp = relying_party(pool).await?;
+        let pending = passkey_repo::take_challenge(pool, payload.challenge_id, REGISTER).await?.ok_or_else(expired)?;
+        if pending.user_id != Some(user_id) {
+            return Err(expired());
+        }
+        let response = &payload.credential.response;
+        let client_data = webauthn::decode_b64("clientDataJSON", &response.client_data_json)?;
+        let attestation = webauthn::decode_b64("attestationObject", &response.attestation_object)?;
+        let registered = webauthn::verify_registration(&rp, &pending.challenge, &client_data, &attestation)?;
+        let credential_id = webauthn::encode_b64(&registered.credential_id);
+        if normalize_credential_id(&payload.credential.id) != credential_id {
+            return Err(AppError::BadRequest("Invalid passkey response: credential id mismatch".into()));
+        }
+        let name = payload.name.as_deref().map(str::trim).filter(|n| !n.is_empty()).unwrap_or("Passkey");
+        let name: String = name.chars().take(MAX_NAME_LENGTH).collect();
+        let new = NewPasskey {
+            user_id,
+            name: &name,
+            credential_id: &credential_id,
+            public_key: &registered.public_key,
+            alg: registered.alg as i32,
+            sign_count: i64::from(registered.sign_count),
+        };
+        passkey_repo::create(pool, &new).await.map_err(|e| match e {
+            AppError::Db(s) if s.contains("unique") => AppError::Conflict("This passkey is already registered".into()),
+            other => other,
+        })
+    }
+    .await;
+    audit_service::outcome(pool, event, result).await
+}
+
+pub async fn list(pool: &DbPool, user_id: Uuid) -> Result<Vec<PublicPasskey>, AppError> {
+    passkey_repo::list_for_user(pool, user_id).await
+}
+
+/// Removes a passkey, refusing to remove the account's last way to sign in.
+pub async fn delete(pool: &DbPool, client: &ClientInfo, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let user = user_repo::get_by_id(pool, user_id).await?;
+    let others = identity_repo::count_for_user(pool, user_id).await? + passkey_repo::count_for_user(pool, user_id).await?;
+    if user.password_hash.is_empty() && others <= 1 {
+        return Err(AppError::BadRequest("Set a password before removing your last sign-in method".into()));
+    }
+    if passkey_repo::delete(pool, id, user_id).await? == 0 {
+        return Err(AppError::NotFound("Passkey not found".into()));
+    }
+    audit_service::success(pool, Event::new(EventKind::PasskeyRemoved, client).user(user_id).detail(id.to_string())).await;
+    Ok(())
+}
+
+/// Options for `navigator.credentials.get()`. No credentials are listed, so the
+/// authenticator offers whichever passkeys it holds for this site.
+pub async fn start_login(pool: &DbPool) -> Result<CeremonyStart<RequestOptions>, AppError> {
+    let rp = relying_party(pool).await?;
+    let (challenge_id, challenge) = issue_challenge(pool, None, LOGIN).await?;
+    Ok(CeremonyStart {
+        challenge_id,
+        public_key: RequestOptions {
+            challenge,
+            rp_id: rp.id,
+            timeout: CHALLENGE_TTL_SECS as u64 * 1000,
+            allow_credentials: Vec::new(),
+            user_verification: "preferred",
+        },
+    })
+}
+
+/// Verifies a sign-in assertion and returns the passkey's owner, and whether the
+/// authenticator verified the user (PIN or biometric) rather than just their presence.
+pub async fn authenticate(pool: &DbPool, payload: &FinishLogin) -> Result<(User, bool), AppError> {
+    let rp = relying_party(pool).await?;
+    let pending = passkey_repo::take_challenge(pool, payload.challenge_id, LOGIN).await?.ok_or_else(expired)?;
+    let passkey = passkey_repo::find_by_credential_id(pool, &normalize_credential_id(&payload.credential.id))
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let response = &payload.credential.response;
+    if let Some(handle) = response.user_handle.as_deref().filter(|h| !h.is_empty()) {
+        if webauthn::decode_b64("userHandle", handle)? != passkey.user_id.as_bytes() {
+            return Err(AppError::Unauthorized);
+        }
+    }
+    let client_data = webauthn::decode_b64("clientDataJSON", &response.client_data_json)?;
+    let authenticator_data = webauthn::decode_b64("authenticatorData", &response.authenticator_data)?;
+    let signature = webauthn::decode_b64("signature", &response.signature)?;
+    let stored = u32::try_from(passkey.sign_count).unwrap_or(u32::MAX);
+    let verified = webauthn::verify_assertion(&rp, &pending.challenge, &passkey.public_key, stored, &client_data, &authenticator_data, &signature)
+        .map_err(|e| {
+            log::warn!("Rejected passkey assertion for passkey {}: {}", passkey.id, e);
+            AppError::Unauthorized
+        })?;
+    // A concurrent sign-in with the same counter value means one of them is a replay
+    if passkey_repo::record_use(pool, passkey.id, passkey.sign_count, i64::from(verified.sign_count)).await? == 0 {
+        return Err(AppError::Unauthorized);
+    }
+    let user = user_repo::get_by_id(pool, passkey.user_id).await?;
+    Ok((user, verified.user_verified))
+}
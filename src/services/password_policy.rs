@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use data_encoding::HEXUPPER;
+use sha1::{Digest, Sha1};
+
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::errors::{AppError, FieldError};
+use crate::repositories::settings_repo;
+
+// Argon2 cost grows with input length; nobody needs more than this
+const MAX_LENGTH: usize = 256;
+// Name/email fragments shorter than this are too common to reject on
+const MIN_PERSONAL_FRAGMENT: usize = 3;
+
+/// Password rules, tunable by admins through `app_settings`.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub min_entropy_bits: f64,
+    pub check_breached: bool,
+}
+
+impl PasswordPolicy {
+    pub async fn load(pool: &DbPool) -> Result<Self, AppError> {
+        Ok(Self {
+            min_length: settings_repo::get_or(pool, "password_min_length", 8usize).await?.clamp(1, MAX_LENGTH),
+            min_entropy_bits: settings_repo::get_or(pool, "password_min_entropy_bits", 35f64).await?.max(0.0),
+            check_breached: settings_repo::get_or(pool, "password_check_breached", true).await?,
+        })
+    }
+}
+
+// Rough guessing-entropy estimate: alphabet size per character, counting neither repeats
+// of the previous character nor steps in a run like "abc" or "123".
+fn estimate_entropy_bits(password: &str) -> f64 {
+    let chars: Vec<char> = password.chars().collect();
+    let mut pool = 0u32;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) { pool += 26; }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) { pool += 26; }
+    if chars.iter().any(|c| c.is_ascii_digit()) { pool += 10; }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') { pool += 33; }
+    if chars.iter().any(|c| !c.is_ascii()) { pool += 100; }
+    if pool == 0 {
+        return 0.0;
+    }
+    let effective = chars
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| {
+            let Some(prev) = i.checked_sub(1).map(|p| chars[p]) else { return true };
+            let step = (**c as i64) - (prev as i64);
+            step.abs() > 1
+        })
+        .count();
+    effective as f64 * f64::from(pool).log2()
+}
+
+fn personal_fragments(email: &str, name: &str) -> Vec<String> {
+    let local = email.split('@').next().unwrap_or_default().to_lowercase();
+    std::iter::once(local)
+        .chain(name.split_whitespace().map(|w| w.to_lowercase()))
+        .filter(|f| f.chars().count() >= MIN_PERSONAL_FRAGMENT)
+        .collect()
+}
+
+/// SHA-1 hashes of breached passwords, read once at startup from a local copy of a
+/// k-anonymity breach corpus: `dir/<PREFIX>` (or `<PREFIX>.txt`) holds "SUFFIX:COUNT" lines
+/// for the hashes starting with the 5-digit prefix. Kept in memory, so meant for a trimmed
+/// corpus such as the most common few million passwords.
+#[derive(Default)]
+pub struct BreachedPasswords {
+    hashes: HashSet<[u8; 20]>,
+}
+
+impl fmt::Debug for BreachedPasswords {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BreachedPasswords({} hashes)", self.hashes.len())
+    }
+}
+
+impl BreachedPasswords {
+    pub fn load(dir: &Path) -> std::io::Result<Self> {
+        let mut hashes = HashSet::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(prefix) = path.file_name().and_then(|n| n.to_str()).map(|n| n.trim_end_matches(".txt")) else { continue };
+            if prefix.len() != 5 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+                continue;
+            }
+            let prefix = prefix.to_ascii_uppercase();
+            for line in std::fs::read_to_string(&path)?.lines() {
+                let (suffix, count) = line.trim().split_once(':').unwrap_or((line.trim(), "1"));
+                if count.trim() == "0" {
+                    continue;
+                }
+                let hex = format!("{}{}", prefix, suffix.to_ascii_uppercase());
+                if let Some(hash) = HEXUPPER.decode(hex.as_bytes()).ok().and_then(|h| <[u8; 20]>::try_from(h).ok()) {
+                    hashes.insert(hash);
+                }
+            }
+        }
+        Ok(Self { hashes })
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        self.hashes.contains(&<[u8; 20]>::from(Sha1::digest(password.as_bytes())))
+    }
+}
+
+/// Checks a new password against the policy, reporting every broken rule under `field`.
+pub async fn validate(pool: &DbPool, cfg: &AppConfig, field: &str, password: &str, email: &str, name: &str) -> Result<(), AppError> {
+    let policy = PasswordPolicy::load(pool).await?;
+    let mut errors = Vec::new();
+    let length = password.chars().count();
+    if length < policy.min_length {
+        errors.push(FieldError::new(field, format!("Must be at least {} characters", policy.min_length)));
+    }
+    if length > MAX_LENGTH {
+        errors.push(FieldError::new(field, format!("Must be at most {} characters", MAX_LENGTH)));
+    }
+    if length > 0 && estimate_entropy_bits(password) < policy.min_entropy_bits {
+        errors.push(FieldError::new(field, "Too easy to guess; use a longer mix of words, numbers and symbols"));
+    }
+    let lowered = password.to_lowercase();
+    if personal_fragments(email, name).iter().any(|f| lowered.contains(f.as_str())) {
+        errors.push(FieldError::new(field, "Must not contain your name or email address"));
+    }
+    if errors.is_empty() && policy.check_breached && cfg.breached_passwords.contains(password) {
+        errors.push(FieldError::new(field, "This password has appeared in a data breach; choose another"));
+    }
+    if errors.is_empty() { Ok(()) } else { Err(AppError::Validation(errors)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus(files: &[(&str, &str)]) -> BreachedPasswords {
+        let dir = std::env::temp_dir().join(format!("breached-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        for (name, contents) in files {
+            std::fs::write(dir.join(name), contents).unwrap();
+        }
+        let loaded = BreachedPasswords::load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn entropy_counts_alphabets_and_skips_repeats_and_runs() {
+        let bits_per_lower = 26f64.log2();
+        assert_eq!(estimate_entropy_bits(""), 0.0);
+        assert!((estimate_entropy_bits("zzzzzzzz") - bits_per_lower).abs() < 1e-9);
+        assert!((estimate_entropy_bits("abcdefgh") - bits_per_lower).abs() < 1e-9);
+        assert!((estimate_entropy_bits("qmwnebrv") - 8.0 * bits_per_lower).abs() < 1e-9);
+        // Lower, upper, digit and symbol: 95 characters
+        assert!((estimate_entropy_bits("Tq7!") - 4.0 * 95f64.log2()).abs() < 1e-9);
+        assert!(estimate_entropy_bits("12345678") < 35.0);
+        assert!(estimate_entropy_bits("correct horse battery staple") > 100.0);
+    }
+
+    #[test]
+    fn breached_passwords_match_by_full_hash() {
+        // SHA-1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+        let corpus = corpus(&[
+            ("5BAA6", "1D2DA4053E34E76F6576ED1DA63134B5E2A:2\n1E4C9B93F3F0682250B6CF8331B7EE68FD8:3861493\n"),
+            ("README.md", "not a range file"),
+        ]);
+        assert_eq!(corpus.len(), 2);
+        assert!(corpus.contains("password"));
+        assert!(!corpus.contains("Password"));
+        assert!(!corpus.contains("a much less common passphrase"));
+    }
+
+    #[test]
+    fn breached_corpus_accepts_txt_names_lowercase_and_padding_rows() {
+        // A zero count marks padding, not a breached password
+        let padding = corpus(&[("5baa6.txt", "1e4c9b93f3f0682250b6cf8331b7ee68fd8:0\n")]);
+        assert!(padding.is_empty());
+        let breached = corpus(&[("5baa6.txt", "1e4c9b93f3f0682250b6cf8331b7ee68fd8:12\n")]);
+        assert!(breached.contains("password"));
+    }
+}
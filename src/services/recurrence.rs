@@ -0,0 +1,192 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+pub const FREQUENCIES: [&str; 4] = ["daily", "weekly", "monthly", "yearly"];
+pub const WEEKDAYS: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            "monthly" => Some(Self::Monthly),
+            "yearly" => Some(Self::Yearly),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_weekday(raw: &str) -> Option<Weekday> {
+    WEEKDAYS.iter().position(|w| *w == raw).and_then(|i| Weekday::try_from(i as u8).ok())
+}
+
+/// When a series repeats: every `every` days, weeks, months or years from `start`. Monthly
+/// and yearly series fall on `start`'s day of the month (the last day in shorter months)
+/// unless `nth_weekday` asks for e.g. the 2nd Tuesday or (with -1) the last Friday.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub frequency: Frequency,
+    pub every: u32,
+    pub start: NaiveDate,
+    pub nth_weekday: Option<(Weekday, i16)>,
+}
+
+fn last_of_month(first: NaiveDate) -> Option<NaiveDate> {
+    first.checked_add_months(Months::new(1))?.pred_opt()
+}
+
+fn nth_weekday_of_month(first: NaiveDate, weekday: Weekday, nth: i16) -> Option<NaiveDate> {
+    if nth < 0 {
+        let last = last_of_month(first)?;
+        let back = (7 + last.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+        return last.checked_sub_days(Days::new(u64::from(back)));
+    }
+    let ahead = (7 + weekday.num_days_from_monday() - first.weekday().num_days_from_monday()) % 7;
+    first.checked_add_days(Days::new(u64::from(ahead) + 7 * (nth as u64 - 1)))
+}
+
+impl Schedule {
+    // Date of the n-th period; `None` past the calendar's range
+    fn period(&self, n: u32) -> Option<NaiveDate> {
+        let steps = n.checked_mul(self.every)?;
+        match self.frequency {
+            Frequency::Daily => self.start.checked_add_days(Days::new(u64::from(steps))),
+            Frequency::Weekly => self.start.checked_add_days(Days::new(7 * u64::from(steps))),
+            Frequency::Monthly | Frequency::Yearly => {
+                let months = if self.frequency == Frequency::Yearly { steps.checked_mul(12)? } else { steps };
+                let first = self.start.with_day(1)?.checked_add_months(Months::new(months))?;
+                match self.nth_weekday {
+                    Some((weekday, nth)) => nth_weekday_of_month(first, weekday, nth),
+                    None => {
+                        let last = last_of_month(first)?;
+                        first.with_day(self.start.day().min(last.day()))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every date of the schedule, in order. Endless; bound it with `take_while` or `take`.
+    pub fn dates(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        // With an nth weekday the first month's date may fall before `start`
+        (0u32..).map_while(|n| self.period(n)).filter(|d| *d >= self.start)
+    }
+}
+
+/// A schedule plus where the series stands: occurrences up to `after` are used up, and the
+/// series stops after `end_date` or once `remaining` more occurrences have come.
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub schedule: Schedule,
+    pub after: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub remaining: Option<u32>,
+}
+
+impl Series {
+    /// The occurrences still to come, skipped ones included.
+    pub fn upcoming(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        let end_date = self.end_date;
+        let after = self.after;
+        self.schedule
+            .dates()
+            .skip_while(move |d| after.is_some_and(|a| *d <= a))
+            .take_while(move |d| end_date.is_none_or(|e| *d <= e))
+            .take(self.remaining.map_or(usize::MAX, |r| r as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn schedule(frequency: Frequency, every: u32, start: NaiveDate) -> Schedule {
+        Schedule { frequency, every, start, nth_weekday: None }
+    }
+
+    fn first(schedule: &Schedule, n: usize) -> Vec<NaiveDate> {
+        schedule.dates().take(n).collect()
+    }
+
+    #[test]
+    fn daily_and_weekly_step_by_every() {
+        assert_eq!(first(&schedule(Frequency::Daily, 3, day(2025, 1, 30)), 3), [day(2025, 1, 30), day(2025, 2, 2), day(2025, 2, 5)]);
+        assert_eq!(first(&schedule(Frequency::Weekly, 2, day(2025, 12, 24)), 3), [day(2025, 12, 24), day(2026, 1, 7), day(2026, 1, 21)]);
+    }
+
+    #[test]
+    fn monthly_on_the_31st_falls_on_the_last_day_of_shorter_months() {
+        let dates = first(&schedule(Frequency::Monthly, 1, day(2025, 1, 31)), 5);
+        assert_eq!(dates, [day(2025, 1, 31), day(2025, 2, 28), day(2025, 3, 31), day(2025, 4, 30), day(2025, 5, 31)]);
+        let leap = first(&schedule(Frequency::Monthly, 1, day(2024, 1, 31)), 3);
+        assert_eq!(leap, [day(2024, 1, 31), day(2024, 2, 29), day(2024, 3, 31)]);
+        let every_other = first(&schedule(Frequency::Monthly, 2, day(2024, 12, 31)), 3);
+        assert_eq!(every_other, [day(2024, 12, 31), day(2025, 2, 28), day(2025, 4, 30)]);
+    }
+
+    #[test]
+    fn yearly_on_february_29th_falls_on_the_28th_outside_leap_years() {
+        let dates = first(&schedule(Frequency::Yearly, 1, day(2024, 2, 29)), 5);
+        assert_eq!(dates, [day(2024, 2, 29), day(2025, 2, 28), day(2026, 2, 28), day(2027, 2, 28), day(2028, 2, 29)]);
+        assert_eq!(first(&schedule(Frequency::Yearly, 4, day(2024, 2, 29)), 2), [day(2024, 2, 29), day(2028, 2, 29)]);
+    }
+
+    #[test]
+    fn nth_weekday_of_the_month() {
+        let second_tuesday = Schedule { nth_weekday: Some((Weekday::Tue, 2)), ..schedule(Frequency::Monthly, 1, day(2025, 1, 1)) };
+        assert_eq!(first(&second_tuesday, 3), [day(2025, 1, 14), day(2025, 2, 11), day(2025, 3, 11)]);
+        let fourth_thursday_of_november =
+            Schedule { nth_weekday: Some((Weekday::Thu, 4)), ..schedule(Frequency::Yearly, 1, day(2025, 11, 1)) };
+        assert_eq!(first(&fourth_thursday_of_november, 3), [day(2025, 11, 27), day(2026, 11, 26), day(2027, 11, 25)]);
+    }
+
+    #[test]
+    fn last_weekday_is_the_fifth_when_the_month_has_one() {
+        let last_friday = Schedule { nth_weekday: Some((Weekday::Fri, -1)), ..schedule(Frequency::Monthly, 1, day(2025, 1, 1)) };
+        // January 2025 has five Fridays, February and March four
+        assert_eq!(first(&last_friday, 3), [day(2025, 1, 31), day(2025, 2, 28), day(2025, 3, 28)]);
+        let last_sunday = Schedule { nth_weekday: Some((Weekday::Sun, -1)), ..schedule(Frequency::Monthly, 1, day(2025, 8, 1)) };
+        assert_eq!(first(&last_sunday, 2), [day(2025, 8, 31), day(2025, 9, 28)]);
+    }
+
+    #[test]
+    fn a_start_off_the_pattern_begins_with_the_next_matching_date() {
+        let from_mid_january = Schedule { nth_weekday: Some((Weekday::Tue, 2)), ..schedule(Frequency::Monthly, 1, day(2025, 1, 20)) };
+        assert_eq!(first(&from_mid_january, 2), [day(2025, 2, 11), day(2025, 3, 11)]);
+        let on_the_day = Schedule { nth_weekday: Some((Weekday::Tue, 2)), ..schedule(Frequency::Monthly, 1, day(2025, 1, 14)) };
+        assert_eq!(first(&on_the_day, 1), [day(2025, 1, 14)]);
+    }
+
+    fn daily_series(end_date: Option<NaiveDate>, remaining: Option<u32>) -> Series {
+        Series { schedule: schedule(Frequency::Daily, 1, day(2025, 1, 1)), after: None, end_date, remaining }
+    }
+
+    #[test]
+    fn the_series_stops_at_end_date_or_max_occurrences_whichever_comes_first() {
+        let by_count: Vec<_> = daily_series(Some(day(2025, 1, 5)), Some(3)).upcoming().collect();
+        assert_eq!(by_count, [day(2025, 1, 1), day(2025, 1, 2), day(2025, 1, 3)]);
+        let by_date: Vec<_> = daily_series(Some(day(2025, 1, 3)), Some(10)).upcoming().collect();
+        assert_eq!(by_date, [day(2025, 1, 1), day(2025, 1, 2), day(2025, 1, 3)]);
+        assert_eq!(daily_series(None, Some(0)).upcoming().next(), None);
+        assert_eq!(daily_series(None, None).upcoming().nth(999), Some(day(2027, 9, 27)));
+    }
+
+    #[test]
+    fn used_up_occurrences_are_not_repeated() {
+        let series = Series { after: Some(day(2025, 1, 2)), ..daily_series(Some(day(2025, 1, 10)), Some(2)) };
+        assert_eq!(series.upcoming().collect::<Vec<_>>(), [day(2025, 1, 3), day(2025, 1, 4)]);
+        let ended = Series { after: Some(day(2025, 1, 10)), ..daily_series(Some(day(2025, 1, 10)), None) };
+        assert_eq!(ended.upcoming().next(), None);
+    }
+}
@@ -0,0 +1,379 @@
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use uuid::Uuid;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::recurring::{DueRecurring, RecurringProgress, RecurringTransaction, RecurringValues};
+use crate::dto::recurring::{CreateRecurring, Occurrence, OccurrenceQuery, UpdateRecurring};
+use crate::repositories::{recurring_repo as repo, category_repo, user_repo};
+use crate::services::recurrence::{self, Frequency, Schedule, Series, FREQUENCIES, WEEKDAYS};
+use crate::services::{currency, money_account_service, transaction_service};
+
+const DEFAULT_PREVIEW: usize = 10;
+const MAX_PREVIEW: usize = 100;
+const MAX_EVERY: i32 = 366;
+// Templates handled per scheduler run, and occurrences booked per template and run; a
+// series started long ago catches up over a few runs.
+const DUE_BATCH: i64 = 500;
+const MAX_BOOKED_PER_RUN: usize = 1000;
+
+struct ScheduleFields<'a> {
+    frequency: &'a str,
+    every: i32,
+    weekday: Option<&'a str>,
+    week_of_month: Option<i16>,
+    start_date: NaiveDate,
+}
+
+fn parse_schedule(fields: &ScheduleFields<'_>) -> Result<Schedule, AppError> {
+    let frequency = Frequency::parse(fields.frequency)
+        .ok_or_else(|| AppError::BadRequest(format!("frequency must be one of: {}", FREQUENCIES.join(", "))))?;
+    if !(1..=MAX_EVERY).contains(&fields.every) {
+        return Err(AppError::BadRequest(format!("every must be between 1 and {}", MAX_EVERY)));
+    }
+    let nth_weekday = match (fields.weekday, fields.week_of_month) {
+        (None, None) => None,
+        (Some(weekday), Some(nth)) => {
+            if !matches!(frequency, Frequency::Monthly | Frequency::Yearly) {
+                return Err(AppError::BadRequest("weekday and week_of_month only apply to monthly and yearly series".into()));
+            }
+            let weekday = recurrence::parse_weekday(weekday)
+                .ok_or_else(|| AppError::BadRequest(format!("weekday must be one of: {}", WEEKDAYS.join(", "))))?;
+            if !matches!(nth, -1 | 1..=4) {
+                return Err(AppError::BadRequest("week_of_month must be 1-4, or -1 for the last".into()));
+            }
+            Some((weekday, nth))
+        }
+        _ => return Err(AppError::BadRequest("weekday and week_of_month go together".into())),
+    };
+    Ok(Schedule { frequency, every: fields.every as u32, start: fields.start_date, nth_weekday })
+}
+
+fn series_of(t: &RecurringTransaction) -> Result<Series, AppError> {
+    let fields = ScheduleFields {
+        frequency: &t.frequency,
+        every: t.every,
+        weekday: t.weekday.as_deref(),
+        week_of_month: t.week_of_month,
+        start_date: t.start_date,
+    };
+    Ok(Series {
+        schedule: parse_schedule(&fields)?,
+        after: t.last_occurrence,
+        end_date: t.end_date,
+        remaining: t.max_occurrences.map(|max| (max - t.occurrences_generated).max(0) as u32),
+    })
+}
+
+fn validate_max_occurrences(max: Option<i32>) -> Result<(), AppError> {
+    if max.is_some_and(|m| m < 1) {
+        return Err(AppError::BadRequest("max_occurrences must be at least 1".into()));
+    }
+    Ok(())
+}
+
+async fn ensure_category(pool: &DbPool, user_id: Uuid, category_id: Uuid) -> Result<(), AppError> {
+    if category_repo::get_by_id_user(pool, category_id, user_id).await?.is_none() {
+        return Err(AppError::BadRequest("Invalid category for user".into()));
+    }
+    Ok(())
+}
+
+pub async fn list(pool: &DbPool, user_id: Uuid) -> Result<Vec<RecurringTransaction>, AppError> {
+    repo::list_by_user(pool, user_id).await
+}
+
+pub async fn get(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<RecurringTransaction, AppError> {
+    repo::get_by_id_user(pool, id, user_id).await?.ok_or_else(|| AppError::NotFound("Recurring transaction not found".into()))
+}
+
+pub async fn create(pool: &DbPool, user_id: Uuid, payload: CreateRecurring) -> Result<RecurringTransaction, AppError> {
+    ensure_category(pool, user_id, payload.category_id).await?;
+    let frequency = payload.frequency.trim().to_lowercase();
+    let weekday = payload.weekday.map(|w| w.trim().to_lowercase());
+    let fields = ScheduleFields {
+        frequency: &frequency,
+        every: payload.every.unwrap_or(1),
+        weekday: weekday.as_deref(),
+        week_of_month: payload.week_of_month,
+        start_date: payload.start_date,
+    };
+    let series = Series { schedule: parse_schedule(&fields)?, after: None, end_date: payload.end_date, remaining: None };
+    validate_max_occurrences(payload.max_occurrences)?;
+    if payload.end_date.is_some_and(|end| end < payload.start_date) {
+        return Err(AppError::BadRequest("end_date must not be before start_date".into()));
+    }
+    let next_occurrence = series.upcoming().next().ok_or_else(|| AppError::BadRequest("The schedule has no occurrence before end_date".into()))?;
+
+    let currency = match payload.account_id {
+        Some(account_id) => {
+            let account_currency = money_account_service::ensure_usable(pool, user_id, account_id).await?;
+            transaction_service::resolve_currency(payload.currency.as_deref(), account_currency)?
+        }
+        None => match payload.currency.as_deref() {
+            Some(c) => currency::parse_code("currency", c)?,
+            None => user_repo::get_by_id(pool, user_id).await?.preferred_currency,
+        },
+    };
+    let values = RecurringValues {
+        category_id: payload.category_id,
+        account_id: payload.account_id,
+        amount: currency::normalize_amount("amount", payload.amount, &currency)?,
+        currency: &currency,
+        description: payload.description.as_deref(),
+        frequency: &frequency,
+        every: fields.every,
+        weekday: fields.weekday,
+        week_of_month: fields.week_of_month,
+        start_date: payload.start_date,
+        end_date: payload.end_date,
+        max_occurrences: payload.max_occurrences,
+        next_occurrence: Some(next_occurrence),
+    };
+    repo::insert(pool, Uuid::new_v4(), user_id, &values).await
+}
+
+/// Changes the series from its next occurrence on; transactions already booked are left as
+/// they are. A new schedule starts at `start_date` (by default the next occurrence), which
+/// must come after the last booked occurrence. The occurrence count carries over.
+pub async fn update(pool: &DbPool, user_id: Uuid, id: Uuid, payload: UpdateRecurring) -> Result<RecurringTransaction, AppError> {
+    let current = get(pool, user_id, id).await?;
+    let category_id = payload.category_id.unwrap_or(current.category_id);
+    if category_id != current.category_id {
+        ensure_category(pool, user_id, category_id).await?;
+    }
+    let account_id = payload.account_id.or(current.account_id);
+    let account_currency = match payload.account_id.filter(|a| Some(*a) != current.account_id) {
+        Some(new_account) => Some(money_account_service::ensure_usable(pool, user_id, new_account).await?),
+        None => account_id.map(|_| current.currency.clone()),
+    };
+    let currency = match account_currency {
+        Some(account_currency) => transaction_service::resolve_currency(payload.currency.as_deref(), account_currency)?,
+        None => match payload.currency.as_deref() {
+            Some(c) => currency::parse_code("currency", c)?,
+            None => current.currency.clone(),
+        },
+    };
+    let amount = currency::normalize_amount("amount", payload.amount.unwrap_or(current.amount), &currency)?;
+
+    // A new frequency starts from a clean slate unless an nth weekday is given with it
+    let frequency = payload.frequency.map(|f| f.trim().to_lowercase());
+    let (weekday, week_of_month) = if frequency.is_some() {
+        (payload.weekday.map(|w| w.trim().to_lowercase()), payload.week_of_month)
+    } else {
+        (payload.weekday.map(|w| w.trim().to_lowercase()).or(current.weekday.clone()), payload.week_of_month.or(current.week_of_month))
+    };
+    let frequency = frequency.unwrap_or(current.frequency.clone());
+    let every = payload.every.unwrap_or(current.every);
+    let schedule_changed = frequency != current.frequency
+        || every != current.every
+        || weekday != current.weekday
+        || week_of_month != current.week_of_month
+        || payload.start_date.is_some_and(|s| s != current.start_date);
+    let start_date = if schedule_changed {
+        let start = payload
+            .start_date
+            .or(current.next_occurrence)
+            .ok_or_else(|| AppError::BadRequest("start_date is required to reschedule a series that has ended".into()))?;
+        if current.last_occurrence.is_some_and(|last| start <= last) {
+            return Err(AppError::BadRequest(format!(
+                "start_date must be after the last booked occurrence ({})",
+                current.last_occurrence.unwrap_or(start)
+            )));
+        }
+        start
+    } else {
+        current.start_date
+    };
+    let fields = ScheduleFields { frequency: &frequency, every, weekday: weekday.as_deref(), week_of_month, start_date };
+    let end_date = payload.end_date.or(current.end_date);
+    let max_occurrences = payload.max_occurrences.or(current.max_occurrences);
+    validate_max_occurrences(max_occurrences)?;
+    let series = Series {
+        schedule: parse_schedule(&fields)?,
+        after: current.last_occurrence,
+        end_date,
+        remaining: max_occurrences.map(|max| (max - current.occurrences_generated).max(0) as u32),
+    };
+    let description = payload.description.or(current.description);
+    let values = RecurringValues {
+        category_id,
+        account_id,
+        amount,
+        currency: &currency,
+        description: description.as_deref(),
+        frequency: &frequency,
+        every,
+        weekday: fields.weekday,
+        week_of_month,
+        start_date,
+        end_date,
+        max_occurrences,
+        next_occurrence: series.upcoming().next(),
+    };
+    repo::update(pool, id, user_id, &values).await
+}
+
+pub async fn delete(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let affected = repo::delete(pool, id, user_id).await?;
+    if affected == 0 { return Err(AppError::NotFound("Recurring transaction not found".into())); }
+    Ok(())
+}
+
+/// The next occurrences of the series, including skipped ones.
+pub async fn preview(pool: &DbPool, user_id: Uuid, id: Uuid, q: OccurrenceQuery) -> Result<Vec<Occurrence>, AppError> {
+    let template = get(pool, user_id, id).await?;
+    let skipped = repo::skipped_dates(pool, id).await?;
+    let limit = q.limit.unwrap_or(DEFAULT_PREVIEW).clamp(1, MAX_PREVIEW);
+    Ok(series_of(&template)?
+        .upcoming()
+        .take(limit)
+        .map(|date| Occurrence { date, skipped: skipped.contains(&date) })
+        .collect())
+}
+
+/// Marks an upcoming occurrence so it is not booked; it still counts towards `max_occurrences`.
+pub async fn skip(pool: &DbPool, user_id: Uuid, id: Uuid, occurrence_date: NaiveDate) -> Result<(), AppError> {
+    let template = get(pool, user_id, id).await?;
+    let series = series_of(&template)?;
+    if !series.upcoming().take_while(|d| *d <= occurrence_date).any(|d| d == occurrence_date) {
+        return Err(AppError::BadRequest(format!("{} is not an upcoming occurrence of this series", occurrence_date)));
+    }
+    repo::add_skip(pool, id, occurrence_date).await
+}
+
+pub async fn unskip(pool: &DbPool, user_id: Uuid, id: Uuid, occurrence_date: NaiveDate) -> Result<(), AppError> {
+    let template = get(pool, user_id, id).await?;
+    if template.last_occurrence.is_some_and(|last| occurrence_date <= last) {
+        return Err(AppError::BadRequest(format!("The occurrence on {} has already passed", occurrence_date)));
+    }
+    if repo::remove_skip(pool, id, occurrence_date).await? == 0 {
+        return Err(AppError::NotFound("Skipped occurrence not found".into()));
+    }
+    Ok(())
+}
+
+// Uses up the occurrences due by `today`: returns the dates to book (skipped ones are used
+// up without a booking) and where the series stands afterwards.
+fn plan_due(series: &Series, skipped: &[NaiveDate], today: NaiveDate, mut progress: RecurringProgress) -> (Vec<NaiveDate>, RecurringProgress) {
+    let mut upcoming = series.upcoming().peekable();
+    let mut dates = Vec::new();
+    let mut handled = 0;
+    while handled < MAX_BOOKED_PER_RUN {
+        let Some(date) = upcoming.next_if(|d| *d <= today) else { break };
+        progress.occurrences_generated += 1;
+        progress.last_occurrence = Some(date);
+        if !skipped.contains(&date) {
+            dates.push(date);
+        }
+        handled += 1;
+    }
+    progress.next_occurrence = upcoming.next();
+    (dates, progress)
+}
+
+// Books the occurrences of one template that are due by `today`.
+async fn book_due(pool: &DbPool, due: &DueRecurring) -> Result<u64, AppError> {
+    let template = &due.template;
+    let skipped = repo::skipped_dates(pool, template.id).await?;
+    let progress = RecurringProgress {
+        occurrences_generated: template.occurrences_generated,
+        last_occurrence: template.last_occurrence,
+        next_occurrence: None,
+    };
+    let (dates, progress) = plan_due(&series_of(template)?, &skipped, due.today, progress);
+    repo::book(pool, template, &dates, &progress).await
+}
+
+/// Books every due occurrence of every series, unless another instance is already doing
+/// so. Returns the number of transactions created.
+pub async fn run_due(pool: &DbPool) -> Result<u64, AppError> {
+    let Some(lock) = repo::try_lock_scheduler(pool).await? else { return Ok(0) };
+    let result = async {
+        let mut booked = 0;
+        for due in repo::due(pool, DUE_BATCH).await? {
+            match book_due(pool, &due).await {
+                Ok(n) => booked += n,
+                // One broken series must not hold up the others
+                Err(e) => log::error!("Booking recurring transaction {} failed: {}", due.template.id, e),
+            }
+        }
+        Ok(booked)
+    }
+    .await;
+    repo::unlock_scheduler(lock).await?;
+    result
+}
+
+/// Runs `run_due` in the background every `every`; a zero interval disables the scheduler.
+pub fn spawn_scheduler(pool: DbPool, every: Duration) {
+    if every.is_zero() {
+        log::info!("Recurring transaction scheduler disabled");
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match run_due(&pool).await {
+                Ok(0) => {}
+                Ok(n) => log::info!("Booked {} recurring transaction(s)", n),
+                Err(e) => log::error!("Recurring transaction run failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn daily(max_occurrences: Option<u32>) -> Series {
+        let schedule = Schedule { frequency: Frequency::Daily, every: 1, start: day(1), nth_weekday: None };
+        Series { schedule, after: None, end_date: None, remaining: max_occurrences }
+    }
+
+    fn fresh() -> RecurringProgress {
+        RecurringProgress { occurrences_generated: 0, last_occurrence: None, next_occurrence: None }
+    }
+
+    #[test]
+    fn books_every_due_occurrence_and_points_at_the_next() {
+        let (dates, progress) = plan_due(&daily(None), &[], day(3), fresh());
+        assert_eq!(dates, [day(1), day(2), day(3)]);
+        assert_eq!(progress.occurrences_generated, 3);
+        assert_eq!(progress.last_occurrence, Some(day(3)));
+        assert_eq!(progress.next_occurrence, Some(day(4)));
+    }
+
+    #[test]
+    fn skipped_occurrences_are_used_up_without_a_booking() {
+        let (dates, progress) = plan_due(&daily(Some(3)), &[day(2)], day(5), fresh());
+        assert_eq!(dates, [day(1), day(3)]);
+        // The skip counts towards max_occurrences, so the series has ended
+        assert_eq!(progress.occurrences_generated, 3);
+        assert_eq!(progress.last_occurrence, Some(day(3)));
+        assert_eq!(progress.next_occurrence, None);
+    }
+
+    #[test]
+    fn a_skipped_next_occurrence_stays_next_until_it_is_due() {
+        let (dates, progress) = plan_due(&daily(None), &[day(2)], day(1), fresh());
+        assert_eq!(dates, [day(1)]);
+        assert_eq!(progress.next_occurrence, Some(day(2)));
+    }
+
+    #[test]
+    fn nothing_is_booked_before_the_first_occurrence() {
+        let (dates, progress) = plan_due(&daily(None), &[], day(1).pred_opt().unwrap(), fresh());
+        assert!(dates.is_empty());
+        assert_eq!(progress.occurrences_generated, 0);
+        assert_eq!(progress.next_occurrence, Some(day(1)));
+    }
+}
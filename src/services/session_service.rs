@@ -0,0 +1,131 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::auth::{create_jwt, generate_token_secret, hash_token, Role};
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::auth::TokenPair;
+use crate::errors::AppError;
+use crate::models::session::PublicSession;
+use crate::repositories::session_repo;
+
+// Refresh tokens are "<session_id>.<secret>"; only the hash of the secret is stored.
+fn parse_refresh_token(token: &str) -> Option<(Uuid, &str)> {
+    let (sid, secret) = token.trim().split_once('.')?;
+    Some((Uuid::parse_str(sid).ok()?, secret))
+}
+
+fn token_pair(cfg: &AppConfig, role: Role, subject_id: Uuid, session_id: Uuid, secret: &str) -> Result<TokenPair, AppError> {
+    Ok(TokenPair {
+        token: create_jwt(subject_id, session_id, role, cfg)?,
+        refresh_token: format!("{}.{}", session_id, secret),
+        expires_in: cfg.jwt_access_exp_minutes * 60,
+        session_id,
+    })
+}
+
+/// Opens a new session for a successful login and issues its first token pair.
+pub async fn start(pool: &DbPool, cfg: &AppConfig, role: Role, subject_id: Uuid, client: &ClientInfo) -> Result<TokenPair, AppError> {
+    let session_id = Uuid::new_v4();
+    let secret = generate_token_secret();
+    let expires_at = Utc::now() + Duration::days(cfg.refresh_token_exp_days as i64);
+    let (user_id, admin_id) = role.owner_ids(subject_id);
+    session_repo::create(pool, session_id, user_id, admin_id, &hash_token(&secret), expires_at, client).await?;
+    token_pair(cfg, role, subject_id, session_id, &secret)
+}
+
+/// Exchanges a refresh token for a new access token, rotating the refresh token.
+pub async fn refresh(pool: &DbPool, cfg: &AppConfig, role: Role, refresh_token: &str) -> Result<TokenPair, AppError> {
+    let (session_id, secret) = parse_refresh_token(refresh_token).ok_or(AppError::Unauthorized)?;
+    let session = session_repo::find_active(pool, session_id).await?.ok_or(AppError::Unauthorized)?;
+    let subject_id = match role {
+        Role::User => session.user_id,
+        Role::Admin => session.admin_id,
+    }
+    .ok_or(AppError::Unauthorized)?;
+
+    let presented = hash_token(secret);
+    if presented != session.refresh_token_hash {
+        // An already rotated token is being replayed: assume it leaked and end the session.
+        log::warn!("Refresh token reuse detected for session {}; revoking", session_id);
+        session_repo::revoke(pool, session_id).await?;
+        return Err(AppError::Unauthorized);
+    }
+
+    let new_secret = generate_token_secret();
+    let expires_at = Utc::now() + Duration::days(cfg.refresh_token_exp_days as i64);
+    let rotated = session_repo::rotate(pool, session_id, &presented, &hash_token(&new_secret), expires_at).await?;
+    if rotated == 0 { return Err(AppError::Unauthorized); }
+    token_pair(cfg, role, subject_id, session_id, &new_secret)
+}
+
+/// Revokes one of the subject's own sessions; its access and refresh tokens stop working.
+pub async fn revoke(pool: &DbPool, role: Role, subject_id: Uuid, session_id: Uuid) -> Result<(), AppError> {
+    let (user_id, admin_id) = role.owner_ids(subject_id);
+    let affected = session_repo::revoke_owned(pool, session_id, user_id, admin_id).await?;
+    if affected == 0 { return Err(AppError::NotFound("Session not found".into())); }
+    Ok(())
+}
+
+/// Active logins of a user, newest activity first, with the caller's own session flagged.
+pub async fn list_for_user(pool: &DbPool, user_id: Uuid, current_session: Uuid) -> Result<Vec<PublicSession>, AppError> {
+    let mut rows = session_repo::list_active_for_user(pool, user_id).await?;
+    for row in rows.iter_mut() {
+        row.current = row.id == current_session;
+    }
+    Ok(rows)
+}
+
+/// Signs the user out everywhere, optionally keeping the session making the request.
+pub async fn revoke_all_for_user(pool: &DbPool, user_id: Uuid, keep: Option<Uuid>) -> Result<u64, AppError> {
+    session_repo::revoke_all_for_user(pool, user_id, keep).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn refresh_tokens_name_their_session() {
+        let sid = Uuid::new_v4();
+        assert_eq!(parse_refresh_token(&format!(" {}.s3cret ", sid)), Some((sid, "s3cret")));
+        assert_eq!(parse_refresh_token("s3cret"), None);
+        assert_eq!(parse_refresh_token("not-a-uuid.s3cret"), None);
+    }
+
+    #[tokio::test]
+    async fn refresh_rotates_and_reuse_ends_the_session() {
+        let Some(pool) = db::test_pool().await else { return };
+        let cfg = AppConfig::for_tests();
+        let user_id = db::test_user(&pool).await;
+
+        let first = start(&pool, &cfg, Role::User, user_id, &ClientInfo::default()).await.unwrap();
+        let (session_id, _) = parse_refresh_token(&first.refresh_token).unwrap();
+        let second = refresh(&pool, &cfg, Role::User, &first.refresh_token).await.unwrap();
+        assert_eq!(parse_refresh_token(&second.refresh_token).unwrap().0, session_id);
+        assert_ne!(second.refresh_token, first.refresh_token);
+        // Only a user session can be refreshed as a user
+        assert!(matches!(refresh(&pool, &cfg, Role::Admin, &second.refresh_token).await, Err(AppError::Unauthorized)));
+
+        // Replaying the rotated token revokes the session, so the current one dies with it
+        assert!(matches!(refresh(&pool, &cfg, Role::User, &first.refresh_token).await, Err(AppError::Unauthorized)));
+        assert!(session_repo::find_active(&pool, session_id).await.unwrap().is_none());
+        assert!(matches!(refresh(&pool, &cfg, Role::User, &second.refresh_token).await, Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn signing_out_everywhere_can_keep_the_current_session() {
+        let Some(pool) = db::test_pool().await else { return };
+        let cfg = AppConfig::for_tests();
+        let user_id = db::test_user(&pool).await;
+        let client = ClientInfo::default();
+
+        let kept = start(&pool, &cfg, Role::User, user_id, &client).await.unwrap();
+        let other = start(&pool, &cfg, Role::User, user_id, &client).await.unwrap();
+        assert_eq!(revoke_all_for_user(&pool, user_id, Some(parse_refresh_token(&kept.refresh_token).unwrap().0)).await.unwrap(), 1);
+        assert!(refresh(&pool, &cfg, Role::User, &kept.refresh_token).await.is_ok());
+        assert!(matches!(refresh(&pool, &cfg, Role::User, &other.refresh_token).await, Err(AppError::Unauthorized)));
+    }
+}
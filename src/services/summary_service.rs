@@ -1,51 +1,133 @@
+use std::collections::HashMap;
+
 use uuid::Uuid;
-use chrono::NaiveDate;
+use chrono::{Duration, NaiveDate};
 use rust_decimal::Decimal;
 use crate::db::DbPool;
 use crate::errors::AppError;
 use crate::routes::summary::MonthlySummary; // reuse struct
 use crate::models::category::CategoryBreakdownItem;
+use crate::repositories::{exchange_rate_repo, settings_repo, user_repo};
+use crate::services::currency;
+use crate::services::exchange_rates::RateTable;
+
+// Category totals per currency and day, converted afterwards at that day's rate
+#[derive(sqlx::FromRow)]
+struct DayTotal {
+    category_id: Uuid,
+    name: String,
+    kind: String,
+    currency: String,
+    occurred_on: NaiveDate,
+    total: Decimal,
+}
+
+// Adds a day's total, converted at `rate`, to its category. Amounts and rates come from user
+// data, so a product beyond what Decimal holds is refused instead of panicking.
+fn add_converted(item: &mut CategoryBreakdownItem, total: Decimal, rate: Decimal, currency: &str) -> Result<(), AppError> {
+    item.total = total
+        .checked_mul(rate)
+        .and_then(|converted| item.total.checked_add(converted))
+        .ok_or_else(|| AppError::BadRequest(format!("The {} total in {} is too large to convert", item.name, currency)))?;
+    Ok(())
+}
 
-pub async fn monthly_summary(pool: &DbPool, user_id: Uuid, year: i32, month: u32) -> Result<MonthlySummary, AppError> {
+/// Income and expenses of one month in the user's preferred currency, optionally limited to
+/// one account. Transfer legs have no category, so the category join leaves them out;
+/// transfer fees count as expenses, and each line of a split transaction counts in its own
+/// category. Amounts in other currencies are converted at the rate
+/// of their day (see `RateTable::rate`), and the summary fails rather than guess when no
+/// recent rate is stored.
+pub async fn monthly_summary(pool: &DbPool, user_id: Uuid, year: i32, month: u32, account_id: Option<Uuid>) -> Result<MonthlySummary, AppError> {
     let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(|| AppError::BadRequest("Invalid year/month".into()))?;
     let end = if month == 12 { NaiveDate::from_ymd_opt(year + 1, 1, 1).unwrap() } else { NaiveDate::from_ymd_opt(year, month + 1, 1).unwrap() };
+    let base = user_repo::get_by_id(pool, user_id).await?.preferred_currency;
+    let max_age_days = settings_repo::get_or(pool, "exchange_rate_max_age_days", 7i64).await?.max(0);
 
-    let (total_income, total_expense): (Option<Decimal>, Option<Decimal>) = sqlx::query_as(
+    let rows = sqlx::query_as::<_, DayTotal>(
         r#"
-        SELECT 
-            SUM(CASE WHEN c.kind='income' THEN t.amount ELSE 0 END) AS income,
-            SUM(CASE WHEN c.kind='expense' THEN t.amount ELSE 0 END) AS expense
+        SELECT c.id AS category_id, c.name AS name, c.kind AS kind, t.currency AS currency,
+               t.occurred_on AS occurred_on, SUM(COALESCE(s.amount, t.amount)) AS total
         FROM transactions t
-        JOIN categories c ON c.id = t.category_id
+        LEFT JOIN transaction_splits s ON s.transaction_id = t.id
+        JOIN categories c ON c.id = COALESCE(s.category_id, t.category_id)
         WHERE t.user_id=$1 AND t.occurred_on >= $2 AND t.occurred_on < $3
+          AND ($4::uuid IS NULL OR t.account_id = $4)
+        GROUP BY c.id, c.name, c.kind, t.currency, t.occurred_on
         "#,
     )
     .bind(user_id)
     .bind(start)
     .bind(end)
-    .fetch_one(pool)
+    .bind(account_id)
+    .fetch_all(pool)
     .await?;
 
-    let total_income = total_income.unwrap_or(Decimal::ZERO);
-    let total_expense = total_expense.unwrap_or(Decimal::ZERO);
-    let balance = total_income - total_expense;
+    let rates = if rows.iter().any(|r| r.currency != base) {
+        RateTable::new(exchange_rate_repo::between(pool, start - Duration::days(max_age_days), end).await?)
+    } else {
+        RateTable::new(Vec::new())
+    };
+    let mut by_category: HashMap<Uuid, CategoryBreakdownItem> = HashMap::new();
+    for row in rows {
+        let rate = rates.rate(&row.currency, &base, row.occurred_on, max_age_days).ok_or_else(|| {
+            AppError::BadRequest(format!("No {}/{} exchange rate available for {}", row.currency, base, row.occurred_on))
+        })?;
+        let item = by_category
+            .entry(row.category_id)
+            .or_insert_with(|| CategoryBreakdownItem { category_id: row.category_id, name: row.name, kind: row.kind, total: Decimal::ZERO });
+        add_converted(item, row.total, rate, &row.currency)?;
+    }
+    // Each category is rounded once, and the totals add up the rounded figures
+    let mut breakdown: Vec<CategoryBreakdownItem> = by_category
+        .into_values()
+        .map(|mut item| {
+            item.total = currency::round(item.total, &base);
+            item
+        })
+        .collect();
+    breakdown.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
 
-    let breakdown = sqlx::query_as::<_, CategoryBreakdownItem>(
-        r#"
-        SELECT t.category_id as category_id, c.name as name, c.kind as kind, COALESCE(SUM(t.amount),0) as total
-        FROM transactions t
-        JOIN categories c ON c.id = t.category_id
-        WHERE t.user_id=$1 AND t.occurred_on >= $2 AND t.occurred_on < $3
-        GROUP BY t.category_id, c.name, c.kind
-        ORDER BY total DESC
-        "#,
-    )
-    .bind(user_id)
-    .bind(start)
-    .bind(end)
-    .fetch_all(pool)
-    .await?;
+    let too_large = || AppError::BadRequest(format!("The totals in {} are too large to add up", base));
+    let sum_kind = |kind: &str| {
+        breakdown
+            .iter()
+            .filter(|i| i.kind == kind)
+            .try_fold(Decimal::ZERO, |sum, i| sum.checked_add(i.total))
+            .ok_or_else(too_large)
+    };
+    let total_income = currency::round(sum_kind("income")?, &base);
+    let total_expense = currency::round(sum_kind("expense")?, &base);
+    let balance = total_income.checked_sub(total_expense).ok_or_else(too_large)?;
 
-    Ok(MonthlySummary { year, month, total_income, total_expense, balance, category_breakdown: breakdown })
+    Ok(MonthlySummary { year, month, currency: base, total_income, total_expense, balance, category_breakdown: breakdown })
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn item() -> CategoryBreakdownItem {
+        CategoryBreakdownItem { category_id: Uuid::nil(), name: "Groceries".into(), kind: "expense".into(), total: Decimal::ZERO }
+    }
+
+    #[test]
+    fn converted_totals_add_up_per_category() {
+        let mut groceries = item();
+        add_converted(&mut groceries, Decimal::from(10), Decimal::from_str("16350.5").unwrap(), "USD").unwrap();
+        add_converted(&mut groceries, Decimal::from(25000), Decimal::ONE, "IDR").unwrap();
+        assert_eq!(groceries.total, Decimal::from(188505));
+    }
+
+    #[test]
+    fn overflowing_conversions_are_refused_not_panicking() {
+        let mut groceries = item();
+        let huge = Decimal::from_str("99999999999999999").unwrap();
+        let err = add_converted(&mut groceries, huge, Decimal::from_str("99999999999999").unwrap(), "KWD").unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(ref m) if m.contains("Groceries") && m.contains("KWD")), "{:?}", err);
+        groceries.total = Decimal::MAX;
+        assert!(add_converted(&mut groceries, Decimal::ONE, Decimal::ONE, "IDR").is_err());
+        assert_eq!(groceries.total, Decimal::MAX);
+    }
+}
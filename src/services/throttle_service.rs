@@ -0,0 +1,122 @@
+use chrono::{Duration, Utc};
+
+use crate::client::ClientInfo;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::repositories::throttle_repo;
+
+/// Guessable operations protected against brute force. Each has its own counters.
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+    Login,
+    AdminLogin,
+    VerifyOtp,
+    ResetPassword,
+    Mfa,
+    PasswordlessLogin,
+    EmailChange,
+    PasswordChange,
+    PasskeyLogin,
+}
+
+impl Action {
+    fn as_str(self) -> &'static str {
+        match self {
+            Action::Login => "login",
+            Action::AdminLogin => "admin_login",
+            Action::VerifyOtp => "verify_otp",
+            Action::ResetPassword => "reset_password",
+            Action::Mfa => "mfa",
+            Action::PasswordlessLogin => "passwordless_login",
+            Action::EmailChange => "email_change",
+            Action::PasswordChange => "password_change",
+            Action::PasskeyLogin => "passkey_login",
+        }
+    }
+}
+
+// Failures allowed before the first lockout. An IP is shared by many people behind NAT,
+// so it gets more slack than a single account.
+const ACCOUNT_FREE_ATTEMPTS: i32 = 5;
+const IP_FREE_ATTEMPTS: i32 = 20;
+// Lockout doubles with every further failure, starting here, up to the cap.
+const BASE_LOCKOUT_SECONDS: i64 = 30;
+const MAX_LOCKOUT_SECONDS: i64 = 60 * 60;
+// Counters reset when the last failure is older than this.
+const WINDOW_HOURS: i32 = 24;
+
+fn account_key(account: &str) -> String {
+    format!("account:{}", account)
+}
+
+fn keys(account: &str, client: &ClientInfo) -> Vec<(String, i32)> {
+    let mut keys = vec![(account_key(account), ACCOUNT_FREE_ATTEMPTS)];
+    if let Some(ip) = &client.ip {
+        keys.push((format!("ip:{}", ip), IP_FREE_ATTEMPTS));
+    }
+    keys
+}
+
+fn lockout_seconds(failures: i32, free_attempts: i32) -> Option<i64> {
+    if failures < free_attempts {
+        return None;
+    }
+    let doublings = (failures - free_attempts).min(16) as u32;
+    Some((BASE_LOCKOUT_SECONDS << doublings).min(MAX_LOCKOUT_SECONDS))
+}
+
+/// Rejects the attempt with `TooManyRequests` while the account or client IP is locked out.
+pub async fn check(pool: &DbPool, action: Action, account: &str, client: &ClientInfo) -> Result<(), AppError> {
+    let keys: Vec<String> = keys(account, client).into_iter().map(|(k, _)| k).collect();
+    if let Some(until) = throttle_repo::locked_until(pool, action.as_str(), &keys).await? {
+        let wait = (until - Utc::now()).num_seconds().max(1) as u64;
+        return Err(AppError::TooManyRequests(format!("Too many failed attempts. Try again in {}s", wait), wait));
+    }
+    Ok(())
+}
+
+pub async fn record_failure(pool: &DbPool, action: Action, account: &str, client: &ClientInfo) -> Result<(), AppError> {
+    for (key, free_attempts) in keys(account, client) {
+        let failures = throttle_repo::record_failure(pool, action.as_str(), &key, WINDOW_HOURS).await?;
+        if let Some(secs) = lockout_seconds(failures, free_attempts) {
+            log::warn!("{} locked out for {}s after {} failed {} attempts", key, secs, failures, action.as_str());
+            throttle_repo::lock(pool, action.as_str(), &key, Utc::now() + Duration::seconds(secs)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Clears the account's counter. The IP counter is left alone so one valid login cannot
+/// reset a spraying attack across many accounts.
+pub async fn record_success(pool: &DbPool, action: Action, account: &str) -> Result<(), AppError> {
+    throttle_repo::clear(pool, action.as_str(), &account_key(account)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_attempts_are_not_locked() {
+        for failures in 0..ACCOUNT_FREE_ATTEMPTS {
+            assert_eq!(lockout_seconds(failures, ACCOUNT_FREE_ATTEMPTS), None);
+        }
+        assert_eq!(lockout_seconds(IP_FREE_ATTEMPTS - 1, IP_FREE_ATTEMPTS), None);
+    }
+
+    #[test]
+    fn lockout_doubles_from_the_base() {
+        let curve: Vec<Option<i64>> = (0..5).map(|n| lockout_seconds(ACCOUNT_FREE_ATTEMPTS + n, ACCOUNT_FREE_ATTEMPTS)).collect();
+        assert_eq!(curve, vec![Some(30), Some(60), Some(120), Some(240), Some(480)]);
+        assert_eq!(lockout_seconds(IP_FREE_ATTEMPTS, IP_FREE_ATTEMPTS), Some(BASE_LOCKOUT_SECONDS));
+    }
+
+    #[test]
+    fn lockout_is_capped() {
+        // 30s doubled 7 times passes an hour
+        assert_eq!(lockout_seconds(ACCOUNT_FREE_ATTEMPTS + 6, ACCOUNT_FREE_ATTEMPTS), Some(1920));
+        assert_eq!(lockout_seconds(ACCOUNT_FREE_ATTEMPTS + 7, ACCOUNT_FREE_ATTEMPTS), Some(MAX_LOCKOUT_SECONDS));
+        assert_eq!(lockout_seconds(ACCOUNT_FREE_ATTEMPTS + 40, ACCOUNT_FREE_ATTEMPTS), Some(MAX_LOCKOUT_SECONDS));
+        assert_eq!(lockout_seconds(i32::MAX, ACCOUNT_FREE_ATTEMPTS), Some(MAX_LOCKOUT_SECONDS));
+    }
+}
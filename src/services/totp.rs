@@ -0,0 +1,159 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+// RFC 6238 defaults understood by every authenticator app
+const STEP_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+// Accept the previous and next step to tolerate clock drift
+const SKEW_STEPS: i64 = 1;
+// Marks a secret sealed with AES-256-GCM; older rows hold the base32 secret itself
+const SEALED_PREFIX: &str = "v1:";
+
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let issuer_enc = utf8_percent_encode(issuer, NON_ALPHANUMERIC).to_string();
+    let account_enc = utf8_percent_encode(account, NON_ALPHANUMERIC).to_string();
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer_enc, account_enc, secret, issuer_enc, DIGITS, STEP_SECONDS
+    )
+}
+
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]) & 0x7fff_ffff;
+    binary % 10u32.pow(DIGITS)
+}
+
+/// Checks `code` against the secret at `unix_time`. Returns the matched time step, which
+/// callers persist so the same code cannot be replayed; steps at or before
+/// `last_used_step` are never accepted.
+pub fn verify(secret: &str, code: &str, unix_time: i64, last_used_step: Option<i64>) -> Option<i64> {
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let expected: u32 = code.parse().ok()?;
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let current = unix_time / STEP_SECONDS;
+    (current - SKEW_STEPS..=current + SKEW_STEPS)
+        .filter(|step| *step >= 0 && last_used_step.is_none_or(|last| *step > last))
+        .find(|step| hotp(&key, *step as u64) == expected)
+}
+
+fn sealing_key(key: &str) -> LessSafeKey {
+    let digest = Sha256::digest(key.as_bytes());
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &digest).expect("SHA-256 output is a valid AES-256 key"))
+}
+
+/// Encrypts a secret for storage: `v1:` followed by base64 of nonce and ciphertext.
+pub fn seal_secret(key: &str, secret: &str) -> String {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let mut data = secret.as_bytes().to_vec();
+    sealing_key(key)
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .expect("AES-GCM sealing does not fail for short inputs");
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&data);
+    format!("{}{}", SEALED_PREFIX, STANDARD.encode(out))
+}
+
+/// Recovers a secret stored by `seal_secret`. `None` means the value was sealed with a
+/// different key, altered, or never sealed at all.
+pub fn open_secret(key: &str, stored: &str) -> Option<String> {
+    let raw = STANDARD.decode(stored.strip_prefix(SEALED_PREFIX)?).ok()?;
+    if raw.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, data) = raw.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let mut data = data.to_vec();
+    let plain = sealing_key(key).open_in_place(nonce, Aad::empty(), &mut data).ok()?;
+    String::from_utf8(plain.to_vec()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B, SHA-1 seed "12345678901234567890"; we use the last 6 of the 8 digits
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+    const RFC_VECTORS: [(i64, &str); 6] = [
+        (59, "287082"),
+        (1_111_111_109, "081804"),
+        (1_111_111_111, "050471"),
+        (1_234_567_890, "005924"),
+        (2_000_000_000, "279037"),
+        (20_000_000_000, "353130"),
+    ];
+
+    #[test]
+    fn matches_rfc_6238_vectors() {
+        for (time, code) in RFC_VECTORS {
+            assert_eq!(verify(RFC_SECRET, code, time, None), Some(time / STEP_SECONDS), "T={}", time);
+        }
+    }
+
+    #[test]
+    fn accepts_one_step_of_drift_either_way() {
+        let (time, code) = RFC_VECTORS[3];
+        assert!(verify(RFC_SECRET, code, time - STEP_SECONDS, None).is_some());
+        assert!(verify(RFC_SECRET, code, time + STEP_SECONDS, None).is_some());
+        assert_eq!(verify(RFC_SECRET, code, time - 2 * STEP_SECONDS, None), None);
+        assert_eq!(verify(RFC_SECRET, code, time + 2 * STEP_SECONDS, None), None);
+    }
+
+    #[test]
+    fn rejects_replayed_and_older_steps() {
+        let (time, code) = RFC_VECTORS[3];
+        let step = verify(RFC_SECRET, code, time, None).unwrap();
+        assert_eq!(verify(RFC_SECRET, code, time, Some(step)), None);
+        assert_eq!(verify(RFC_SECRET, code, time, Some(step + 1)), None);
+        assert_eq!(verify(RFC_SECRET, code, time, Some(step - 1)), Some(step));
+    }
+
+    #[test]
+    fn rejects_malformed_codes() {
+        let (time, code) = RFC_VECTORS[3];
+        assert_eq!(verify(RFC_SECRET, &code[1..], time, None), None);
+        assert_eq!(verify(RFC_SECRET, &format!("{}0", code), time, None), None);
+        assert_eq!(verify(RFC_SECRET, "00592a", time, None), None);
+        assert_eq!(verify("not base32!", code, time, None), None);
+    }
+
+    #[test]
+    fn sealed_secret_round_trips_only_with_its_key() {
+        let secret = generate_secret();
+        let sealed = seal_secret("key-a", &secret);
+        assert!(sealed.starts_with(SEALED_PREFIX));
+        assert!(!sealed.contains(&secret));
+        assert_eq!(open_secret("key-a", &sealed).as_deref(), Some(secret.as_str()));
+        assert_eq!(open_secret("key-b", &sealed), None);
+        assert_ne!(seal_secret("key-a", &secret), sealed, "nonces must differ");
+    }
+
+    #[test]
+    fn unsealed_or_damaged_values_are_rejected() {
+        assert_eq!(open_secret("key-a", RFC_SECRET), None);
+        assert_eq!(open_secret("key-a", "v1:not base64!"), None);
+        assert_eq!(open_secret("key-a", "v1:AAAA"), None);
+        let mut sealed = seal_secret("key-a", RFC_SECRET);
+        sealed.push('A');
+        assert_eq!(open_secret("key-a", &sealed), None);
+    }
+}
@@ -1,38 +1,526 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::db::DbPool;
-use crate::errors::AppError;
-use crate::models::transaction::Transaction;
-use crate::dto::transaction::{CreateTransaction, UpdateTransaction, TxnQuery};
-use crate::repositories::{transaction_repo as repo, category_repo};
+use crate::errors::{AppError, FieldError};
+use crate::models::transaction::{SplitValues, Transaction, TransactionSplit, TransactionValues};
+use crate::dto::page::Page;
+use crate::dto::transaction::{CreateTransaction, SortOrder, SplitLine, TxnQuery, TxnSort, UpdateTransaction};
+use crate::repositories::transaction_repo::{Keyset, SortColumn, SortValue, TxnFilter};
+use crate::repositories::{transaction_repo as repo, category_repo, user_repo};
+use crate::services::{currency, money_account_service};
+
+const DEFAULT_PAGE: i64 = 50;
+const MAX_PAGE: i64 = 200;
+const MAX_SPLITS: usize = 50;
+
+// Cursors are base64url JSON, opaque to clients. They carry the sort they were issued for,
+// so a cursor cannot be replayed against a different ordering.
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    sort: TxnSort,
+    order: SortOrder,
+    key: String,
+    id: Uuid,
+}
+
+fn sort_key(sort: TxnSort, txn: &Transaction) -> String {
+    match sort {
+        TxnSort::Date => txn.occurred_on.to_string(),
+        TxnSort::Amount => txn.amount.to_string(),
+        TxnSort::CreatedAt => txn.created_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+    }
+}
+
+fn encode_cursor(sort: TxnSort, order: SortOrder, last: &Transaction) -> String {
+    let cursor = Cursor { sort, order, key: sort_key(sort, last), id: last.id };
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&cursor).unwrap_or_default())
+}
 
-pub async fn list(pool: &DbPool, user_id: Uuid, q: TxnQuery) -> Result<Vec<Transaction>, AppError> {
-    repo::list(pool, user_id, q.category_id, q.start_date, q.end_date).await
+fn decode_cursor(raw: &str, sort: TxnSort, order: SortOrder) -> Result<(SortValue, Uuid), AppError> {
+    let invalid = || AppError::BadRequest("Invalid cursor".into());
+    let bytes = URL_SAFE_NO_PAD.decode(raw.trim()).map_err(|_| invalid())?;
+    let cursor: Cursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    if cursor.sort != sort || cursor.order != order {
+        return Err(AppError::BadRequest("Cursor belongs to a different sort order".into()));
+    }
+    let value = match sort {
+        TxnSort::Date => SortValue::Date(NaiveDate::from_str(&cursor.key).map_err(|_| invalid())?),
+        TxnSort::Amount => SortValue::Amount(Decimal::from_str(&cursor.key).map_err(|_| invalid())?),
+        TxnSort::CreatedAt => SortValue::Time(DateTime::parse_from_rfc3339(&cursor.key).map_err(|_| invalid())?.with_timezone(&Utc)),
+    };
+    Ok((value, cursor.id))
 }
 
+// Longer search terms only slow the query down
+const MAX_SEARCH_LENGTH: usize = 100;
+
+// Makes user input match literally inside an ILIKE pattern
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn build_filter(q: &TxnQuery) -> Result<TxnFilter, AppError> {
+    let mut errors = Vec::new();
+    let mut category_ids: Vec<Uuid> = q.category_id.into_iter().collect();
+    for raw in q.category_ids.as_deref().unwrap_or_default().split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match Uuid::parse_str(raw) {
+            Ok(id) if !category_ids.contains(&id) => category_ids.push(id),
+            Ok(_) => {}
+            Err(_) => errors.push(FieldError::new("category_ids", format!("'{}' is not a valid id", raw))),
+        }
+    }
+    if let (Some(min), Some(max)) = (q.min_amount, q.max_amount) {
+        if min > max {
+            errors.push(FieldError::new("min_amount", "Must not be greater than max_amount"));
+        }
+    }
+    if let Some(kind) = q.kind.as_deref() {
+        if !matches!(kind, "income" | "expense") {
+            errors.push(FieldError::new("kind", "Must be 'income' or 'expense'"));
+        }
+    }
+    let search = q.q.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    if search.is_some_and(|s| s.chars().count() > MAX_SEARCH_LENGTH) {
+        errors.push(FieldError::new("q", format!("Must be at most {} characters", MAX_SEARCH_LENGTH)));
+    }
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+    Ok(TxnFilter {
+        category_ids: (!category_ids.is_empty()).then_some(category_ids),
+        account_id: q.account_id,
+        start_date: q.start_date,
+        end_date: q.end_date,
+        min_amount: q.min_amount,
+        max_amount: q.max_amount,
+        kind: q.kind.clone(),
+        description_pattern: search.map(|s| format!("%{}%", escape_like(s))),
+        has_description: q.has_description,
+        has_attachment: q.has_attachment,
+        // Transfer legs are signed by direction, so they would land among income/expense amounts
+        exclude_transfer_legs: q.min_amount.is_some() || q.max_amount.is_some() || q.sort == TxnSort::Amount,
+    })
+}
+
+pub async fn list(pool: &DbPool, user_id: Uuid, q: TxnQuery) -> Result<Page<Transaction>, AppError> {
+    let filter = build_filter(&q)?;
+    let limit = q.limit.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE);
+    let after = match q.cursor.as_deref().filter(|c| !c.trim().is_empty()) {
+        Some(raw) => Some(decode_cursor(raw, q.sort, q.order)?),
+        None => None,
+    };
+    let sort = match q.sort {
+        TxnSort::Date => SortColumn::OccurredOn,
+        TxnSort::Amount => SortColumn::Amount,
+        TxnSort::CreatedAt => SortColumn::CreatedAt,
+    };
+    // One extra row tells whether another page follows
+    let keyset = Keyset { sort, descending: q.order == SortOrder::Desc, after, limit: limit + 1 };
+    let mut items = repo::list(pool, user_id, &filter, &keyset).await?;
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(|last| encode_cursor(q.sort, q.order, last))
+    } else {
+        None
+    };
+    let total = repo::count(pool, user_id, &filter).await?;
+    attach_splits(pool, &mut items).await?;
+    Ok(Page { items, next_cursor, total })
+}
+
+async fn attach_splits(pool: &DbPool, txns: &mut [Transaction]) -> Result<(), AppError> {
+    let ids: Vec<Uuid> = txns.iter().map(|t| t.id).collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+    for line in repo::splits_for(pool, &ids).await? {
+        if let Some(txn) = txns.iter_mut().find(|t| t.id == line.transaction_id) {
+            txn.splits.push(line);
+        }
+    }
+    Ok(())
+}
+
+// Checks split lines against their transaction: at least two, each in one of the user's
+// categories (`kinds` maps them to their kind), all of one kind, non-zero with the sign of
+// `amount`, in the currency's decimals and adding up to `amount`. Every problem is reported
+// under its line.
+fn check_splits<'a>(
+    lines: &'a [SplitLine],
+    kinds: &HashMap<Uuid, String>,
+    amount: Decimal,
+    currency: &str,
+) -> Result<Vec<SplitValues<'a>>, AppError> {
+    let mut errors = Vec::new();
+    if lines.len() == 1 || lines.len() > MAX_SPLITS {
+        errors.push(FieldError::new("splits", format!("Must have between 2 and {} lines", MAX_SPLITS)));
+    }
+    let mut line_kinds = HashSet::new();
+    let mut values = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        match kinds.get(&line.category_id) {
+            Some(kind) => {
+                line_kinds.insert(kind.as_str());
+            }
+            None => errors.push(FieldError::new(&format!("splits[{}].category_id", i), "Not one of your categories")),
+        }
+        let field = format!("splits[{}].amount", i);
+        if line.amount.is_zero() || line.amount.is_sign_negative() != amount.is_sign_negative() {
+            errors.push(FieldError::new(&field, "Must be non-zero and have the same sign as amount"));
+        }
+        match currency::normalize_amount(&field, line.amount, currency) {
+            Ok(normalized) => values.push(SplitValues { category_id: line.category_id, amount: normalized, description: line.description.as_deref() }),
+            Err(_) => errors.push(FieldError::new(&field, format!("Can have at most {} decimal places in {}", currency::minor_units(currency), currency))),
+        }
+    }
+    if line_kinds.len() > 1 {
+        errors.push(FieldError::new("splits", "All lines must be income categories or all expense categories"));
+    }
+    if errors.is_empty() && values.iter().map(|v| v.amount).sum::<Decimal>() != amount {
+        errors.push(FieldError::new("splits", format!("Lines must add up to the amount, {}", amount)));
+    }
+    if errors.is_empty() { Ok(values) } else { Err(AppError::Validation(errors)) }
+}
+
+async fn validate_splits<'a>(
+    pool: &DbPool,
+    user_id: Uuid,
+    lines: &'a [SplitLine],
+    amount: Decimal,
+    currency: &str,
+) -> Result<Vec<SplitValues<'a>>, AppError> {
+    let ids: Vec<Uuid> = lines.iter().map(|l| l.category_id).collect();
+    let kinds = category_repo::list_by_ids_user(pool, &ids, user_id)
+        .await?
+        .into_iter()
+        .map(|c| (c.id, c.kind))
+        .collect();
+    check_splits(lines, &kinds, amount, currency)
+}
+
+// The lines to validate and store on update: the new ones, or the current ones again when
+// the amount, currency or account changes under them.
+fn lines_for_update(new: Option<Vec<SplitLine>>, current: &[TransactionSplit], money_changes: bool) -> Option<Vec<SplitLine>> {
+    match new {
+        Some(lines) => Some(lines),
+        None if !current.is_empty() && money_changes => Some(current_split_lines(current)),
+        None => None,
+    }
+}
+
+fn current_split_lines(splits: &[TransactionSplit]) -> Vec<SplitLine> {
+    splits
+        .iter()
+        .map(|s| SplitLine { category_id: s.category_id, amount: s.amount, description: s.description.clone() })
+        .collect()
+}
+
+// Transfer legs only change together with their transfer
+fn reject_transfer_leg(txn: &Transaction) -> Result<(), AppError> {
+    match txn.transfer_id {
+        Some(transfer_id) => Err(AppError::BadRequest(format!(
+            "This transaction belongs to a transfer; change it through /api/transfers/{}",
+            transfer_id
+        ))),
+        None => Ok(()),
+    }
+}
+
+// A transaction on an account is always in the account's currency.
+pub(crate) fn resolve_currency(requested: Option<&str>, account_currency: String) -> Result<String, AppError> {
+    match requested.map(|c| currency::parse_code("currency", c)).transpose()? {
+        Some(c) if c != account_currency => Err(AppError::BadRequest(format!("Account holds {}, not {}", account_currency, c))),
+        _ => Ok(account_currency),
+    }
+}
+
+/// Books a transaction. With `splits` its amount is spread over several categories and
+/// `category_id`, if given, must be the first line's.
 pub async fn create(pool: &DbPool, user_id: Uuid, payload: CreateTransaction) -> Result<Transaction, AppError> {
-    // Ensure category belongs to user
-    let owner = category_repo::get_by_id_user(pool, payload.category_id, user_id).await?;
-    if owner.is_none() { return Err(AppError::BadRequest("Invalid category for user".into())); }
+    let lines = payload.splits.unwrap_or_default();
+    let category_id = match (payload.category_id, lines.first()) {
+        (Some(id), Some(first)) if id != first.category_id => {
+            return Err(AppError::Validation(vec![FieldError::new("category_id", "Must match the first split line, or be left out")]));
+        }
+        (_, Some(first)) => first.category_id,
+        (Some(id), None) => {
+            // Ensure category belongs to user
+            let owner = category_repo::get_by_id_user(pool, id, user_id).await?;
+            if owner.is_none() { return Err(AppError::BadRequest("Invalid category for user".into())); }
+            id
+        }
+        (None, None) => return Err(AppError::Validation(vec![FieldError::new("category_id", "Required unless splits are given")])),
+    };
+    let account_currency = match payload.account_id {
+        Some(account_id) => Some(money_account_service::ensure_usable(pool, user_id, account_id).await?),
+        None => None,
+    };
+    let currency = match account_currency {
+        Some(account_currency) => resolve_currency(payload.currency.as_deref(), account_currency)?,
+        None => match payload.currency.as_deref() {
+            Some(c) => currency::parse_code("currency", c)?,
+            None => user_repo::get_by_id(pool, user_id).await?.preferred_currency,
+        },
+    };
+    let amount = currency::normalize_amount("amount", payload.amount, &currency)?;
+    let splits = if lines.is_empty() { Vec::new() } else { validate_splits(pool, user_id, &lines, amount, &currency).await? };
     let id = Uuid::new_v4();
-    repo::insert(pool, id, user_id, payload.category_id, &payload.amount, payload.occurred_on, &payload.description).await
+    let values = TransactionValues {
+        category_id,
+        account_id: payload.account_id,
+        amount,
+        currency: &currency,
+        occurred_on: payload.occurred_on,
+        description: payload.description.as_deref(),
+    };
+    let mut txn = repo::insert(pool, id, user_id, &values, &splits).await?;
+    attach_splits(pool, std::slice::from_mut(&mut txn)).await?;
+    Ok(txn)
 }
 
 pub async fn update(pool: &DbPool, user_id: Uuid, id: Uuid, payload: UpdateTransaction) -> Result<Transaction, AppError> {
     let current = repo::get_by_id_user(pool, id, user_id).await?.ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
-    let category_id = payload.category_id.unwrap_or(current.category_id);
-    if category_id != current.category_id {
-        let owner = category_repo::get_by_id_user(pool, category_id, user_id).await?;
-        if owner.is_none() { return Err(AppError::BadRequest("Invalid category for user".into())); }
-    }
-    let amount = payload.amount.unwrap_or(current.amount);
-    let occurred_on = payload.occurred_on.unwrap_or(current.occurred_on);
+    reject_transfer_leg(&current)?;
+    let mut current_splits = vec![current.clone()];
+    attach_splits(pool, &mut current_splits).await?;
+    let was_split = !current_splits[0].splits.is_empty();
+    let money_changes = payload.amount.is_some() || payload.currency.is_some() || payload.account_id.is_some();
+    let lines = lines_for_update(payload.splits, &current_splits[0].splits, money_changes);
+    let category_id = match lines.as_deref().and_then(|l| l.first()) {
+        Some(first) if payload.category_id.is_some_and(|id| id != first.category_id) => {
+            return Err(AppError::Validation(vec![FieldError::new("category_id", "Must match the first split line, or be left out")]));
+        }
+        Some(first) => first.category_id,
+        None if was_split && lines.is_none() && payload.category_id.is_some_and(|id| Some(id) != current.category_id) => {
+            return Err(AppError::Validation(vec![FieldError::new("category_id", "Change the categories of a split transaction through splits")]));
+        }
+        None => {
+            let category_id = payload.category_id.or(current.category_id).ok_or(AppError::Internal)?;
+            if Some(category_id) != current.category_id {
+                let owner = category_repo::get_by_id_user(pool, category_id, user_id).await?;
+                if owner.is_none() { return Err(AppError::BadRequest("Invalid category for user".into())); }
+            }
+            category_id
+        }
+    };
+    let account_id = payload.account_id.or(current.account_id);
+    // The row's currency already matches its current account
+    let account_currency = match payload.account_id.filter(|a| Some(*a) != current.account_id) {
+        Some(new_account) => Some(money_account_service::ensure_usable(pool, user_id, new_account).await?),
+        None => account_id.map(|_| current.currency.clone()),
+    };
+    let currency = match account_currency {
+        Some(account_currency) => resolve_currency(payload.currency.as_deref(), account_currency)?,
+        None => match payload.currency.as_deref() {
+            Some(c) => currency::parse_code("currency", c)?,
+            None => current.currency.clone(),
+        },
+    };
+    // Rows from before currencies were tracked may carry more decimals; leave them be unless touched
+    let amount = match payload.amount {
+        Some(amount) => currency::normalize_amount("amount", amount, &currency)?,
+        None if currency != current.currency => currency::normalize_amount("amount", current.amount, &currency)?,
+        None => current.amount,
+    };
+    let splits = match lines.as_deref() {
+        Some(lines) if !lines.is_empty() => Some(validate_splits(pool, user_id, lines, amount, &currency).await?),
+        Some(_) => Some(Vec::new()),
+        None => None,
+    };
     let description = payload.description.or(current.description);
-    repo::update(pool, id, user_id, category_id, &amount, occurred_on, &description).await
+    let values = TransactionValues {
+        category_id,
+        account_id,
+        amount,
+        currency: &currency,
+        occurred_on: payload.occurred_on.unwrap_or(current.occurred_on),
+        description: description.as_deref(),
+    };
+    let mut txn = repo::update(pool, id, user_id, &values, splits.as_deref()).await?;
+    attach_splits(pool, std::slice::from_mut(&mut txn)).await?;
+    Ok(txn)
 }
 
 pub async fn delete(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    if let Some(current) = repo::get_by_id_user(pool, id, user_id).await? {
+        reject_transfer_leg(&current)?;
+    }
     let affected = repo::delete(pool, id, user_id).await?;
     if affected == 0 { return Err(AppError::NotFound("Transaction not found".into())); }
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(raw: &str) -> Decimal {
+        Decimal::from_str(raw).unwrap()
+    }
+
+    fn txn() -> Transaction {
+        Transaction {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            category_id: Some(Uuid::new_v4()),
+            account_id: None,
+            transfer_id: None,
+            recurring_id: None,
+            amount: dec("1234.50"),
+            currency: "USD".into(),
+            occurred_on: NaiveDate::from_ymd_opt(2025, 9, 10).unwrap(),
+            description: None,
+            created_at: DateTime::parse_from_rfc3339("2025-09-10T10:00:00.123456Z").unwrap().with_timezone(&Utc),
+            splits: Vec::new(),
+        }
+    }
+
+    fn rejection(result: Result<(SortValue, Uuid), AppError>) -> String {
+        match result {
+            Err(AppError::BadRequest(msg)) => msg,
+            other => panic!("expected the cursor to be rejected, got {:?}", other),
+        }
+    }
+
+    fn json_cursor(sort: &str, order: &str, key: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({ "sort": sort, "order": order, "key": key, "id": Uuid::nil() })).unwrap()
+    }
+
+    #[test]
+    fn cursor_round_trips_for_every_sort() {
+        let last = txn();
+        for order in [SortOrder::Asc, SortOrder::Desc] {
+            let (value, id) = decode_cursor(&encode_cursor(TxnSort::Date, order, &last), TxnSort::Date, order).unwrap();
+            assert!(matches!(value, SortValue::Date(d) if d == last.occurred_on));
+            assert_eq!(id, last.id);
+            let (value, _) = decode_cursor(&encode_cursor(TxnSort::Amount, order, &last), TxnSort::Amount, order).unwrap();
+            assert!(matches!(value, SortValue::Amount(a) if a == last.amount));
+            let (value, _) = decode_cursor(&encode_cursor(TxnSort::CreatedAt, order, &last), TxnSort::CreatedAt, order).unwrap();
+            assert!(matches!(value, SortValue::Time(t) if t == last.created_at));
+        }
+    }
+
+    #[test]
+    fn cursor_is_rejected_under_another_sort_or_order() {
+        let cursor = encode_cursor(TxnSort::Amount, SortOrder::Desc, &txn());
+        let wrong = "Cursor belongs to a different sort order";
+        assert_eq!(rejection(decode_cursor(&cursor, TxnSort::Date, SortOrder::Desc)), wrong);
+        assert_eq!(rejection(decode_cursor(&cursor, TxnSort::Amount, SortOrder::Asc)), wrong);
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        assert_eq!(rejection(decode_cursor("not base64!", TxnSort::Date, SortOrder::Desc)), "Invalid cursor");
+        let not_json = URL_SAFE_NO_PAD.encode(b"plain text");
+        assert_eq!(rejection(decode_cursor(&not_json, TxnSort::Date, SortOrder::Desc)), "Invalid cursor");
+        let bad_key = URL_SAFE_NO_PAD.encode(json_cursor("date", "desc", "yesterday"));
+        assert_eq!(rejection(decode_cursor(&bad_key, TxnSort::Date, SortOrder::Desc)), "Invalid cursor");
+    }
+
+    fn query(value: serde_json::Value) -> TxnQuery {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn transfer_legs_are_left_out_of_amount_filters_and_sort() {
+        assert!(!build_filter(&query(serde_json::json!({}))).unwrap().exclude_transfer_legs);
+        assert!(!build_filter(&query(serde_json::json!({ "sort": "created_at" }))).unwrap().exclude_transfer_legs);
+        assert!(build_filter(&query(serde_json::json!({ "sort": "amount" }))).unwrap().exclude_transfer_legs);
+        assert!(build_filter(&query(serde_json::json!({ "min_amount": "10" }))).unwrap().exclude_transfer_legs);
+        assert!(build_filter(&query(serde_json::json!({ "max_amount": "10" }))).unwrap().exclude_transfer_legs);
+    }
+
+    const GROCERIES: Uuid = Uuid::from_u128(1);
+    const HOUSEHOLD: Uuid = Uuid::from_u128(2);
+    const SALARY: Uuid = Uuid::from_u128(3);
+
+    fn kinds() -> HashMap<Uuid, String> {
+        HashMap::from([(GROCERIES, "expense".into()), (HOUSEHOLD, "expense".into()), (SALARY, "income".into())])
+    }
+
+    fn line(category_id: Uuid, amount: &str) -> SplitLine {
+        SplitLine { category_id, amount: dec(amount), description: None }
+    }
+
+    // Fields with a problem, in the order they were reported
+    fn split_errors(lines: &[SplitLine], amount: &str, currency: &str) -> Vec<String> {
+        match check_splits(lines, &kinds(), dec(amount), currency) {
+            Ok(_) => Vec::new(),
+            Err(AppError::Validation(errors)) => errors.into_iter().map(|e| e.field).collect(),
+            Err(other) => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn split_lines_must_add_up_to_the_amount() {
+        let lines = [line(GROCERIES, "1200.5"), line(HOUSEHOLD, "300")];
+        let values = check_splits(&lines, &kinds(), dec("1500.50"), "USD").unwrap();
+        let amounts: Vec<String> = values.iter().map(|v| v.amount.to_string()).collect();
+        assert_eq!(amounts, ["1200.50", "300.00"]);
+        assert_eq!(split_errors(&lines, "1500.51", "USD"), ["splits"]);
+    }
+
+    #[test]
+    fn split_lines_take_the_sign_of_the_amount() {
+        assert!(split_errors(&[line(GROCERIES, "-20"), line(HOUSEHOLD, "-5")], "-25", "USD").is_empty());
+        assert_eq!(split_errors(&[line(GROCERIES, "30"), line(HOUSEHOLD, "-5")], "25", "USD"), ["splits[1].amount"]);
+        assert_eq!(split_errors(&[line(GROCERIES, "25"), line(HOUSEHOLD, "0")], "25", "USD"), ["splits[1].amount"]);
+    }
+
+    #[test]
+    fn split_lines_are_all_income_or_all_expense_in_known_categories() {
+        assert_eq!(split_errors(&[line(GROCERIES, "20"), line(SALARY, "5")], "25", "USD"), ["splits"]);
+        assert_eq!(split_errors(&[line(GROCERIES, "20"), line(Uuid::from_u128(9), "5")], "25", "USD"), ["splits[1].category_id"]);
+        assert!(split_errors(&[line(GROCERIES, "20"), line(GROCERIES, "5")], "25", "USD").is_empty());
+    }
+
+    #[test]
+    fn split_lines_keep_to_the_line_count_and_currency_decimals() {
+        assert_eq!(split_errors(&[line(GROCERIES, "25")], "25", "USD"), ["splits"]);
+        let many: Vec<SplitLine> = (0..=MAX_SPLITS).map(|_| line(GROCERIES, "1")).collect();
+        assert_eq!(split_errors(&many, &(MAX_SPLITS + 1).to_string(), "USD"), ["splits"]);
+        assert_eq!(split_errors(&[line(GROCERIES, "20000.5"), line(HOUSEHOLD, "4999.5")], "25000", "IDR"), ["splits[0].amount", "splits[1].amount"]);
+    }
+
+    fn stored_lines() -> Vec<TransactionSplit> {
+        [(GROCERIES, "1200.50"), (HOUSEHOLD, "300.00")]
+            .into_iter()
+            .map(|(category_id, amount)| TransactionSplit {
+                id: Uuid::new_v4(),
+                transaction_id: Uuid::nil(),
+                category_id,
+                amount: dec(amount),
+                description: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn updates_recheck_the_current_lines_when_money_changes() {
+        let current = stored_lines();
+        let lines = lines_for_update(None, &current, true).unwrap();
+        assert_eq!(lines.iter().map(|l| l.category_id).collect::<Vec<_>>(), [GROCERIES, HOUSEHOLD]);
+        // A new amount the lines no longer add up to is refused
+        assert_eq!(split_errors(&lines, "1600.00", "USD"), ["splits"]);
+        assert!(split_errors(&lines, "1500.50", "USD").is_empty());
+        // Moving to a currency without decimals is refused while lines have cents
+        assert_eq!(split_errors(&lines, "1500.50", "IDR"), ["splits[0].amount"]);
+    }
+
+    #[test]
+    fn updates_leave_lines_alone_otherwise() {
+        assert!(lines_for_update(None, &stored_lines(), false).is_none());
+        assert!(lines_for_update(None, &[], true).is_none());
+        let replaced = lines_for_update(Some(vec![line(SALARY, "1")]), &stored_lines(), true).unwrap();
+        assert_eq!(replaced[0].category_id, SALARY);
+        assert!(lines_for_update(Some(Vec::new()), &stored_lines(), false).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::transfer::{Transfer, TransferValues};
+use crate::dto::transfer::{CreateTransfer, TransferQuery, UpdateTransfer};
+use crate::repositories::{transfer_repo as repo, category_repo, money_account_repo};
+use crate::services::{currency, money_account_service};
+
+// Checks the combined values and brings amount and fee to the accounts' decimals; accounts
+// are only checked for being usable when `check_accounts`.
+async fn validate(pool: &DbPool, user_id: Uuid, values: &mut TransferValues<'_>, check_accounts: bool) -> Result<(), AppError> {
+    if values.from_account_id == values.to_account_id {
+        return Err(AppError::BadRequest("Source and destination account must differ".into()));
+    }
+    if values.amount <= Decimal::ZERO {
+        return Err(AppError::BadRequest("amount must be positive".into()));
+    }
+    if values.fee < Decimal::ZERO {
+        return Err(AppError::BadRequest("fee must not be negative".into()));
+    }
+    if values.fee > Decimal::ZERO {
+        let category_id = values
+            .fee_category_id
+            .ok_or_else(|| AppError::BadRequest("fee_category_id is required with a fee".into()))?;
+        let category = category_repo::get_by_id_user(pool, category_id, user_id).await?;
+        if category.is_none_or(|c| c.kind != "expense") {
+            return Err(AppError::BadRequest("fee_category_id must be one of your expense categories".into()));
+        }
+    }
+    let currency = if check_accounts {
+        let from_currency = money_account_service::ensure_usable(pool, user_id, values.from_account_id).await?;
+        let to_currency = money_account_service::ensure_usable(pool, user_id, values.to_account_id).await?;
+        if from_currency != to_currency {
+            return Err(AppError::BadRequest("Both accounts must use the same currency".into()));
+        }
+        from_currency
+    } else {
+        let (_, currency) = money_account_repo::find_state(pool, values.from_account_id, user_id).await?.ok_or(AppError::Internal)?;
+        currency
+    };
+    values.amount = currency::normalize_amount("amount", values.amount, &currency)?;
+    values.fee = currency::normalize_amount("fee", values.fee, &currency)?;
+    Ok(())
+}
+
+pub async fn list(pool: &DbPool, user_id: Uuid, q: TransferQuery) -> Result<Vec<Transfer>, AppError> {
+    repo::list(pool, user_id, q.account_id, q.start_date, q.end_date).await
+}
+
+pub async fn get(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<Transfer, AppError> {
+    repo::get_by_id_user(pool, id, user_id).await?.ok_or_else(|| AppError::NotFound("Transfer not found".into()))
+}
+
+pub async fn create(pool: &DbPool, user_id: Uuid, payload: CreateTransfer) -> Result<Transfer, AppError> {
+    let fee = payload.fee.unwrap_or(Decimal::ZERO);
+    let mut values = TransferValues {
+        from_account_id: payload.from_account_id,
+        to_account_id: payload.to_account_id,
+        amount: payload.amount,
+        fee,
+        fee_category_id: payload.fee_category_id.filter(|_| fee > Decimal::ZERO),
+        occurred_on: payload.occurred_on,
+        description: payload.description.as_deref(),
+    };
+    validate(pool, user_id, &mut values, true).await?;
+    repo::insert(pool, Uuid::new_v4(), user_id, &values).await
+}
+
+pub async fn update(pool: &DbPool, user_id: Uuid, id: Uuid, payload: UpdateTransfer) -> Result<Transfer, AppError> {
+    let current = get(pool, user_id, id).await?;
+    let fee = payload.fee.unwrap_or(current.fee);
+    let description = payload.description.or(current.description);
+    let mut values = TransferValues {
+        from_account_id: payload.from_account_id.unwrap_or(current.from_account_id),
+        to_account_id: payload.to_account_id.unwrap_or(current.to_account_id),
+        amount: payload.amount.unwrap_or(current.amount),
+        fee,
+        fee_category_id: payload.fee_category_id.or(current.fee_category_id).filter(|_| fee > Decimal::ZERO),
+        occurred_on: payload.occurred_on.unwrap_or(current.occurred_on),
+        description: description.as_deref(),
+    };
+    // Archived accounts may keep old transfers; only a newly chosen account must be usable
+    let accounts_changed = values.from_account_id != current.from_account_id || values.to_account_id != current.to_account_id;
+    validate(pool, user_id, &mut values, accounts_changed).await?;
+    repo::update(pool, id, user_id, &values).await
+}
+
+pub async fn delete(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let affected = repo::delete(pool, id, user_id).await?;
+    if affected == 0 { return Err(AppError::NotFound("Transfer not found".into())); }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use chrono::{Duration, Utc};
+use data_encoding::BASE32_NOPAD;
+use rand::RngCore;
+use uuid::Uuid;
+
+use crate::auth::{create_purpose_jwt, hash_token, verify_purpose_jwt, Role};
+use crate::client::ClientInfo;
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::dto::auth::{MfaChallenge, MfaVerifyRequest};
+use crate::dto::two_factor::{RecoveryCodes, TotpSetup, TwoFactorStatus};
+use crate::errors::AppError;
+use crate::models::totp::TotpCredential;
+use crate::repositories::{admin_repo, settings_repo, totp_repo, user_repo};
+use crate::services::throttle_service::{self, Action};
+use crate::services::totp;
+
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let raw = BASE32_NOPAD.encode(&bytes).to_lowercase();
+    format!("{}-{}", &raw[..4], &raw[4..])
+}
+
+// Recovery codes are compared case- and dash-insensitively.
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase();
+    hash_token(&normalized)
+}
+
+async fn account_label(pool: &DbPool, role: Role, subject_id: Uuid) -> Result<String, AppError> {
+    Ok(match role {
+        Role::User => user_repo::get_by_id(pool, subject_id).await?.email,
+        Role::Admin => admin_repo::get_by_id(pool, subject_id).await?.email,
+    })
+}
+
+async fn confirmed_credential(pool: &DbPool, role: Role, subject_id: Uuid) -> Result<Option<TotpCredential>, AppError> {
+    let (user_id, admin_id) = role.owner_ids(subject_id);
+    Ok(totp_repo::get_by_owner(pool, user_id, admin_id).await?.filter(|c| c.confirmed_at.is_some()))
+}
+
+fn open_secret(cfg: &AppConfig, cred: &TotpCredential) -> Result<String, AppError> {
+    totp::open_secret(&cfg.totp_encryption_key, &cred.secret).ok_or_else(|| {
+        log::error!("TOTP secret of credential {} cannot be decrypted; was TOTP_ENCRYPTION_KEY changed?", cred.id);
+        AppError::Internal
+    })
+}
+
+// Accepts either a current TOTP code or one unused recovery code.
+async fn check_code(pool: &DbPool, cfg: &AppConfig, cred: &TotpCredential, code: &str) -> Result<bool, AppError> {
+    let code = code.trim();
+    if code.chars().all(|c| c.is_ascii_digit()) {
+        let Some(step) = totp::verify(&open_secret(cfg, cred)?, code, Utc::now().timestamp(), cred.last_used_step) else {
+            return Ok(false);
+        };
+        return Ok(totp_repo::record_step(pool, cred.id, step).await? > 0);
+    }
+    Ok(totp_repo::use_recovery_code(pool, cred.id, &hash_recovery_code(code)).await? > 0)
+}
+
+pub async fn is_enabled(pool: &DbPool, role: Role, subject_id: Uuid) -> Result<bool, AppError> {
+    Ok(confirmed_credential(pool, role, subject_id).await?.is_some())
+}
+
+pub async fn status(pool: &DbPool, role: Role, subject_id: Uuid) -> Result<TwoFactorStatus, AppError> {
+    Ok(TwoFactorStatus { totp_enabled: is_enabled(pool, role, subject_id).await? })
+}
+
+/// Starts enrolment: stores a fresh pending secret and returns it for the authenticator app.
+pub async fn setup_totp(pool: &DbPool, cfg: &AppConfig, role: Role, subject_id: Uuid) -> Result<TotpSetup, AppError> {
+    if is_enabled(pool, role, subject_id).await? {
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".into()));
+    }
+    let issuer = settings_repo::get_value(pool, "app_name").await?.unwrap_or_else(|| "Finance Tracker".into());
+    let label = account_label(pool, role, subject_id).await?;
+    let secret = totp::generate_secret();
+    let (user_id, admin_id) = role.owner_ids(subject_id);
+    totp_repo::upsert_pending(pool, user_id, admin_id, &totp::seal_secret(&cfg.totp_encryption_key, &secret)).await?;
+    let otpauth_uri = totp::otpauth_uri(&issuer, &label, &secret);
+    Ok(TotpSetup { secret, otpauth_uri })
+}
+
+/// Completes enrolment with a first code and hands out the one-time recovery codes.
+pub async fn confirm_totp(pool: &DbPool, cfg: &AppConfig, role: Role, subject_id: Uuid, code: &str) -> Result<RecoveryCodes, AppError> {
+    let (user_id, admin_id) = role.owner_ids(subject_id);
+    let cred = totp_repo::get_by_owner(pool, user_id, admin_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Start TOTP setup first".into()))?;
+    if cred.confirmed_at.is_some() {
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".into()));
+    }
+    let step = totp::verify(&open_secret(cfg, &cred)?, code.trim(), Utc::now().timestamp(), cred.last_used_step)
+        .ok_or_else(|| AppError::BadRequest("Invalid code".into()))?;
+    totp_repo::record_step(pool, cred.id, step).await?;
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+    let hashes: Vec<String> = codes.iter().map(|c| hash_recovery_code(c)).collect();
+    totp_repo::confirm(pool, cred.id, &hashes).await?;
+    Ok(RecoveryCodes { recovery_codes: codes })
+}
+
+pub async fn disable_totp(pool: &DbPool, cfg: &AppConfig, role: Role, subject_id: Uuid, code: &str) -> Result<(), AppError> {
+    let cred = confirmed_credential(pool, role, subject_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Two-factor authentication is not enabled".into()))?;
+    if !check_code(pool, cfg, &cred, code).await? {
+        return Err(AppError::BadRequest("Invalid code".into()));
+    }
+    totp_repo::delete(pool, cred.id).await?;
+    Ok(())
+}
+
+/// Issued instead of tokens when the password was right but a second factor is required.
+pub fn challenge(cfg: &AppConfig, role: Role, subject_id: Uuid) -> Result<MfaChallenge, AppError> {
+    let ttl = Duration::minutes(CHALLENGE_TTL_MINUTES);
+    Ok(MfaChallenge {
+        mfa_required: true,
+        challenge_token: create_purpose_jwt(subject_id, role.mfa_audience(), ttl, cfg)?,
+        expires_in: ttl.num_seconds() as u64,
+    })
+}
+
+/// Validates a challenge token plus second factor and returns the authenticated subject.
+pub async fn complete_challenge(pool: &DbPool, cfg: &AppConfig, client: &ClientInfo, role: Role, payload: &MfaVerifyRequest) -> Result<Uuid, AppError> {
+    let claims = verify_purpose_jwt(&payload.challenge_token, role.mfa_audience(), cfg)?;
+    let account = claims.sub.to_string();
+    throttle_service::check(pool, Action::Mfa, &account, client).await?;
+    let cred = confirmed_credential(pool, role, claims.sub).await?.ok_or(AppError::Unauthorized)?;
+    if !check_code(pool, cfg, &cred, &payload.code).await? {
+        throttle_service::record_failure(pool, Action::Mfa, &account, client).await?;
+        return Err(AppError::Unauthorized);
+    }
+    throttle_service::record_success(pool, Action::Mfa, &account).await?;
+    Ok(claims.sub)
+}
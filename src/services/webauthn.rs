@@ -0,0 +1,252 @@
+use std::io::Cursor;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ciborium::Value;
+use rand::RngCore;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::errors::AppError;
+
+// COSE algorithm identifiers we accept, in order of preference
+pub const ALG_ES256: i64 = -7;
+pub const ALG_EDDSA: i64 = -8;
+pub const ALG_RS256: i64 = -257;
+pub const SUPPORTED_ALGORITHMS: [i64; 3] = [ALG_ES256, ALG_EDDSA, ALG_RS256];
+
+// Authenticator data flags
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+/// Who the credentials are scoped to: `id` is the domain (e.g. `app.example.com`) and
+/// `origins` the exact web origins allowed to run ceremonies for it.
+#[derive(Debug, Clone)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+    pub origins: Vec<String>,
+}
+
+fn invalid(msg: &str) -> AppError {
+    AppError::BadRequest(format!("Invalid passkey response: {}", msg))
+}
+
+pub fn generate_challenge() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Decodes the base64url fields of a credential; padding is tolerated.
+pub fn decode_b64(field: &str, value: &str) -> Result<Vec<u8>, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim().trim_end_matches('='))
+        .map_err(|_| invalid(&format!("{} is not base64url", field)))
+}
+
+pub fn encode_b64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[derive(Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+    origin: String,
+    #[serde(rename = "crossOrigin", default)]
+    cross_origin: bool,
+}
+
+fn check_client_data(raw: &[u8], kind: &str, challenge: &str, rp: &RelyingParty) -> Result<(), AppError> {
+    let data: ClientData = serde_json::from_slice(raw).map_err(|_| invalid("malformed clientDataJSON"))?;
+    if data.kind != kind {
+        return Err(invalid("wrong ceremony type"));
+    }
+    if data.challenge.trim_end_matches('=') != challenge {
+        return Err(invalid("challenge mismatch"));
+    }
+    if data.cross_origin || !rp.origins.iter().any(|o| o == &data.origin) {
+        return Err(invalid("origin not allowed"));
+    }
+    Ok(())
+}
+
+struct AuthenticatorData<'a> {
+    rp_id_hash: &'a [u8],
+    flags: u8,
+    sign_count: u32,
+    // Credential id and COSE public key, present during registration
+    attested: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+fn parse_authenticator_data(data: &[u8]) -> Result<AuthenticatorData<'_>, AppError> {
+    if data.len() < 37 {
+        return Err(invalid("authenticator data too short"));
+    }
+    let flags = data[32];
+    let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+    let attested = if flags & FLAG_ATTESTED_CREDENTIAL != 0 {
+        // aaguid (16) + credential id length (2) + credential id + COSE key
+        let rest = &data[37..];
+        if rest.len() < 18 {
+            return Err(invalid("attested credential data too short"));
+        }
+        let id_len = u16::from_be_bytes([rest[16], rest[17]]) as usize;
+        let rest = &rest[18..];
+        if rest.len() < id_len {
+            return Err(invalid("credential id truncated"));
+        }
+        let (credential_id, rest) = rest.split_at(id_len);
+        // The key is the first CBOR item; extensions may follow it
+        let mut cursor = Cursor::new(rest);
+        let _: Value = ciborium::de::from_reader(&mut cursor).map_err(|_| invalid("malformed credential public key"))?;
+        let key_len = cursor.position() as usize;
+        Some((credential_id.to_vec(), rest[..key_len].to_vec()))
+    } else {
+        None
+    };
+    Ok(AuthenticatorData { rp_id_hash: &data[..32], flags, sign_count, attested })
+}
+
+fn check_rp_and_presence(auth: &AuthenticatorData<'_>, rp: &RelyingParty) -> Result<(), AppError> {
+    if auth.rp_id_hash != Sha256::digest(rp.id.as_bytes()).as_slice() {
+        return Err(invalid("credential belongs to another site"));
+    }
+    if auth.flags & FLAG_USER_PRESENT == 0 {
+        return Err(invalid("user presence not confirmed"));
+    }
+    Ok(())
+}
+
+enum CoseKey {
+    Es256 { point: Vec<u8> },
+    Ed25519 { x: Vec<u8> },
+    Rs256 { n: Vec<u8>, e: Vec<u8> },
+}
+
+fn cose_param(map: &[(Value, Value)], label: i64) -> Option<&Value> {
+    map.iter()
+        .find(|(k, _)| k.as_integer().is_some_and(|i| i128::from(i) == i128::from(label)))
+        .map(|(_, v)| v)
+}
+
+fn cose_int(map: &[(Value, Value)], label: i64) -> Option<i64> {
+    cose_param(map, label)?.as_integer().and_then(|i| i64::try_from(i).ok())
+}
+
+fn cose_bytes(map: &[(Value, Value)], label: i64) -> Option<Vec<u8>> {
+    cose_param(map, label)?.as_bytes().cloned()
+}
+
+// RFC 9053 key parameters: kty (1), alg (3), and per key type crv (-1), x (-2), y (-3)
+// or RSA n (-1), e (-2).
+fn parse_cose_key(bytes: &[u8]) -> Result<(i64, CoseKey), AppError> {
+    let value: Value = ciborium::de::from_reader(bytes).map_err(|_| invalid("malformed credential public key"))?;
+    let map = value.as_map().ok_or_else(|| invalid("credential public key is not a map"))?;
+    let kty = cose_int(map, 1).ok_or_else(|| invalid("credential key has no type"))?;
+    let alg = cose_int(map, 3).ok_or_else(|| invalid("credential key has no algorithm"))?;
+    let key = match (kty, alg) {
+        (2, ALG_ES256) if cose_int(map, -1) == Some(1) => {
+            let x = cose_bytes(map, -2).filter(|x| x.len() == 32).ok_or_else(|| invalid("bad P-256 x coordinate"))?;
+            let y = cose_bytes(map, -3).filter(|y| y.len() == 32).ok_or_else(|| invalid("bad P-256 y coordinate"))?;
+            let mut point = Vec::with_capacity(65);
+            point.push(0x04);
+            point.extend_from_slice(&x);
+            point.extend_from_slice(&y);
+            CoseKey::Es256 { point }
+        }
+        (1, ALG_EDDSA) if cose_int(map, -1) == Some(6) => {
+            let x = cose_bytes(map, -2).filter(|x| x.len() == 32).ok_or_else(|| invalid("bad Ed25519 key"))?;
+            CoseKey::Ed25519 { x }
+        }
+        (3, ALG_RS256) => {
+            let n = cose_bytes(map, -1).ok_or_else(|| invalid("bad RSA modulus"))?;
+            let e = cose_bytes(map, -2).ok_or_else(|| invalid("bad RSA exponent"))?;
+            CoseKey::Rs256 { n, e }
+        }
+        _ => return Err(invalid("unsupported credential algorithm")),
+    };
+    Ok((alg, key))
+}
+
+fn verify_signature(key: &CoseKey, message: &[u8], sig: &[u8]) -> bool {
+    match key {
+        CoseKey::Es256 { point } => UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_ASN1, point).verify(message, sig).is_ok(),
+        CoseKey::Ed25519 { x } => UnparsedPublicKey::new(&signature::ED25519, x).verify(message, sig).is_ok(),
+        CoseKey::Rs256 { n, e } => RsaPublicKeyComponents { n, e }
+            .verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, sig)
+            .is_ok(),
+    }
+}
+
+/// A credential that passed the registration checks, ready to be stored.
+#[derive(Debug)]
+pub struct RegisteredCredential {
+    pub credential_id: Vec<u8>,
+    /// COSE_Key bytes as sent by the authenticator.
+    pub public_key: Vec<u8>,
+    pub alg: i64,
+    pub sign_count: u32,
+}
+
+/// Checks a `navigator.credentials.create()` response against the challenge issued for it.
+/// Attestation statements are not evaluated (we request `attestation: "none"`), so any
+/// authenticator model is accepted.
+pub fn verify_registration(
+    rp: &RelyingParty,
+    challenge: &str,
+    client_data_json: &[u8],
+    attestation_object: &[u8],
+) -> Result<RegisteredCredential, AppError> {
+    check_client_data(client_data_json, "webauthn.create", challenge, rp)?;
+    let attestation: Value = ciborium::de::from_reader(attestation_object).map_err(|_| invalid("malformed attestationObject"))?;
+    let auth_data = attestation
+        .as_map()
+        .and_then(|m| m.iter().find(|(k, _)| k.as_text() == Some("authData")))
+        .and_then(|(_, v)| v.as_bytes())
+        .ok_or_else(|| invalid("attestationObject has no authData"))?;
+    let auth = parse_authenticator_data(auth_data)?;
+    check_rp_and_presence(&auth, rp)?;
+    let (credential_id, public_key) = auth.attested.ok_or_else(|| invalid("no credential in authenticator data"))?;
+    let (alg, _) = parse_cose_key(&public_key)?;
+    Ok(RegisteredCredential { credential_id, public_key, alg, sign_count: auth.sign_count })
+}
+
+/// Outcome of a valid assertion.
+#[derive(Debug)]
+pub struct VerifiedAssertion {
+    pub sign_count: u32,
+    /// The authenticator checked a PIN or biometric, not just presence.
+    pub user_verified: bool,
+}
+
+/// Checks a `navigator.credentials.get()` response: challenge, origin, site, signature and
+/// that the signature counter moved forward (a counter that does not suggests a cloned
+/// authenticator). Authenticators that do not count always report 0.
+pub fn verify_assertion(
+    rp: &RelyingParty,
+    challenge: &str,
+    public_key: &[u8],
+    stored_sign_count: u32,
+    client_data_json: &[u8],
+    authenticator_data: &[u8],
+    sig: &[u8],
+) -> Result<VerifiedAssertion, AppError> {
+    check_client_data(client_data_json, "webauthn.get", challenge, rp)?;
+    let auth = parse_authenticator_data(authenticator_data)?;
+    check_rp_and_presence(&auth, rp)?;
+    let (_, key) = parse_cose_key(public_key)?;
+    let mut message = authenticator_data.to_vec();
+    message.extend_from_slice(&Sha256::digest(client_data_json));
+    if !verify_signature(&key, &message, sig) {
+        return Err(invalid("signature does not match"));
+    }
+    if (auth.sign_count != 0 || stored_sign_count != 0) && auth.sign_count <= stored_sign_count {
+        return Err(invalid("signature counter went backwards; the authenticator may have been cloned"));
+    }
+    Ok(VerifiedAssertion { sign_count: auth.sign_count, user_verified: auth.flags & FLAG_USER_VERIFIED != 0 })
+}
@@ -0,0 +1,307 @@
+//! Passkey ceremonies driven by a software authenticator: an ES256 or EdDSA key pair that
+//! builds `clientDataJSON`, `attestationObject`, `authenticatorData` and signatures the way
+//! a browser and security key would.
+//!
+//! The test that goes through the database needs `TEST_DATABASE_URL` pointing at a
+//! disposable Postgres database; it is skipped without one.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ciborium::Value;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, Ed25519KeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use finance_backend::client::ClientInfo;
+use finance_backend::db::{self, DbPool};
+use finance_backend::dto::passkey::{FinishLogin, FinishRegistration};
+use finance_backend::errors::AppError;
+use finance_backend::repositories::passkey_repo;
+use finance_backend::services::passkey_service;
+use finance_backend::services::webauthn::{self, RelyingParty, ALG_EDDSA, ALG_ES256};
+
+const RP_ID: &str = "localhost";
+const ORIGIN: &str = "https://localhost:8443";
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+enum Key {
+    Es256(EcdsaKeyPair),
+    EdDsa(Ed25519KeyPair),
+}
+
+struct SoftAuthenticator {
+    key: Key,
+    credential_id: Vec<u8>,
+    sign_count: u32,
+    rng: SystemRandom,
+}
+
+impl SoftAuthenticator {
+    fn new(alg: i64) -> Self {
+        let rng = SystemRandom::new();
+        let key = match alg {
+            ALG_ES256 => {
+                let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+                Key::Es256(EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng).unwrap())
+            }
+            ALG_EDDSA => {
+                let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+                Key::EdDsa(Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap())
+            }
+            other => panic!("unsupported algorithm {}", other),
+        };
+        Self { key, credential_id: Uuid::new_v4().as_bytes().to_vec(), sign_count: 0, rng }
+    }
+
+    fn credential_id(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.credential_id)
+    }
+
+    // RFC 9053 COSE_Key of the public key
+    fn cose_key(&self) -> Vec<u8> {
+        let int = |i: i64| Value::Integer(i.into());
+        let map = match &self.key {
+            Key::Es256(pair) => {
+                let point = pair.public_key().as_ref();
+                vec![
+                    (int(1), int(2)),
+                    (int(3), int(ALG_ES256)),
+                    (int(-1), int(1)),
+                    (int(-2), Value::Bytes(point[1..33].to_vec())),
+                    (int(-3), Value::Bytes(point[33..65].to_vec())),
+                ]
+            }
+            Key::EdDsa(pair) => vec![
+                (int(1), int(1)),
+                (int(3), int(ALG_EDDSA)),
+                (int(-1), int(6)),
+                (int(-2), Value::Bytes(pair.public_key().as_ref().to_vec())),
+            ],
+        };
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(&Value::Map(map), &mut out).unwrap();
+        out
+    }
+
+    fn authenticator_data(&self, flags: u8, attested: bool) -> Vec<u8> {
+        let mut data = Sha256::digest(RP_ID.as_bytes()).to_vec();
+        data.push(flags | if attested { FLAG_ATTESTED_CREDENTIAL } else { 0 });
+        data.extend_from_slice(&self.sign_count.to_be_bytes());
+        if attested {
+            data.extend_from_slice(&[0u8; 16]);
+            data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            data.extend_from_slice(&self.credential_id);
+            data.extend_from_slice(&self.cose_key());
+        }
+        data
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match &self.key {
+            Key::Es256(pair) => pair.sign(&self.rng, message).unwrap().as_ref().to_vec(),
+            Key::EdDsa(pair) => pair.sign(message).as_ref().to_vec(),
+        }
+    }
+
+    /// `clientDataJSON` and `attestationObject` for `navigator.credentials.create()`.
+    fn register(&self, challenge: &str) -> (Vec<u8>, Vec<u8>) {
+        let attestation = Value::Map(vec![
+            (Value::Text("fmt".into()), Value::Text("none".into())),
+            (Value::Text("attStmt".into()), Value::Map(Vec::new())),
+            (Value::Text("authData".into()), Value::Bytes(self.authenticator_data(FLAG_USER_PRESENT, true))),
+        ]);
+        let mut attestation_object = Vec::new();
+        ciborium::ser::into_writer(&attestation, &mut attestation_object).unwrap();
+        (client_data("webauthn.create", challenge), attestation_object)
+    }
+
+    /// `clientDataJSON`, `authenticatorData` and signature for `navigator.credentials.get()`,
+    /// bumping the signature counter first as real authenticators do.
+    fn assert(&mut self, challenge: &str, flags: u8) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        self.sign_count += 1;
+        let client_data = client_data("webauthn.get", challenge);
+        let authenticator_data = self.authenticator_data(flags, false);
+        let mut message = authenticator_data.clone();
+        message.extend_from_slice(&Sha256::digest(&client_data));
+        let signature = self.sign(&message);
+        (client_data, authenticator_data, signature)
+    }
+}
+
+fn client_data(kind: &str, challenge: &str) -> Vec<u8> {
+    serde_json::to_vec(&json!({ "type": kind, "challenge": challenge, "origin": ORIGIN, "crossOrigin": false })).unwrap()
+}
+
+fn relying_party() -> RelyingParty {
+    RelyingParty { id: RP_ID.into(), name: "Finance Tracker".into(), origins: vec![ORIGIN.into()] }
+}
+
+fn rejection(result: Result<impl std::fmt::Debug, AppError>) -> String {
+    match result {
+        Err(AppError::BadRequest(msg)) => msg,
+        other => panic!("expected the response to be rejected, got {:?}", other),
+    }
+}
+
+#[test]
+fn registers_es256_and_eddsa_credentials() {
+    for alg in [ALG_ES256, ALG_EDDSA] {
+        let authenticator = SoftAuthenticator::new(alg);
+        let challenge = webauthn::generate_challenge();
+        let (client_data, attestation) = authenticator.register(&challenge);
+        let registered = webauthn::verify_registration(&relying_party(), &challenge, &client_data, &attestation).unwrap();
+        assert_eq!(registered.alg, alg);
+        assert_eq!(registered.credential_id, authenticator.credential_id);
+        assert_eq!(registered.public_key, authenticator.cose_key());
+        assert_eq!(registered.sign_count, 0);
+    }
+}
+
+#[test]
+fn verifies_assertions() {
+    for alg in [ALG_ES256, ALG_EDDSA] {
+        let mut authenticator = SoftAuthenticator::new(alg);
+        let rp = relying_party();
+        let challenge = webauthn::generate_challenge();
+        let (client_data, attestation) = authenticator.register(&challenge);
+        let registered = webauthn::verify_registration(&rp, &challenge, &client_data, &attestation).unwrap();
+
+        let challenge = webauthn::generate_challenge();
+        let (client_data, auth_data, signature) = authenticator.assert(&challenge, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+        let verified = webauthn::verify_assertion(&rp, &challenge, &registered.public_key, 0, &client_data, &auth_data, &signature).unwrap();
+        assert_eq!(verified.sign_count, 1);
+        assert!(verified.user_verified);
+
+        let challenge = webauthn::generate_challenge();
+        let (client_data, auth_data, mut signature) = authenticator.assert(&challenge, FLAG_USER_PRESENT);
+        let verified = webauthn::verify_assertion(&rp, &challenge, &registered.public_key, 1, &client_data, &auth_data, &signature).unwrap();
+        assert!(!verified.user_verified);
+
+        let last = signature.len() - 1;
+        signature[last] ^= 0x01;
+        let tampered = webauthn::verify_assertion(&rp, &challenge, &registered.public_key, 1, &client_data, &auth_data, &signature);
+        assert!(rejection(tampered).contains("signature does not match"));
+    }
+}
+
+#[test]
+fn rejects_a_response_to_another_challenge() {
+    let mut authenticator = SoftAuthenticator::new(ALG_ES256);
+    let rp = relying_party();
+    let issued = webauthn::generate_challenge();
+    let (client_data, attestation) = authenticator.register(&webauthn::generate_challenge());
+    let registration = webauthn::verify_registration(&rp, &issued, &client_data, &attestation);
+    assert!(rejection(registration).contains("challenge mismatch"));
+
+    let (client_data, attestation) = authenticator.register(&issued);
+    let registered = webauthn::verify_registration(&rp, &issued, &client_data, &attestation).unwrap();
+    let issued = webauthn::generate_challenge();
+    let (client_data, auth_data, signature) = authenticator.assert(&webauthn::generate_challenge(), FLAG_USER_PRESENT);
+    let assertion = webauthn::verify_assertion(&rp, &issued, &registered.public_key, 0, &client_data, &auth_data, &signature);
+    assert!(rejection(assertion).contains("challenge mismatch"));
+}
+
+#[test]
+fn rejects_a_signature_counter_that_does_not_move_forward() {
+    let mut authenticator = SoftAuthenticator::new(ALG_ES256);
+    let rp = relying_party();
+    let challenge = webauthn::generate_challenge();
+    let (client_data, attestation) = authenticator.register(&challenge);
+    let registered = webauthn::verify_registration(&rp, &challenge, &client_data, &attestation).unwrap();
+    authenticator.sign_count = 4;
+    let (client_data, auth_data, signature) = authenticator.assert(&challenge, FLAG_USER_PRESENT);
+    for stored in [5, 6] {
+        let assertion = webauthn::verify_assertion(&rp, &challenge, &registered.public_key, stored, &client_data, &auth_data, &signature);
+        assert!(rejection(assertion).contains("counter went backwards"));
+    }
+}
+
+async fn test_pool() -> Option<DbPool> {
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL not set; skipping database test");
+        return None;
+    };
+    let pool = db::init_pool(&url).await.expect("cannot connect to TEST_DATABASE_URL");
+    db::run_migrations(&pool).await.expect("migrations failed");
+    for (key, value) in [("webauthn_rp_id", RP_ID), ("webauthn_origins", ORIGIN)] {
+        sqlx::query("INSERT INTO app_settings (key, value) VALUES ($1,$2) ON CONFLICT (key) DO UPDATE SET value=EXCLUDED.value")
+            .bind(key)
+            .bind(value)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+    Some(pool)
+}
+
+async fn sign_in(pool: &DbPool, authenticator: &mut SoftAuthenticator) -> Result<Uuid, AppError> {
+    let start = passkey_service::start_login(pool).await?;
+    let (client_data, auth_data, signature) = authenticator.assert(&start.public_key.challenge, FLAG_USER_PRESENT);
+    let payload: FinishLogin = serde_json::from_value(json!({
+        "challenge_id": start.challenge_id,
+        "credential": {
+            "id": authenticator.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": URL_SAFE_NO_PAD.encode(client_data),
+                "authenticatorData": URL_SAFE_NO_PAD.encode(auth_data),
+                "signature": URL_SAFE_NO_PAD.encode(signature),
+            }
+        }
+    }))
+    .unwrap();
+    passkey_service::authenticate(pool, &payload).await.map(|(user, _)| user.id)
+}
+
+#[tokio::test]
+async fn record_use_rejects_a_sign_count_regression() {
+    let Some(pool) = test_pool().await else { return };
+    let user_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO users (id, name, email, password_hash, auth_provider, is_verified) VALUES ($1, 'Passkey Test', $2, '', 'local', true)")
+        .bind(user_id)
+        .bind(format!("passkey-{}@example.com", user_id))
+        .execute(&pool)
+        .await
+        .unwrap();
+    let client = ClientInfo { ip: Some("127.0.0.1".into()), user_agent: Some("integration test".into()) };
+
+    let mut authenticator = SoftAuthenticator::new(ALG_ES256);
+    let start = passkey_service::start_registration(&pool, user_id).await.unwrap();
+    let (client_data, attestation) = authenticator.register(&start.public_key.challenge);
+    let payload: FinishRegistration = serde_json::from_value(json!({
+        "challenge_id": start.challenge_id,
+        "name": "Software key",
+        "credential": {
+            "id": authenticator.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": URL_SAFE_NO_PAD.encode(client_data),
+                "attestationObject": URL_SAFE_NO_PAD.encode(attestation),
+            }
+        }
+    }))
+    .unwrap();
+    passkey_service::finish_registration(&pool, &client, user_id, payload).await.unwrap();
+
+    assert_eq!(sign_in(&pool, &mut authenticator).await.unwrap(), user_id);
+    assert_eq!(sign_in(&pool, &mut authenticator).await.unwrap(), user_id);
+    let passkey = passkey_repo::find_by_credential_id(&pool, &authenticator.credential_id()).await.unwrap().unwrap();
+    assert_eq!(passkey.sign_count, 2);
+
+    // A second sign-in racing on the counter value read before the first one stored its own
+    assert_eq!(passkey_repo::record_use(&pool, passkey.id, 1, 2).await.unwrap(), 0);
+    assert_eq!(passkey_repo::record_use(&pool, passkey.id, 2, 3).await.unwrap(), 1);
+
+    // A clone of the authenticator still counting from before is turned away
+    authenticator.sign_count = 1;
+    assert!(matches!(sign_in(&pool, &mut authenticator).await, Err(AppError::Unauthorized)));
+    let passkey = passkey_repo::find_by_credential_id(&pool, &authenticator.credential_id()).await.unwrap().unwrap();
+    assert_eq!(passkey.sign_count, 3);
+
+    sqlx::query("DELETE FROM users WHERE id=$1").bind(user_id).execute(&pool).await.unwrap();
+}